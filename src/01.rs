@@ -1,38 +1,141 @@
-use anyhow::Error;
+use crate::{
+    solution::{Example, Solution},
+    trace::{self, Level},
+};
+use advent_of_code_2022::{error, rng::Rng, stream, SpanContext};
+use anyhow::{anyhow, Error};
+use std::io::BufRead;
 
-fn main() -> Result<(), Error> {
-    let input = include_str!("01.txt");
-    println!(
-        "Part 1: {}",
-        calories_carried_by_elf_with_most_calories(input)?,
-    );
-    println!(
-        "Part 1: {}",
-        calories_carried_by_elves_with_most_calories(input, 3)?,
-    );
-    Ok(())
+#[derive(Debug, Clone)]
+pub struct CalorieCounting;
+
+impl Solution for CalorieCounting {
+    type Input = Vec<i64>;
+    type Answer1 = i64;
+    type Answer2 = i64;
+
+    fn parse(&self, input: &str) -> Result<Vec<i64>, Error> {
+        calories_carried_by_elves(input)
+    }
+
+    fn part1(&self, elves: &Vec<i64>) -> Result<i64, Error> {
+        calories_carried_by_elf_with_most_calories(elves)
+    }
+
+    fn part2(&self, elves: &Vec<i64>) -> Result<i64, Error> {
+        calories_carried_by_elves_with_most_calories(elves, 3)
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+
+    fn reference1(&self, input: &str) -> Result<i64, Error> {
+        naive_calories_carried_by_top_elves(input, 1)
+    }
+
+    fn reference2(&self, input: &str) -> Result<i64, Error> {
+        naive_calories_carried_by_top_elves(input, 3)
+    }
+
+    fn stream(&self, input: &mut dyn BufRead) -> Result<Option<(i64, i64)>, Error> {
+        let mut top = Vec::new();
+        let mut elf = 0;
+        stream::for_each_line(input, |line| {
+            if line.is_empty() {
+                keep_top(&mut top, std::mem::take(&mut elf), 3);
+            } else {
+                elf = total([elf, line.parse().at(line)?])?;
+            }
+            Ok(())
+        })?;
+        keep_top(&mut top, elf, 3);
+        Ok(Some((top.first().copied().unwrap_or(0), total(top)?)))
+    }
 }
 
-fn calories_carried_by_elf_with_most_calories(input: &str) -> Result<i64, Error> {
-    calories_carried_by_elves_with_most_calories(input, 1)
+// Slots the elf into the running top `n`, which is kept biggest first.
+fn keep_top(top: &mut Vec<i64>, elf: i64, n: usize) {
+    let i = top
+        .iter()
+        .position(|&other| elf > other)
+        .unwrap_or(top.len());
+    top.insert(i, elf);
+    top.truncate(n);
 }
 
-fn calories_carried_by_elves_with_most_calories(input: &str, n: usize) -> Result<i64, Error> {
-    let mut elves = Vec::new();
-    for elf in input.split("\n\n") {
-        let mut calories = 0;
-        for line in elf.lines() {
-            calories += line.parse::<i64>()?;
+fn calories_carried_by_elves(input: &str) -> Result<Vec<i64>, Error> {
+    error::collect(input.split("\n\n").enumerate().map(|(i, elf)| {
+        let calories: Vec<i64> = error::collect(elf.lines().map(|line| line.parse().at(line)))?;
+        let total = total(calories)?;
+        trace::event(
+            Level::Steps,
+            "elf",
+            &[("elf", &(i + 1)), ("calories", &total)],
+        );
+        Ok(total)
+    }))
+}
+
+fn calories_carried_by_elf_with_most_calories(elves: &[i64]) -> Result<i64, Error> {
+    calories_carried_by_elves_with_most_calories(elves, 1)
+}
+
+fn calories_carried_by_elves_with_most_calories(elves: &[i64], n: usize) -> Result<i64, Error> {
+    let mut elves = elves.to_vec();
+    elves.sort();
+    let top: Vec<i64> = elves.into_iter().rev().take(n).collect();
+    if trace::enabled(Level::Summary) {
+        let calories: Vec<String> = top.iter().map(i64::to_string).collect();
+        trace::event(Level::Summary, "top", &[("calories", &calories.join(","))]);
+    }
+    total(top)
+}
+
+fn total(calories: impl IntoIterator<Item = i64>) -> Result<i64, Error> {
+    calories
+        .into_iter()
+        .try_fold(0, i64::checked_add)
+        .ok_or_else(|| anyhow!("too many calories to add up"))
+}
+
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let elves: Vec<String> = (0..size.max(1))
+        .map(|_| {
+            let items: Vec<String> = (0..rng.range(1..=6))
+                .map(|_| rng.range(1000..=60000).to_string())
+                .collect();
+            items.join("\n")
+        })
+        .collect();
+    elves.join("\n\n") + "\n"
+}
+
+// Totals each elf line by line and picks out the biggest one at a time, without sorting.
+fn naive_calories_carried_by_top_elves(input: &str, n: usize) -> Result<i64, Error> {
+    let mut elves = vec![0];
+    for line in input.lines() {
+        if line.is_empty() {
+            elves.push(0);
+        } else if let Some(elf) = elves.last_mut() {
+            *elf += line.parse::<i64>()?;
         }
-        elves.push(calories)
     }
-    elves.sort();
-    Ok(elves.into_iter().rev().take(n).sum())
+    let mut total = 0;
+    for _ in 0..n {
+        let (i, calories) = elves
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by_key(|&(_, calories)| calories)
+            .ok_or_else(|| anyhow!("fewer than {} elves", n))?;
+        total += calories;
+        elves.remove(i);
+    }
+    Ok(total)
 }
 
-#[test]
-fn example_1() {
-    let input = "1000
+pub const EXAMPLE: &str = "1000
 2000
 3000
 
@@ -47,31 +150,33 @@ fn example_1() {
 
 10000
 ";
+
+pub const EXAMPLES: [Example; 1] = [Example {
+    name: "example",
+    input: EXAMPLE,
+    parameters: &[],
+    part1: Some("24000"),
+    part2: Some("45000"),
+}];
+
+#[test]
+fn example_1() {
+    let input = EXAMPLE;
     assert_eq!(
-        calories_carried_by_elf_with_most_calories(input).unwrap(),
+        calories_carried_by_elf_with_most_calories(&calories_carried_by_elves(input).unwrap())
+            .unwrap(),
         24000
     );
+    assert!(calories_carried_by_elves("9223372036854775807\n7").is_err());
 }
 
 #[test]
 fn example_2() {
-    let input = "1000
-2000
-3000
-
-4000
-
-5000
-6000
-
-7000
-8000
-9000
-
-10000
-";
+    let input = EXAMPLE;
     assert_eq!(
-        calories_carried_by_elves_with_most_calories(input, 3).unwrap(),
+        calories_carried_by_elves_with_most_calories(&calories_carried_by_elves(input).unwrap(), 3)
+            .unwrap(),
         45000
     );
+    assert!(calories_carried_by_elves_with_most_calories(&[i64::MAX, 1], 3).is_err());
 }
@@ -1,18 +1,64 @@
+use crate::{
+    solution::{Example, Solution},
+    trace::{self, Level},
+};
+use advent_of_code_2022::{error, rng::Rng, stream, SpanContext, Tokens};
 use anyhow::{anyhow, Error};
-use std::str::FromStr;
+use std::{io::BufRead, str::FromStr};
 
-fn main() -> Result<(), Error> {
-    let input = include_str!("02.txt");
-    println!("Part 1: {}", total_score(input, false)?);
-    println!("Part 2: {}", total_score(input, true)?);
-    Ok(())
+#[derive(Debug, Clone)]
+pub struct RockPaperScissors;
+
+impl Solution for RockPaperScissors {
+    type Input = Vec<Round>;
+    type Answer1 = i64;
+    type Answer2 = i64;
+
+    fn parse(&self, input: &str) -> Result<Vec<Round>, Error> {
+        error::collect(input.lines().map(|line| line.parse()))
+    }
+
+    fn part1(&self, rounds: &Vec<Round>) -> Result<i64, Error> {
+        Ok(total_score(rounds, false))
+    }
+
+    fn part2(&self, rounds: &Vec<Round>) -> Result<i64, Error> {
+        Ok(total_score(rounds, true))
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+
+    fn reference1(&self, input: &str) -> Result<i64, Error> {
+        naive_total_score(input, false)
+    }
+
+    fn reference2(&self, input: &str) -> Result<i64, Error> {
+        naive_total_score(input, true)
+    }
+
+    fn stream(&self, input: &mut dyn BufRead) -> Result<Option<(i64, i64)>, Error> {
+        let games = [false, true].map(|second_value_is_result| Game {
+            second_value_is_result,
+        });
+        let mut scores = [0, 0];
+        stream::for_each_line(input, |line| {
+            let round = line.parse()?;
+            for (score, game) in scores.iter_mut().zip(&games) {
+                *score += game.round_score(&round);
+            }
+            Ok(())
+        })?;
+        Ok(Some((scores[0], scores[1])))
+    }
 }
 
-fn total_score(input: &str, second_value_is_result: bool) -> Result<i64, Error> {
+fn total_score(rounds: &[Round], second_value_is_result: bool) -> i64 {
     let game = Game {
         second_value_is_result,
     };
-    game.total_score(input)
+    game.total_score(rounds)
 }
 
 #[derive(Debug)]
@@ -21,13 +67,20 @@ struct Game {
 }
 
 #[derive(Debug)]
+pub struct Round {
+    other: Shape,
+    me: Shape,
+    win_loss_draw: WinLossDraw,
+}
+
+#[derive(Debug, Clone, Copy)]
 enum Shape {
     Rock,
     Paper,
     Scissors,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum WinLossDraw {
     Win,
     Loss,
@@ -35,35 +88,63 @@ enum WinLossDraw {
 }
 
 impl Game {
-    fn total_score(&self, input: &str) -> Result<i64, Error> {
+    fn total_score(&self, rounds: &[Round]) -> i64 {
         let mut score = 0;
-        for line in input.lines() {
-            score += self.round_score(line)?;
+        for (i, round) in rounds.iter().enumerate() {
+            let round_score = self.round_score(round);
+            score += round_score;
+            if trace::enabled(Level::Steps) {
+                let (win_loss_draw, me) = self.play(round);
+                trace::event(
+                    Level::Steps,
+                    "round",
+                    &[
+                        ("round", &(i + 1)),
+                        ("other", &format!("{:?}", round.other)),
+                        ("me", &format!("{:?}", me)),
+                        ("result", &format!("{:?}", win_loss_draw)),
+                        ("score", &round_score),
+                        ("total", &score),
+                    ],
+                );
+            }
         }
-        Ok(score)
-    }
-
-    fn round_score(&self, input: &str) -> Result<i64, Error> {
-        let mut iter = input.split(" ");
-        let other = iter
-            .next()
-            .ok_or_else(|| anyhow!("invalid round: {}", input))
-            .and_then(|s| s.parse::<Shape>())?;
-        let (win_loss_draw, me) = if self.second_value_is_result {
-            let win_loss_draw = iter
-                .next()
-                .ok_or_else(|| anyhow!("invalid round: {}", input))
-                .and_then(|s| s.parse::<WinLossDraw>())?;
-            let me = win_loss_draw.me(&other);
-            (win_loss_draw, me)
+        trace::event(
+            Level::Summary,
+            "total",
+            &[("rounds", &rounds.len()), ("score", &score)],
+        );
+        score
+    }
+
+    fn round_score(&self, round: &Round) -> i64 {
+        let (win_loss_draw, me) = self.play(round);
+        me.score() + win_loss_draw.score()
+    }
+
+    // How the round turns out and what I play.
+    fn play(&self, round: &Round) -> (WinLossDraw, Shape) {
+        if self.second_value_is_result {
+            (round.win_loss_draw, round.win_loss_draw.me(&round.other))
         } else {
-            let me = iter
-                .next()
-                .ok_or_else(|| anyhow!("invalid round: {}", input))
-                .and_then(|s| s.parse::<Shape>())?;
-            (me.win_loss_draw(&other), me)
-        };
-        Ok(me.score() + win_loss_draw.score())
+            (round.me.win_loss_draw(&round.other), round.me)
+        }
+    }
+}
+
+impl FromStr for Round {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Tokens::new(s);
+        let other = tokens.parse()?;
+        let second = tokens.next_str()?;
+        tokens.finish()?;
+        Ok(Round {
+            other,
+            me: second.parse().at(second)?,
+            win_loss_draw: second.parse().at(second)?,
+        })
     }
 }
 
@@ -161,15 +242,69 @@ impl FromStr for WinLossDraw {
     }
 }
 
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut input = String::new();
+    for _ in 0..size.max(1) {
+        input.push(*rng.choose(&['A', 'B', 'C']).unwrap_or(&'A'));
+        input.push(' ');
+        input.push(*rng.choose(&['X', 'Y', 'Z']).unwrap_or(&'X'));
+        input.push('\n');
+    }
+    input
+}
+
+// Every possible round written out as (shape score, outcome score).
+fn naive_total_score(input: &str, second_value_is_result: bool) -> Result<i64, Error> {
+    let mut total = 0;
+    for line in input.lines() {
+        let (shape, outcome) = match (line, second_value_is_result) {
+            ("A X", false) => (1, 3),
+            ("A Y", false) => (2, 6),
+            ("A Z", false) => (3, 0),
+            ("B X", false) => (1, 0),
+            ("B Y", false) => (2, 3),
+            ("B Z", false) => (3, 6),
+            ("C X", false) => (1, 6),
+            ("C Y", false) => (2, 0),
+            ("C Z", false) => (3, 3),
+            ("A X", true) => (3, 0),
+            ("A Y", true) => (1, 3),
+            ("A Z", true) => (2, 6),
+            ("B X", true) => (1, 0),
+            ("B Y", true) => (2, 3),
+            ("B Z", true) => (3, 6),
+            ("C X", true) => (2, 0),
+            ("C Y", true) => (3, 3),
+            ("C Z", true) => (1, 6),
+            _ => return Err(anyhow!("unexpected round: {}", line)),
+        };
+        total += shape + outcome;
+    }
+    Ok(total)
+}
+
+pub const EXAMPLE: &str = "A Y
+B X
+C Z";
+
+pub const EXAMPLES: [Example; 1] = [Example {
+    name: "example",
+    input: EXAMPLE,
+    parameters: &[],
+    part1: Some("15"),
+    part2: Some("12"),
+}];
+
 #[test]
 fn part_1() {
     let game = Game {
         second_value_is_result: false,
     };
-    assert_eq!(game.round_score("A Y").unwrap(), 8);
-    assert_eq!(game.round_score("B X").unwrap(), 1);
-    assert_eq!(game.round_score("C Z").unwrap(), 6);
-    assert_eq!(game.total_score("A Y\nB X\nC Z").unwrap(), 15);
+    assert_eq!(game.round_score(&"A Y".parse().unwrap()), 8);
+    assert_eq!(game.round_score(&"B X".parse().unwrap()), 1);
+    assert_eq!(game.round_score(&"C Z".parse().unwrap()), 6);
+    let rounds = RockPaperScissors.parse(EXAMPLE).unwrap();
+    assert_eq!(game.total_score(&rounds), 15);
 }
 
 #[test]
@@ -177,8 +312,9 @@ fn part_2() {
     let game = Game {
         second_value_is_result: true,
     };
-    assert_eq!(game.round_score("A Y").unwrap(), 4);
-    assert_eq!(game.round_score("B X").unwrap(), 1);
-    assert_eq!(game.round_score("C Z").unwrap(), 7);
-    assert_eq!(game.total_score("A Y\nB X\nC Z").unwrap(), 12);
+    assert_eq!(game.round_score(&"A Y".parse().unwrap()), 4);
+    assert_eq!(game.round_score(&"B X".parse().unwrap()), 1);
+    assert_eq!(game.round_score(&"C Z".parse().unwrap()), 7);
+    let rounds = RockPaperScissors.parse(EXAMPLE).unwrap();
+    assert_eq!(game.total_score(&rounds), 12);
 }
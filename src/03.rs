@@ -1,11 +1,81 @@
+use crate::{
+    solution::{Example, Solution},
+    trace::{self, Level},
+};
+use advent_of_code_2022::{error, rng::Rng, stream};
 use anyhow::{anyhow, Error};
-use std::collections::HashSet;
+use std::{collections::HashSet, io::BufRead};
 
-fn main() -> Result<(), Error> {
-    let input = include_str!("03.txt");
-    println!("Part 1: {}", sum_of_priorities_of_shared_letters(input)?);
-    println!("Part 2: {}", sum_of_priorities_of_badges(input)?);
-    Ok(())
+#[derive(Debug, Clone)]
+pub struct RucksackReorganization;
+
+impl Solution for RucksackReorganization {
+    type Input = Vec<String>;
+    type Answer1 = i64;
+    type Answer2 = i64;
+
+    fn parse(&self, input: &str) -> Result<Vec<String>, Error> {
+        error::collect(input.lines().map(|line| rucksack(line).map(str::to_string)))
+    }
+
+    fn part1(&self, rucksacks: &Vec<String>) -> Result<i64, Error> {
+        sum_of_priorities_of_shared_letters(rucksacks)
+    }
+
+    fn part2(&self, rucksacks: &Vec<String>) -> Result<i64, Error> {
+        sum_of_priorities_of_badges(rucksacks)
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+
+    fn reference1(&self, input: &str) -> Result<i64, Error> {
+        naive_sum_of_priorities(
+            input
+                .lines()
+                .map(|line| {
+                    let (front, back) = line.split_at(line.len() / 2);
+                    vec![front, back]
+                })
+                .collect(),
+        )
+    }
+
+    fn reference2(&self, input: &str) -> Result<i64, Error> {
+        let lines: Vec<&str> = input.lines().collect();
+        if !lines.len().is_multiple_of(3) {
+            return Err(anyhow!("rucksacks don't split into groups of three"));
+        }
+        naive_sum_of_priorities(lines.chunks(3).map(|group| group.to_vec()).collect())
+    }
+
+    // Only the current group of three is kept.
+    fn stream(&self, input: &mut dyn BufRead) -> Result<Option<(i64, i64)>, Error> {
+        let mut shared_letters = 0;
+        let mut badges = 0;
+        let mut group = Vec::new();
+        stream::for_each_line(input, |line| {
+            shared_letters += priority(shared_letter(rucksack(line)?)?)?;
+            group.push(line.to_string());
+            if group.len() == 3 {
+                badges += priority(badge(group.drain(..))?)?;
+            }
+            Ok(())
+        })?;
+        if !group.is_empty() {
+            badges += priority(badge(group)?)?;
+        }
+        Ok(Some((shared_letters, badges)))
+    }
+}
+
+fn rucksack(line: &str) -> Result<&str, Error> {
+    if let Some((i, c)) = line.char_indices().find(|(_, c)| !c.is_ascii_alphabetic()) {
+        Err(error::at(&line[i..], format!("unexpected char: {}", c)))
+    } else {
+        Ok(line)
+    }
 }
 
 fn shared_letter(input: &str) -> Result<char, Error> {
@@ -37,23 +107,34 @@ fn priority(c: char) -> Result<i64, Error> {
     }
 }
 
-fn sum_of_priorities_of_shared_letters(input: &str) -> Result<i64, Error> {
+fn sum_of_priorities_of_shared_letters(rucksacks: &[String]) -> Result<i64, Error> {
     let mut sum = 0;
-    for line in input.lines() {
-        let shared_letter = shared_letter(line)?;
-        sum += priority(shared_letter)?;
+    for (i, rucksack) in rucksacks.iter().enumerate() {
+        let shared_letter = shared_letter(rucksack)?;
+        let priority = priority(shared_letter)?;
+        sum += priority;
+        trace::event(
+            Level::Steps,
+            "rucksack",
+            &[
+                ("rucksack", &(i + 1)),
+                ("shared", &shared_letter),
+                ("priority", &priority),
+                ("sum", &sum),
+            ],
+        );
     }
     Ok(sum)
 }
 
-fn badge(input: &str) -> Result<char, Error> {
+fn badge(group: impl IntoIterator<Item = impl AsRef<str>>) -> Result<char, Error> {
     let mut shared_chars = HashSet::new();
-    for line in input.lines() {
+    for rucksack in group {
         if shared_chars.is_empty() {
-            shared_chars.extend(line.chars());
+            shared_chars.extend(rucksack.as_ref().chars());
         } else {
-            let chars: HashSet<char> = HashSet::from_iter(line.chars());
-            shared_chars = shared_chars.intersection(&chars).map(|&c| c).collect();
+            let chars: HashSet<char> = HashSet::from_iter(rucksack.as_ref().chars());
+            shared_chars = shared_chars.intersection(&chars).copied().collect();
         }
     }
     if shared_chars.len() == 1 {
@@ -66,20 +147,81 @@ fn badge(input: &str) -> Result<char, Error> {
     }
 }
 
-fn sum_of_priorities_of_badges(input: &str) -> Result<i64, Error> {
-    let input = input.trim();
-    let mut start = 0;
+fn sum_of_priorities_of_badges(rucksacks: &[String]) -> Result<i64, Error> {
+    let mut sum = 0;
+    for (i, group) in rucksacks.chunks(3).enumerate() {
+        let badge = badge(group)?;
+        let priority = priority(badge)?;
+        sum += priority;
+        trace::event(
+            Level::Steps,
+            "group",
+            &[
+                ("group", &(i + 1)),
+                ("badge", &badge),
+                ("priority", &priority),
+                ("sum", &sum),
+            ],
+        );
+    }
+    Ok(sum)
+}
+
+// Each group of three shares only its badge, which is also the one item in both compartments of
+// each rucksack. Every compartment draws its other items from its own pool of letters.
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut input = String::new();
+    for _ in 0..size.max(1).div_ceil(3) {
+        let mut letters: Vec<char> = ('a'..='z').chain('A'..='Z').collect();
+        rng.shuffle(&mut letters);
+        let badge = letters[0];
+        let pools: Vec<&[char]> = letters[1..49].chunks(8).collect();
+        for pool in pools.chunks(2) {
+            let length = rng.below(12) + 4;
+            for compartment in pool {
+                let mut items: Vec<char> = (0..length - 1)
+                    .map(|_| *rng.choose(compartment).unwrap_or(&badge))
+                    .collect();
+                items.insert(rng.below(length), badge);
+                input.extend(items);
+            }
+            input.push('\n');
+        }
+    }
+    input
+}
+
+// Tries every letter against every member of each group rather than intersecting sets.
+fn naive_sum_of_priorities(groups: Vec<Vec<&str>>) -> Result<i64, Error> {
+    let letters: Vec<char> = ('a'..='z').chain('A'..='Z').collect();
     let mut sum = 0;
-    for match_index in input.match_indices('\n').skip(2).step_by(3) {
-        let badge = badge(&input[start..match_index.0])?;
-        sum += priority(badge)?;
-        start = match_index.0 + 1;
+    for group in groups {
+        let shared: Vec<usize> = (0..letters.len())
+            .filter(|&i| group.iter().all(|items| items.contains(letters[i])))
+            .collect();
+        if shared.len() != 1 {
+            return Err(anyhow!("expected one shared letter in {:?}", group));
+        }
+        sum += shared[0] as i64 + 1;
     }
-    let badge = badge(&input[start..])?;
-    sum += priority(badge)?;
     Ok(sum)
 }
 
+pub const EXAMPLE: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw";
+
+pub const EXAMPLES: [Example; 1] = [Example {
+    name: "example",
+    input: EXAMPLE,
+    parameters: &[],
+    part1: Some("157"),
+    part2: Some("70"),
+}];
+
 #[test]
 fn part_1() {
     assert_eq!(shared_letter("vJrwpWtwJgWrhcsFMMfFFhFp").unwrap(), 'p');
@@ -101,15 +243,8 @@ fn part_1() {
     assert_eq!(priority('Z').unwrap(), 52);
 
     assert_eq!(
-        sum_of_priorities_of_shared_letters(
-            "vJrwpWtwJgWrhcsFMMfFFhFp
-jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
-PmmdzqPrVvPwwTWBwg
-wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
-ttgJtRGJQctTZtZT
-CrZsJsPPZsGzwwsLwLmpwMDw"
-        )
-        .unwrap(),
+        sum_of_priorities_of_shared_letters(&RucksackReorganization.parse(EXAMPLE).unwrap())
+            .unwrap(),
         157
     )
 }
@@ -121,6 +256,7 @@ fn part_2() {
             "vJrwpWtwJgWrhcsFMMfFFhFp
 jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
 PmmdzqPrVvPwwTWBwg"
+                .lines()
         )
         .unwrap(),
         'r'
@@ -130,21 +266,14 @@ PmmdzqPrVvPwwTWBwg"
             "wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
 ttgJtRGJQctTZtZT
 CrZsJsPPZsGzwwsLwLmpwMDw"
+                .lines()
         )
         .unwrap(),
         'Z'
     );
 
     assert_eq!(
-        sum_of_priorities_of_badges(
-            "vJrwpWtwJgWrhcsFMMfFFhFp
-jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
-PmmdzqPrVvPwwTWBwg
-wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
-ttgJtRGJQctTZtZT
-CrZsJsPPZsGzwwsLwLmpwMDw"
-        )
-        .unwrap(),
+        sum_of_priorities_of_badges(&RucksackReorganization.parse(EXAMPLE).unwrap()).unwrap(),
         70
     )
 }
@@ -1,50 +1,98 @@
+use crate::{
+    solution::{Example, Solution},
+    trace::{self, Level},
+};
+use advent_of_code_2022::{error, rng::Rng, stream, SpanContext};
 use anyhow::{anyhow, Error};
-use std::str::FromStr;
+use std::{collections::HashSet, fmt::Display, io::BufRead, str::FromStr};
 
-fn main() -> Result<(), Error> {
-    let input = include_str!("04.txt");
-    println!("Part 1: {}", number_of_redundant_pairs(input)?);
-    println!("Part 2: {}", number_of_overlapping_pairs(input)?);
-    Ok(())
-}
+#[derive(Debug, Clone)]
+pub struct CampCleanup;
 
-fn number_of_redundant_pairs(input: &str) -> Result<usize, Error> {
-    let mut count = 0;
-    for line in input.lines() {
-        let ranges = line
-            .split(',')
-            .map(|s| s.parse::<Range>())
-            .collect::<Result<Vec<_>, _>>()?;
-        if ranges.len() != 2 {
-            return Err(anyhow!("invalid input line: {}", line));
-        }
-        if ranges[0].contains(&ranges[1]) || ranges[1].contains(&ranges[0]) {
-            count += 1;
-        }
+impl Solution for CampCleanup {
+    type Input = Vec<Pair>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(&self, input: &str) -> Result<Vec<Pair>, Error> {
+        error::collect(input.lines().map(|line| line.parse()))
     }
-    Ok(count)
+
+    fn part1(&self, pairs: &Vec<Pair>) -> Result<usize, Error> {
+        Ok(number_of_redundant_pairs(pairs))
+    }
+
+    fn part2(&self, pairs: &Vec<Pair>) -> Result<usize, Error> {
+        Ok(number_of_overlapping_pairs(pairs))
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+
+    fn reference1(&self, input: &str) -> Result<usize, Error> {
+        naive_number_of_pairs(input, |first, second| {
+            first.is_subset(second) || second.is_subset(first)
+        })
+    }
+
+    fn reference2(&self, input: &str) -> Result<usize, Error> {
+        naive_number_of_pairs(input, |first, second| !first.is_disjoint(second))
+    }
+
+    fn stream(&self, input: &mut dyn BufRead) -> Result<Option<(usize, usize)>, Error> {
+        let mut redundant = 0;
+        let mut overlapping = 0;
+        stream::for_each_line(input, |line| {
+            let pair: Pair = line.parse()?;
+            redundant += usize::from(pair.is_redundant());
+            overlapping += usize::from(pair.overlaps());
+            Ok(())
+        })?;
+        Ok(Some((redundant, overlapping)))
+    }
+}
+
+fn number_of_redundant_pairs(pairs: &[Pair]) -> usize {
+    count_pairs(pairs, "redundant", Pair::is_redundant)
 }
 
-fn number_of_overlapping_pairs(input: &str) -> Result<usize, Error> {
+fn number_of_overlapping_pairs(pairs: &[Pair]) -> usize {
+    count_pairs(pairs, "overlapping", Pair::overlaps)
+}
+
+// Traces each pair that's counted, under `name`.
+fn count_pairs(pairs: &[Pair], name: &str, f: impl Fn(&Pair) -> bool) -> usize {
     let mut count = 0;
-    for line in input.lines() {
-        let ranges = line
-            .split(',')
-            .map(|s| s.parse::<Range>())
-            .collect::<Result<Vec<_>, _>>()?;
-        if ranges.len() != 2 {
-            return Err(anyhow!("invalid input line: {}", line));
-        }
-        if ranges[0].overlaps(&ranges[1]) {
+    for (i, pair) in pairs.iter().enumerate() {
+        if f(pair) {
             count += 1;
+            trace::event(
+                Level::Steps,
+                name,
+                &[("pair", &(i + 1)), ("assignments", pair), ("count", &count)],
+            );
         }
     }
-    Ok(count)
+    count
 }
 
+#[derive(Debug)]
+pub struct Pair(Range, Range);
+
 #[derive(Debug)]
 struct Range(usize, usize);
 
+impl Pair {
+    fn is_redundant(&self) -> bool {
+        self.0.contains(&self.1) || self.1.contains(&self.0)
+    }
+
+    fn overlaps(&self) -> bool {
+        self.0.overlaps(&self.1)
+    }
+}
+
 impl Range {
     fn contains(&self, other: &Range) -> bool {
         self.0 <= other.0 && self.1 >= other.1
@@ -55,39 +103,100 @@ impl Range {
     }
 }
 
+impl Display for Pair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{},{}-{}", self.0 .0, self.0 .1, self.1 .0, self.1 .1)
+    }
+}
+
+impl FromStr for Pair {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (first, second) = s
+            .split_once(',')
+            .ok_or_else(|| error::at(s, format!("invalid input line: {}", s)))?;
+        Ok(Pair(first.parse()?, second.parse()?))
+    }
+}
+
 impl FromStr for Range {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let values = s
             .split('-')
-            .map(|s| s.parse::<usize>())
+            .map(|s| s.parse::<usize>().at(s))
             .collect::<Result<Vec<_>, _>>()?;
         if values.len() != 2 {
-            Err(anyhow!("invalid range: {}", s))
+            Err(error::at(s, format!("invalid range: {}", s)))
         } else {
             Ok(Range(values[0], values[1]))
         }
     }
 }
 
-#[test]
-fn part_1() {
-    let input = "2-4,6-8
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut input = String::new();
+    for _ in 0..size.max(1) {
+        let mut range = || {
+            let start = rng.range(1..=99);
+            let end = rng.range(start..=99);
+            format!("{}-{}", start, end)
+        };
+        let first = range();
+        let second = range();
+        input.push_str(&format!("{},{}\n", first, second));
+    }
+    input
+}
+
+// Lists out every section in each assignment and compares them as sets.
+fn naive_number_of_pairs(
+    input: &str,
+    f: impl Fn(&HashSet<usize>, &HashSet<usize>) -> bool,
+) -> Result<usize, Error> {
+    let mut count = 0;
+    for line in input.lines() {
+        let numbers = line
+            .split([',', '-'])
+            .map(|s| s.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()?;
+        if numbers.len() != 4 || numbers[0] > numbers[1] || numbers[2] > numbers[3] {
+            return Err(anyhow!("invalid pair: {}", line));
+        }
+        let first: HashSet<usize> = (numbers[0]..=numbers[1]).collect();
+        let second: HashSet<usize> = (numbers[2]..=numbers[3]).collect();
+        if f(&first, &second) {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+pub const EXAMPLE: &str = "2-4,6-8
 2-3,4-5
 5-7,7-9
 2-8,3-7
 6-6,4-6
 2-6,4-8";
-    assert_eq!(number_of_redundant_pairs(input).unwrap(), 2);
+
+pub const EXAMPLES: [Example; 1] = [Example {
+    name: "example",
+    input: EXAMPLE,
+    parameters: &[],
+    part1: Some("2"),
+    part2: Some("4"),
+}];
+
+#[test]
+fn part_1() {
+    let input = EXAMPLE;
+    let pairs = CampCleanup.parse(input).unwrap();
+    assert_eq!(number_of_redundant_pairs(&pairs), 2);
 }
 
 #[test]
 fn part_2() {
-    let input = "2-4,6-8
-2-3,4-5
-5-7,7-9
-2-8,3-7
-6-6,4-6
-2-6,4-8";
-    assert_eq!(number_of_overlapping_pairs(input).unwrap(), 4);
+    let input = EXAMPLE;
+    let pairs = CampCleanup.parse(input).unwrap();
+    assert_eq!(number_of_overlapping_pairs(&pairs), 4);
 }
@@ -1,22 +1,82 @@
+use crate::{
+    animation::Animation,
+    solution::{Example, Solution},
+    trace::{self, Level},
+};
+use advent_of_code_2022::{
+    error,
+    geometry::{BoundingBox, Point},
+    rng::Rng,
+    Tokens,
+};
 use anyhow::{anyhow, Error};
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashMap, fmt::Display, str::FromStr};
 
-fn main() -> Result<(), Error> {
-    let input = include_str!("05.txt");
-    println!("Part 1: {}", top_of_stacks(input, false)?);
-    println!("Part 2: {}", top_of_stacks(input, true)?);
-    Ok(())
-}
+#[derive(Debug, Clone)]
+pub struct SupplyStacks;
+
+impl Solution for SupplyStacks {
+    type Input = Procedure;
+    type Answer1 = String;
+    type Answer2 = String;
+
+    fn parse(&self, input: &str) -> Result<Procedure, Error> {
+        input.parse()
+    }
+
+    fn part1(&self, procedure: &Procedure) -> Result<String, Error> {
+        top_of_stacks(procedure, false)
+    }
+
+    fn part2(&self, procedure: &Procedure) -> Result<String, Error> {
+        top_of_stacks(procedure, true)
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+
+    fn reference1(&self, input: &str) -> Result<String, Error> {
+        naive_top_of_stacks(input, false)
+    }
 
-fn top_of_stacks(input: &str, retain_order: bool) -> Result<String, Error> {
-    let (front, back) = input
-        .split_once("\n\n")
-        .ok_or_else(|| anyhow!("invalid input, no double newline: {}", input))?;
-    let mut stacks: Stacks = front.parse()?;
-    for line in back.lines() {
-        let instruction: Instruction = line.parse()?;
-        stacks.execute(&instruction, retain_order)?;
+    fn reference2(&self, input: &str) -> Result<String, Error> {
+        naive_top_of_stacks(input, true)
     }
+
+    // The stacks after each part, rearranged by the CrateMover 9000 and then the 9001.
+    fn state(&self, procedure: &Procedure) -> Result<Option<String>, Error> {
+        Ok(Some(format!(
+            "{}\n{}",
+            rearrange(procedure, false)?.diagram(),
+            rearrange(procedure, true)?.diagram()
+        )))
+    }
+
+    // Moves one crate at a time, like the CrateMover 9000.
+    fn animate(&self, procedure: &Procedure, animation: &mut Animation) -> Result<bool, Error> {
+        let mut stacks = procedure.stacks.clone();
+        let mut names: Vec<char> = stacks.0.keys().copied().collect();
+        names.sort();
+        let mut focus = Point::new(0, 0);
+        for instruction in &procedure.instructions {
+            for _ in 0..instruction.count {
+                stacks.move_crate(instruction.from, instruction.to)?;
+                focus = stacks.top(&names, instruction.to);
+                animation.frame(stacks.bounds(&names), focus, |point| {
+                    stacks.cell(&names, point)
+                })?;
+            }
+        }
+        animation.finish(stacks.bounds(&names), focus, |point| {
+            stacks.cell(&names, point)
+        })?;
+        Ok(true)
+    }
+}
+
+fn top_of_stacks(procedure: &Procedure, retain_order: bool) -> Result<String, Error> {
+    let stacks = rearrange(procedure, retain_order)?;
     let mut stack_names: Vec<_> = stacks.0.keys().collect();
     stack_names.sort();
     let mut top_of_stacks = String::new();
@@ -30,7 +90,31 @@ fn top_of_stacks(input: &str, retain_order: bool) -> Result<String, Error> {
     Ok(top_of_stacks)
 }
 
+fn rearrange(procedure: &Procedure, retain_order: bool) -> Result<Stacks, Error> {
+    let mut stacks = procedure.stacks.clone();
+    for instruction in &procedure.instructions {
+        stacks.execute(instruction, retain_order)?;
+        trace::event(
+            Level::Steps,
+            "move",
+            &[
+                ("count", &instruction.count),
+                ("from", &instruction.from),
+                ("to", &instruction.to),
+            ],
+        );
+        trace::event(Level::State, "stacks", &[("stacks", &stacks)]);
+    }
+    Ok(stacks)
+}
+
 #[derive(Debug)]
+pub struct Procedure {
+    stacks: Stacks,
+    instructions: Vec<Instruction>,
+}
+
+#[derive(Debug, Clone)]
 struct Stacks(HashMap<char, Vec<char>>);
 
 #[derive(Debug)]
@@ -86,6 +170,60 @@ impl Stacks {
         Ok(())
     }
 
+    fn height(&self) -> i64 {
+        self.0.values().map(Vec::len).max().unwrap_or(0) as i64
+    }
+
+    // Drawn the way the puzzle input is, with the stacks' names underneath.
+    fn bounds(&self, names: &[char]) -> BoundingBox {
+        BoundingBox {
+            min: Point::new(0, 0),
+            max: Point::new(4 * names.len() as i64 - 2, self.height()),
+        }
+    }
+
+    fn top(&self, names: &[char], name: char) -> Point {
+        let column = names.iter().position(|&other| other == name).unwrap_or(0);
+        let crates = self.0.get(&name).map_or(0, Vec::len) as i64;
+        Point::new(4 * column as i64 + 1, self.height() - crates)
+    }
+
+    fn cell(&self, names: &[char], point: Point) -> char {
+        let (column, offset) = (point.x / 4, point.x % 4);
+        let Some(&name) = usize::try_from(column).ok().and_then(|i| names.get(i)) else {
+            return ' ';
+        };
+        if point.y == self.height() {
+            return if offset == 1 { name } else { ' ' };
+        }
+        let level = self.height() - 1 - point.y;
+        let crate_name = usize::try_from(level)
+            .ok()
+            .and_then(|level| self.0.get(&name)?.get(level));
+        match (crate_name, offset) {
+            (Some(_), 0) => '[',
+            (Some(&crate_name), 1) => crate_name,
+            (Some(_), 2) => ']',
+            _ => ' ',
+        }
+    }
+
+    // The stacks the way the puzzle input draws them.
+    fn diagram(&self) -> String {
+        let mut names: Vec<char> = self.0.keys().copied().collect();
+        names.sort();
+        let bounds = self.bounds(&names);
+        let mut diagram = String::new();
+        for y in bounds.min.y..=bounds.max.y {
+            let row: String = (bounds.min.x..=bounds.max.x)
+                .map(|x| self.cell(&names, Point::new(x, y)))
+                .collect();
+            diagram.push_str(row.trim_end());
+            diagram.push('\n');
+        }
+        diagram
+    }
+
     fn stack(&self, name: char) -> Result<&Vec<char>, Error> {
         self.0
             .get(&name)
@@ -99,58 +237,97 @@ impl Stacks {
     }
 }
 
+// Each stack's name and its crates from the bottom up, like `1:ZN 2:MCD 3:P`.
+impl Display for Stacks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut names: Vec<&char> = self.0.keys().collect();
+        names.sort();
+        for (i, name) in names.into_iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}:", name)?;
+            for crate_name in &self.0[name] {
+                write!(f, "{}", crate_name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Procedure {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (front, back) = s
+            .split_once("\n\n")
+            .ok_or_else(|| error::at(&s[s.len()..], "invalid input, no double newline"))?;
+        let (stacks, instructions) = error::both(
+            front.parse(),
+            error::collect(back.lines().map(|line| line.parse())),
+        )?;
+        Ok(Procedure {
+            stacks,
+            instructions,
+        })
+    }
+}
+
 impl FromStr for Stacks {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines().peekable();
-        let mut rows = vec![];
-        while let Some(line) = lines.next() {
-            if lines.peek().is_some() {
-                let mut row = vec![];
-                for i in (0..line.len()).step_by(4) {
-                    let s = if i < line.len() - 4 {
-                        &line[i..i + 4]
-                    } else {
-                        &line[i..]
-                    };
-                    if s.chars().all(|c| c == ' ') {
-                        row.push(None);
-                    } else {
-                        row.push(Some(parse_crate_name(s)?));
+        let lines: Vec<&str> = s.lines().collect();
+        let (names, drawing) = lines
+            .split_last()
+            .ok_or_else(|| error::at(s, "no stack names"))?;
+        let mut rows: Vec<Vec<Option<char>>> =
+            error::collect(drawing.iter().map(|line| row(line)))?;
+        let mut stacks = HashMap::new();
+        for token in names.split_ascii_whitespace() {
+            let stack_name = token
+                .chars()
+                .next()
+                .ok_or_else(|| error::at(token, "empty stack name"))?;
+            let mut stack = vec![];
+            for row in rows.iter_mut().rev() {
+                // Rows stop at their last crate, so one that has run out has nothing left on the
+                // stacks to its right.
+                if !row.is_empty() {
+                    if let Some(crate_name) = row.remove(0) {
+                        stack.push(crate_name)
                     }
                 }
-                rows.push(row);
-            } else {
-                let mut stacks = HashMap::new();
-                for stack_name in line.split_ascii_whitespace() {
-                    let stack_name = stack_name.chars().next().unwrap();
-                    let mut stack = vec![];
-                    for row in rows.iter_mut().rev() {
-                        if row.is_empty() {
-                            return Err(anyhow!("unexpectedly empty row"));
-                        } else if let Some(crate_name) = row.remove(0) {
-                            stack.push(crate_name)
-                        }
-                    }
-                    stacks.insert(stack_name, stack);
-                }
-                return Ok(Stacks(stacks));
             }
+            stacks.insert(stack_name, stack);
         }
-        unreachable!()
+        Ok(Stacks(stacks))
+    }
+}
+
+// One row of the drawing, with `None` where a stack has no crate at this height.
+fn row(line: &str) -> Result<Vec<Option<char>>, Error> {
+    if !line.is_ascii() {
+        return Err(error::at(line, "crate rows must be ASCII"));
     }
+    error::collect((0..line.len()).step_by(4).map(|i| {
+        let s = &line[i..(i + 4).min(line.len())];
+        if s.chars().all(|c| c == ' ') {
+            Ok(None)
+        } else {
+            parse_crate_name(s).map(Some)
+        }
+    }))
 }
 
 impl FromStr for Instruction {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut words = s.split_ascii_whitespace();
-        expect_word(&mut words, "move")?;
-        let count: usize = parse_word(&mut words)?;
-        expect_word(&mut words, "from")?;
-        let from: char = parse_word(&mut words)?;
-        expect_word(&mut words, "to")?;
-        let to: char = parse_word(&mut words)?;
+        let mut tokens = Tokens::new(s);
+        tokens.expect("move")?;
+        let count = tokens.parse()?;
+        tokens.expect("from")?;
+        let from = tokens.parse()?;
+        tokens.expect("to")?;
+        let to = tokens.parse()?;
         Ok(Instruction { count, from, to })
     }
 }
@@ -164,49 +341,106 @@ fn parse_crate_name(s: &str) -> Result<char, Error> {
                     if end == ']' {
                         Ok(crate_name)
                     } else {
-                        Err(anyhow!("crate does not end in ']': {}", s))
+                        Err(error::at(s, format!("crate does not end in ']': {}", s)))
                     }
                 } else {
-                    Err(anyhow!("crate too short: {}", s))
+                    Err(error::at(s, format!("crate too short: {}", s)))
                 }
             } else {
-                Err(anyhow!("crate too short: {}", s))
+                Err(error::at(s, format!("crate too short: {}", s)))
             }
         } else {
-            Err(anyhow!("crate too short: {}", s))
+            Err(error::at(s, format!("crate too short: {}", s)))
         }
     } else {
-        Err(anyhow!("crate empty: {}", s))
+        Err(error::at(s, format!("crate empty: {}", s)))
     }
 }
 
-fn expect_word<'a>(mut iter: impl Iterator<Item = &'a str>, word: &str) -> Result<(), Error> {
-    if let Some(s) = iter.next() {
-        if s == word {
-            Ok(())
-        } else {
-            Err(anyhow!("expected {}, got {}", word, s))
-        }
-    } else {
-        Err(anyhow!("expected {}, got end of iterator", word))
+// Moves never take the last crate off a stack, so every stack has a top at the end.
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let number_of_stacks = rng.below(8) + 2;
+    let mut stacks: Vec<Vec<char>> = (0..number_of_stacks)
+        .map(|_| {
+            (0..rng.below(size.max(1)) + 1)
+                .map(|_| char::from(b'A' + rng.below(26) as u8))
+                .collect()
+        })
+        .collect();
+    let height = stacks.iter().map(|stack| stack.len()).max().unwrap_or(0);
+    let mut input = String::new();
+    for level in (0..height).rev() {
+        let row: Vec<String> = stacks
+            .iter()
+            .map(|stack| match stack.get(level) {
+                Some(crate_name) => format!("[{}]", crate_name),
+                None => "   ".to_string(),
+            })
+            .collect();
+        input.push_str(&row.join(" "));
+        input.push('\n');
+    }
+    let names: Vec<String> = (1..=number_of_stacks).map(|n| format!(" {} ", n)).collect();
+    input.push_str(&names.join(" "));
+    input.push_str("\n\n");
+    for _ in 0..size.max(1) {
+        let sources: Vec<usize> = (0..number_of_stacks)
+            .filter(|&i| stacks[i].len() > 1)
+            .collect();
+        let Some(&from) = rng.choose(&sources) else {
+            break;
+        };
+        let to = (from + 1 + rng.below(number_of_stacks - 1)) % number_of_stacks;
+        let count = rng.below(stacks[from].len() - 1) + 1;
+        let start = stacks[from].len() - count;
+        let crates: Vec<char> = stacks[from].drain(start..).collect();
+        stacks[to].extend(crates);
+        input.push_str(&format!("move {} from {} to {}\n", count, from + 1, to + 1));
     }
+    input
 }
 
-fn parse_word<'a, T>(mut iter: impl Iterator<Item = &'a str>) -> Result<T, Error>
-where
-    T: FromStr,
-    <T as FromStr>::Err: Sync + Send + std::error::Error + 'static,
-{
-    if let Some(s) = iter.next() {
-        s.parse().map_err(Error::from)
-    } else {
-        Err(anyhow!("expected parseable value, got end of iterator",))
+// Moves whole blocks off the end of plain vectors, reversing them when the crane can only lift
+// one crate at a time.
+fn naive_top_of_stacks(input: &str, retain_order: bool) -> Result<String, Error> {
+    let procedure: Procedure = input.parse()?;
+    let mut stacks: Vec<(char, Vec<char>)> = procedure.stacks.0.clone().into_iter().collect();
+    stacks.sort();
+    let names: Vec<char> = stacks.iter().map(|(name, _)| *name).collect();
+    let position = |name: char| {
+        names
+            .iter()
+            .position(|&n| n == name)
+            .ok_or_else(|| anyhow!("invalid stack: {}", name))
+    };
+    for instruction in &procedure.instructions {
+        let (from, to) = (position(instruction.from)?, position(instruction.to)?);
+        if from == to {
+            continue;
+        }
+        let stack = &mut stacks[from].1;
+        let start = stack
+            .len()
+            .checked_sub(instruction.count)
+            .ok_or_else(|| anyhow!("not enough crates on stack {}", instruction.from))?;
+        let mut crates = stack.split_off(start);
+        if !retain_order {
+            crates.reverse();
+        }
+        stacks[to].1.extend(crates);
     }
+    stacks
+        .iter()
+        .map(|(name, stack)| {
+            stack
+                .last()
+                .copied()
+                .ok_or_else(|| anyhow!("empty stack: {}", name))
+        })
+        .collect()
 }
 
-#[test]
-fn part_1() {
-    let input = "    [D]    
+pub const EXAMPLE: &str = "    [D]    
 [N] [C]    
 [Z] [M] [P]
     1   2   3 
@@ -215,19 +449,49 @@ move 1 from 2 to 1
 move 3 from 1 to 3
 move 2 from 2 to 1
 move 1 from 1 to 2";
-    assert_eq!(top_of_stacks(input, false).unwrap(), "CMZ");
+
+pub const EXAMPLES: [Example; 1] = [Example {
+    name: "example",
+    input: EXAMPLE,
+    parameters: &[],
+    part1: Some("CMZ"),
+    part2: Some("MCD"),
+}];
+
+#[test]
+fn part_1() {
+    let input = EXAMPLE;
+    assert_eq!(
+        top_of_stacks(&input.parse().unwrap(), false).unwrap(),
+        "CMZ"
+    );
 }
 
 #[test]
 fn part_2() {
-    let input = "    [D]    
-[N] [C]    
-[Z] [M] [P]
-    1   2   3 
+    let input = EXAMPLE;
+    assert_eq!(top_of_stacks(&input.parse().unwrap(), true).unwrap(), "MCD");
+}
 
-move 1 from 2 to 1
-move 3 from 1 to 3
-move 2 from 2 to 1
-move 1 from 1 to 2";
-    assert_eq!(top_of_stacks(input, true).unwrap(), "MCD");
+#[test]
+fn state() {
+    let procedure = EXAMPLE.parse().unwrap();
+    let state = SupplyStacks.state(&procedure).unwrap().unwrap();
+    crate::snapshot::assert_snapshot("05-stacks", &state);
+}
+
+#[test]
+fn errors() {
+    use crate::solution::Solver;
+    let input = "[A] [B\n[C] [D] x\n 1   2\n\nmove 1 from 1 to 2\nmove x from 2 to 1\nmove 1 to 2";
+    let errors = SupplyStacks.check(input);
+    let lines: Vec<_> = errors
+        .iter()
+        .map(|err| err.to_string().lines().next().unwrap_or("").to_string())
+        .collect();
+    assert_eq!(lines.len(), 4, "{:?}", lines);
+    assert!(lines[0].starts_with("line 1, column 5: crate too short"));
+    assert!(lines[1].starts_with("line 2, column 9: crate too short"));
+    assert!(lines[2].starts_with("line 6, column 6"));
+    assert!(lines[3].starts_with("line 7, column 8"));
 }
@@ -1,93 +1,303 @@
+use crate::{
+    solution::{self, Example, Solution},
+    trace::{self, Level},
+};
+use advent_of_code_2022::{grid::MAX_CELLS, rng::Rng, stream};
 use anyhow::{anyhow, Error};
-use std::collections::{HashMap, VecDeque};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::BufRead,
+};
 
-fn main() -> Result<(), Error> {
-    let input = include_str!("06.txt");
-    println!(
-        "Part 1: {}",
-        start_of_packet(input).ok_or_else(|| anyhow!("no start of packet found: {}", input))?
-    );
-    println!(
-        "Part 2: {}",
-        start_of_message(input).ok_or_else(|| anyhow!("no start of message found: {}", input))?
-    );
-    Ok(())
+#[derive(Debug, Clone)]
+pub struct TuningTrouble {
+    pub packet_length: usize,
+    pub message_length: usize,
 }
 
-fn start_of_packet(input: &str) -> Option<usize> {
-    first_unique_set(input, 4)
+impl TuningTrouble {
+    pub const DEFAULT: TuningTrouble = TuningTrouble {
+        packet_length: 4,
+        message_length: 14,
+    };
 }
 
-fn start_of_message(input: &str) -> Option<usize> {
-    first_unique_set(input, 14)
+impl Solution for TuningTrouble {
+    type Input = String;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(&self, input: &str) -> Result<String, Error> {
+        Ok(input.trim().to_string())
+    }
+
+    fn part1(&self, input: &String) -> Result<usize, Error> {
+        first_unique_set(input, self.packet_length)
+            .ok_or_else(|| anyhow!("no start of packet found: {}", input))
+    }
+
+    fn part2(&self, input: &String) -> Result<usize, Error> {
+        first_unique_set(input, self.message_length)
+            .ok_or_else(|| anyhow!("no start of message found: {}", input))
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        generate(rng, size, self.packet_length.max(self.message_length))
+    }
+
+    fn reference1(&self, input: &str) -> Result<usize, Error> {
+        naive_first_unique_set(input, self.packet_length)
+    }
+
+    fn reference2(&self, input: &str) -> Result<usize, Error> {
+        naive_first_unique_set(input, self.message_length)
+    }
+
+    // Reads only as far as the start of the message. Whitespace is held back until something
+    // follows it, so the characters seen are the ones `parse` keeps: none at the start, none at
+    // the end of a line and none at the end of the input.
+    fn stream(&self, input: &mut dyn BufRead) -> Result<Option<(usize, usize)>, Error> {
+        let mut packet = Marker::new(self.packet_length);
+        let mut message = Marker::new(self.message_length);
+        let (mut start_of_packet, mut start_of_message) = (None, None);
+        let mut whitespace = Vec::new();
+        stream::for_each_char(input, |c| {
+            if c.is_whitespace() {
+                if c == '\n' {
+                    whitespace.retain(|&c| c == '\n');
+                }
+                whitespace.push(c);
+                return true;
+            }
+            if packet.position == 0 {
+                whitespace.clear();
+            }
+            for c in whitespace.drain(..).chain([c]) {
+                start_of_packet = start_of_packet.or_else(|| packet.push(c));
+                start_of_message = start_of_message.or_else(|| message.push(c));
+            }
+            start_of_packet.is_none() || start_of_message.is_none()
+        })?;
+        Ok(Some((
+            start_of_packet.ok_or_else(|| anyhow!("no start of packet found"))?,
+            start_of_message.ok_or_else(|| anyhow!("no start of message found"))?,
+        )))
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("packet-length", self.packet_length.to_string()),
+            ("message-length", self.message_length.to_string()),
+        ]
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        match name {
+            "packet-length" => {
+                self.packet_length = solution::parameter(name, value, 1..=MAX_CELLS)?
+            }
+            "message-length" => {
+                self.message_length = solution::parameter(name, value, 1..=MAX_CELLS)?
+            }
+            _ => return Err(anyhow!("unknown parameter: {}", name)),
+        }
+        Ok(())
+    }
 }
 
 fn first_unique_set(input: &str, len: usize) -> Option<usize> {
-    let mut buffer = VecDeque::new();
-    let mut chars = HashMap::new();
-    for (i, c) in input.chars().enumerate() {
-        buffer.push_back(c);
-        let entry = chars.entry(c).or_insert(0);
-        *entry += 1;
-        if buffer.len() > len {
-            let c = buffer
-                .pop_front()
-                .expect("there must be characters in the buffer");
-            let count = chars
-                .get_mut(&c)
-                .expect("the character should be in the set");
-            *count -= 1;
-            if *count == 0 {
-                chars.remove(&c);
-            }
+    let mut marker = Marker::new(len);
+    let position = input.chars().find_map(|c| {
+        let position = marker.push(c);
+        if trace::enabled(Level::Steps) {
+            trace::event(
+                Level::Steps,
+                "window",
+                &[
+                    ("position", &marker.position),
+                    ("window", &marker.window()),
+                    ("distinct", &marker.chars.len()),
+                ],
+            );
+        }
+        position
+    });
+    if let Some(position) = position {
+        trace::event(
+            Level::Summary,
+            "marker",
+            &[("position", &position), ("marker", &marker.window())],
+        );
+    }
+    position
+}
+
+// The last `len` characters seen, and how many times each of them appears.
+struct Marker {
+    len: usize,
+    position: usize,
+    buffer: VecDeque<char>,
+    chars: HashMap<char, usize>,
+}
+
+impl Marker {
+    fn new(len: usize) -> Marker {
+        Marker {
+            len,
+            position: 0,
+            buffer: VecDeque::new(),
+            chars: HashMap::new(),
         }
-        if buffer.len() == len {
-            if chars.values().all(|count| *count == 1) {
-                return Some(i + 1);
+    }
+
+    // Returns the number of characters seen so far once the last `len` are all different.
+    fn push(&mut self, c: char) -> Option<usize> {
+        self.position += 1;
+        self.buffer.push_back(c);
+        *self.chars.entry(c).or_insert(0) += 1;
+        if self.buffer.len() > self.len {
+            if let Some(c) = self.buffer.pop_front() {
+                if let Some(count) = self.chars.get_mut(&c) {
+                    *count -= 1;
+                    if *count == 0 {
+                        self.chars.remove(&c);
+                    }
+                }
             }
         }
+        if self.buffer.len() == self.len && self.chars.len() == self.len {
+            Some(self.position)
+        } else {
+            None
+        }
+    }
+
+    fn window(&self) -> String {
+        self.buffer.iter().collect()
+    }
+}
+
+// Random letters with a run of `len` distinct ones somewhere, so both markers exist.
+fn generate(rng: &mut Rng, size: usize, len: usize) -> String {
+    let mut letters: Vec<char> = ('a'..='z').collect();
+    let mut input: Vec<char> = (0..size)
+        .map(|_| *rng.choose(&letters).unwrap_or(&'a'))
+        .collect();
+    rng.shuffle(&mut letters);
+    let position = rng.below(input.len() + 1);
+    let _ = input.splice(
+        position..position,
+        letters[..len.min(letters.len())].iter().copied(),
+    );
+    input.into_iter().chain(['\n']).collect()
+}
+
+// Checks every window from scratch.
+fn naive_first_unique_set(input: &str, len: usize) -> Result<usize, Error> {
+    let chars: Vec<char> = input.trim().chars().collect();
+    for end in len..=chars.len() {
+        let window = &chars[end - len..end];
+        if window
+            .iter()
+            .enumerate()
+            .all(|(i, c)| !window[i + 1..].contains(c))
+        {
+            return Ok(end);
+        }
     }
-    None
+    Err(anyhow!("no {} unique characters in a row", len))
 }
 
+pub const EXAMPLE: &str = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+
+pub const EXAMPLES: [Example; 5] = [
+    Example {
+        name: "example 1",
+        input: EXAMPLE,
+        parameters: &[],
+        part1: Some("7"),
+        part2: Some("19"),
+    },
+    Example {
+        name: "example 2",
+        input: "bvwbjplbgvbhsrlpgdmjqwftvncz",
+        parameters: &[],
+        part1: Some("5"),
+        part2: Some("23"),
+    },
+    Example {
+        name: "example 3",
+        input: "nppdvjthqldpwncqszvftbrmjlhg",
+        parameters: &[],
+        part1: Some("6"),
+        part2: Some("23"),
+    },
+    Example {
+        name: "example 4",
+        input: "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg",
+        parameters: &[],
+        part1: Some("10"),
+        part2: Some("29"),
+    },
+    Example {
+        name: "example 5",
+        input: "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw",
+        parameters: &[],
+        part1: Some("11"),
+        part2: Some("26"),
+    },
+];
+
 #[test]
 fn part_1() {
-    assert_eq!(
-        start_of_packet("mjqjpqmgbljsphdztnvjfqwrcgsmlb").unwrap(),
-        7
-    );
-    assert_eq!(start_of_packet("bvwbjplbgvbhsrlpgdmjqwftvncz").unwrap(), 5);
-    assert_eq!(start_of_packet("nppdvjthqldpwncqszvftbrmjlhg").unwrap(), 6);
-    assert_eq!(
-        start_of_packet("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg").unwrap(),
-        10
-    );
-    assert_eq!(
-        start_of_packet("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw").unwrap(),
-        11
-    );
+    for example in EXAMPLES {
+        let start_of_packet =
+            first_unique_set(example.input, TuningTrouble::DEFAULT.packet_length).unwrap();
+        assert_eq!(Some(start_of_packet.to_string().as_str()), example.part1);
+    }
 }
 
 #[test]
 fn part_2() {
-    assert_eq!(
-        start_of_message("mjqjpqmgbljsphdztnvjfqwrcgsmlb").unwrap(),
-        19
-    );
-    assert_eq!(
-        start_of_message("bvwbjplbgvbhsrlpgdmjqwftvncz").unwrap(),
-        23
-    );
-    assert_eq!(
-        start_of_message("nppdvjthqldpwncqszvftbrmjlhg").unwrap(),
-        23
-    );
-    assert_eq!(
-        start_of_message("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg").unwrap(),
-        29
-    );
-    assert_eq!(
-        start_of_message("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw").unwrap(),
-        26
-    );
+    for example in EXAMPLES {
+        let start_of_message =
+            first_unique_set(example.input, TuningTrouble::DEFAULT.message_length).unwrap();
+        assert_eq!(Some(start_of_message.to_string().as_str()), example.part2);
+    }
+}
+
+#[test]
+fn parameters() {
+    let mut solution = TuningTrouble::DEFAULT;
+    solution.set("packet-length", "5").unwrap();
+    solution.set("message-length", "2").unwrap();
+    let input = solution.parse(EXAMPLE).unwrap();
+    assert_eq!(solution.part1(&input).unwrap(), 8);
+    assert_eq!(solution.part2(&input).unwrap(), 2);
+    let answers = solution.stream(&mut EXAMPLE.as_bytes()).unwrap();
+    assert_eq!(answers, Some((8, 2)));
+    assert!(solution.set("packet-length", "0").is_err());
+    assert!(solution.set("length", "4").is_err());
+}
+
+#[test]
+fn streaming() {
+    use crate::solution::{Part, Solver};
+    let mut solution = TuningTrouble::DEFAULT;
+    solution.set("message-length", "6").unwrap();
+    for input in [
+        EXAMPLE,
+        " \n ab ab\tcd \r\n\ne\n\n",
+        "ééaébcdf",
+        "aab€ab€cdef ",
+        "abcdef\n",
+    ] {
+        let solver: &dyn Solver = &solution;
+        assert_eq!(
+            solver.stream(&mut input.as_bytes()).unwrap(),
+            solver.solve(input, &[Part::One, Part::Two]).ok(),
+            "{:?}",
+            input
+        );
+    }
 }
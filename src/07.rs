@@ -1,3 +1,8 @@
+use crate::{
+    solution::{self, Example, Solution},
+    trace::{self, Level},
+};
+use advent_of_code_2022::{error, rng::Rng, SpanContext, Tokens};
 use anyhow::{anyhow, Error};
 use std::{
     collections::HashMap,
@@ -8,33 +13,114 @@ use std::{
 const DISK_SPACE_AVAILABLE: u64 = 70000000;
 const DISK_SPACE_REQUIRED: u64 = 30000000;
 
-fn main() -> Result<(), Error> {
-    let input = include_str!("07.txt");
-    let mut filesystem = Filesystem::from_input(input)?;
-    println!("Part 1: {}", filesystem.sum_of_total_sizes_at_most(100000));
-    println!(
-        "Part 2: {}",
+#[derive(Debug, Clone)]
+pub struct NoSpaceLeftOnDevice {
+    pub disk_space_available: u64,
+    pub disk_space_required: u64,
+    // Part one adds up the directories no bigger than this.
+    pub threshold: u64,
+}
+
+impl NoSpaceLeftOnDevice {
+    pub const DEFAULT: NoSpaceLeftOnDevice = NoSpaceLeftOnDevice {
+        disk_space_available: DISK_SPACE_AVAILABLE,
+        disk_space_required: DISK_SPACE_REQUIRED,
+        threshold: 100000,
+    };
+}
+
+impl Solution for NoSpaceLeftOnDevice {
+    type Input = Filesystem;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(&self, input: &str) -> Result<Filesystem, Error> {
+        Filesystem::from_input(input)
+    }
+
+    fn part1(&self, filesystem: &Filesystem) -> Result<u64, Error> {
+        let mut filesystem = filesystem.clone();
+        filesystem.sum_of_total_sizes_at_most(self.threshold)
+    }
+
+    fn part2(&self, filesystem: &Filesystem) -> Result<u64, Error> {
+        let mut filesystem = filesystem.clone();
         filesystem.smallest_directory_to_free_up_enough_space(
-            DISK_SPACE_AVAILABLE,
-            DISK_SPACE_REQUIRED
-        )?
-    );
-    Ok(())
+            self.disk_space_available,
+            self.disk_space_required,
+        )
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+
+    fn reference1(&self, input: &str) -> Result<u64, Error> {
+        Ok(naive_directory_sizes(input)?
+            .into_iter()
+            .filter(|(path, size)| !path.is_empty() && *size <= self.threshold)
+            .map(|(_, size)| size)
+            .sum())
+    }
+
+    fn reference2(&self, input: &str) -> Result<u64, Error> {
+        let sizes = naive_directory_sizes(input)?;
+        let used = sizes.get(&Vec::new()).copied().unwrap_or(0);
+        let free = self
+            .disk_space_available
+            .checked_sub(used)
+            .ok_or_else(|| anyhow!("more space used than available: {}", used))?;
+        let needed = self
+            .disk_space_required
+            .checked_sub(free)
+            .ok_or_else(|| anyhow!("already enough free space: {}", free))?;
+        sizes
+            .into_iter()
+            .filter(|(path, size)| !path.is_empty() && *size >= needed)
+            .map(|(_, size)| size)
+            .min()
+            .ok_or_else(|| anyhow!("no directories at least {} big", needed))
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "disk-space-available",
+                self.disk_space_available.to_string(),
+            ),
+            ("disk-space-required", self.disk_space_required.to_string()),
+            ("threshold", self.threshold.to_string()),
+        ]
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        match name {
+            "disk-space-available" => {
+                self.disk_space_available = solution::parameter(name, value, 0..=u64::MAX)?
+            }
+            "disk-space-required" => {
+                self.disk_space_required = solution::parameter(name, value, 0..=u64::MAX)?
+            }
+            "threshold" => self.threshold = solution::parameter(name, value, 0..=u64::MAX)?,
+            _ => return Err(anyhow!("unknown parameter: {}", name)),
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Directory {
     entries: HashMap<String, Entry>,
     total_size: Option<u64>,
 }
 
-#[derive(Debug)]
-struct Filesystem {
+#[derive(Debug, Clone)]
+pub struct Filesystem {
     root: Directory,
     shell: Shell,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Shell {
     working_directory: Vec<String>,
 }
@@ -57,7 +143,7 @@ enum List {
     File { size: u64, name: String },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Entry {
     Directory(Directory),
     File(u64),
@@ -78,22 +164,47 @@ impl Filesystem {
     }
 
     fn build(&mut self, input: &str) -> Result<(), Error> {
-        for line in input.lines() {
-            let line: Line = line.parse()?;
-            match line {
-                Line::Command(command) => match command {
-                    Command::Cd(target) => self.cd(target),
-                    Command::Ls => (),
-                },
-                Line::List(list) => match list {
-                    List::Directory(name) => {
-                        self.add_directory(name)?;
+        error::collect(input.lines().map(|text| self.execute(text)))
+    }
+
+    fn execute(&mut self, text: &str) -> Result<(), Error> {
+        let line: Line = text.parse()?;
+        match line {
+            Line::Command(command) => match command {
+                Command::Cd(target) => {
+                    self.cd(target);
+                    if trace::enabled(Level::Steps) {
+                        trace::event(Level::Steps, "cd", &[("cwd", &self.shell.path(None))]);
                     }
-                    List::File { size, name } => {
-                        self.add_file(name, size)?;
+                }
+                Command::Ls => {
+                    if trace::enabled(Level::Steps) {
+                        trace::event(Level::Steps, "ls", &[("cwd", &self.shell.path(None))]);
                     }
-                },
-            }
+                }
+            },
+            Line::List(list) => match list {
+                List::Directory(name) => {
+                    if trace::enabled(Level::Steps) {
+                        trace::event(
+                            Level::Steps,
+                            "mkdir",
+                            &[("path", &self.shell.path(Some(&name)))],
+                        );
+                    }
+                    self.add_directory(name).at(text)?;
+                }
+                List::File { size, name } => {
+                    if trace::enabled(Level::Steps) {
+                        trace::event(
+                            Level::Steps,
+                            "file",
+                            &[("path", &self.shell.path(Some(&name))), ("size", &size)],
+                        );
+                    }
+                    self.add_file(name, size).at(text)?;
+                }
+            },
         }
         Ok(())
     }
@@ -125,7 +236,7 @@ impl Filesystem {
     #[cfg(test)]
     fn total_size(&mut self, path: &str) -> Result<u64, Error> {
         let directory = self.directory_mut(path)?;
-        Ok(directory.total_size())
+        directory.total_size()
     }
 
     #[cfg(test)]
@@ -142,8 +253,8 @@ impl Filesystem {
         Ok(directory)
     }
 
-    fn sum_of_total_sizes_at_most(&mut self, at_most: u64) -> u64 {
-        self.root.sum_of_total_sizes_at_most(at_most)
+    fn sum_of_total_sizes_at_most(&mut self, at_most: u64) -> Result<u64, Error> {
+        self.root.sum_of_total_sizes_at_most(at_most, "")
     }
 
     fn smallest_directory_to_free_up_enough_space(
@@ -151,10 +262,20 @@ impl Filesystem {
         disk_space_available: u64,
         disk_space_required: u64,
     ) -> Result<u64, Error> {
-        let used = self.root.total_size();
-        let min_size = disk_space_required - (disk_space_available - used);
+        let used = self.root.total_size()?;
+        let free = disk_space_available
+            .checked_sub(used)
+            .ok_or_else(|| anyhow!("more space used than available: {}", used))?;
+        let min_size = disk_space_required
+            .checked_sub(free)
+            .ok_or_else(|| anyhow!("already enough free space: {}", free))?;
+        trace::event(
+            Level::Summary,
+            "space",
+            &[("used", &used), ("free", &free), ("needed", &min_size)],
+        );
         self.root
-            .smallest_directory_at_least(min_size)
+            .smallest_directory_at_least(min_size)?
             .ok_or_else(|| anyhow!("no directories at least {} big", min_size))
     }
 }
@@ -175,6 +296,24 @@ impl Shell {
             _ => self.working_directory.push(target),
         }
     }
+
+    // The working directory, or `name` inside it, as an absolute path.
+    fn path(&self, name: Option<&str>) -> String {
+        let mut path = String::new();
+        for directory in self
+            .working_directory
+            .iter()
+            .map(String::as_str)
+            .chain(name)
+        {
+            path.push('/');
+            path.push_str(directory);
+        }
+        if path.is_empty() {
+            path.push('/');
+        }
+        path
+    }
 }
 
 impl Directory {
@@ -185,19 +324,22 @@ impl Directory {
         }
     }
 
-    fn total_size(&mut self) -> u64 {
+    fn total_size(&mut self) -> Result<u64, Error> {
         if let Some(total_size) = self.total_size {
-            return total_size;
+            return Ok(total_size);
         }
-        let mut total_size = 0;
+        let mut total_size: u64 = 0;
         for value in self.entries.values_mut() {
-            match value {
-                Entry::Directory(directory) => total_size += directory.total_size(),
-                Entry::File(size) => total_size += *size,
-            }
+            let size = match value {
+                Entry::Directory(directory) => directory.total_size()?,
+                Entry::File(size) => *size,
+            };
+            total_size = total_size
+                .checked_add(size)
+                .ok_or_else(|| anyhow!("directory is too big to add up"))?;
         }
         self.total_size = Some(total_size);
-        total_size
+        Ok(total_size)
     }
 
     fn get_directory_mut(&mut self, name: &str) -> Result<&mut Directory, Error> {
@@ -207,46 +349,60 @@ impl Directory {
             .ok_or_else(|| anyhow!("directory not in filesystem: {}", name))?
         {
             Entry::Directory(d) => Ok(d),
-            Entry::File(_) => {
-                return Err(anyhow!(
-                    "working directory has a file on its path: {}",
-                    name
-                ))
-            }
+            Entry::File(_) => Err(anyhow!(
+                "working directory has a file on its path: {}",
+                name
+            )),
         }
     }
 
-    fn sum_of_total_sizes_at_most(&mut self, at_most: u64) -> u64 {
-        let mut sum = 0;
-        for entry in self.entries.values_mut() {
+    fn sum_of_total_sizes_at_most(&mut self, at_most: u64, path: &str) -> Result<u64, Error> {
+        let mut sum: u64 = 0;
+        let too_big = || anyhow!("directory sizes are too big to add up");
+        for (name, entry) in self.entries.iter_mut() {
             match entry {
                 Entry::Directory(directory) => {
-                    let total_size = directory.total_size();
+                    // Only needed for tracing, so not built otherwise.
+                    let path = if trace::enabled(Level::Summary) {
+                        format!("{}/{}", path, name)
+                    } else {
+                        String::new()
+                    };
+                    let total_size = directory.total_size()?;
                     if total_size <= at_most {
-                        sum += total_size;
+                        trace::event(
+                            Level::Summary,
+                            "counted",
+                            &[("path", &path), ("size", &total_size)],
+                        );
+                        sum = sum.checked_add(total_size).ok_or_else(too_big)?;
                     }
-                    sum += directory.sum_of_total_sizes_at_most(at_most);
+                    sum = sum
+                        .checked_add(directory.sum_of_total_sizes_at_most(at_most, &path)?)
+                        .ok_or_else(too_big)?;
                 }
                 Entry::File(_) => (),
             }
         }
-        sum
+        Ok(sum)
     }
 
-    fn smallest_directory_at_least(&mut self, at_least: u64) -> Option<u64> {
+    fn smallest_directory_at_least(&mut self, at_least: u64) -> Result<Option<u64>, Error> {
         let mut min_size = None;
         for entry in self.entries.values_mut() {
             if let Entry::Directory(directory) = entry {
-                if let Some(size) = directory.smallest_directory_at_least(at_least) {
-                    min_size = Some(size)
+                if let Some(size) = directory.smallest_directory_at_least(at_least)? {
+                    if min_size.map(|n| n > size).unwrap_or(true) {
+                        min_size = Some(size)
+                    }
                 }
-                let total_size = directory.total_size();
+                let total_size = directory.total_size()?;
                 if total_size >= at_least && min_size.map(|n| n > total_size).unwrap_or(true) {
                     min_size = Some(total_size)
                 }
             }
         }
-        min_size
+        Ok(min_size)
     }
 }
 
@@ -280,17 +436,20 @@ impl FromStr for Line {
 impl FromStr for Command {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut iter = s.split_whitespace();
-        let command = iter
-            .next()
-            .ok_or_else(|| anyhow!("unexpected end of input"))?;
+        let mut tokens = Tokens::new(s);
+        let command = tokens.next_str()?;
         match command {
             "cd" => {
-                let target = iter.next().ok_or_else(|| anyhow!("no target for cd"))?;
+                let target = tokens
+                    .next()
+                    .ok_or_else(|| error::at(tokens.rest(), "no target for cd"))?;
                 Ok(Command::Cd(target.to_string()))
             }
             "ls" => Ok(Command::Ls),
-            _ => Err(anyhow!("unexpected command: {}", command)),
+            _ => Err(error::at(
+                command,
+                format!("unexpected command: {}", command),
+            )),
         }
     }
 }
@@ -298,25 +457,135 @@ impl FromStr for Command {
 impl FromStr for List {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut iter = s.split_whitespace();
-        let first = iter
+        let mut tokens = Tokens::new(s);
+        let first = tokens.next_str()?;
+        let name = tokens
             .next()
-            .ok_or_else(|| anyhow!("unexpected end of input"))?;
-        let name = iter.next().ok_or_else(|| anyhow!("no name in list line"))?;
+            .ok_or_else(|| error::at(tokens.rest(), "no name in list line"))?;
         if first == "dir" {
             Ok(List::Directory(name.to_string()))
         } else {
             Ok(List::File {
-                size: first.parse()?,
+                size: first.parse().at(first)?,
                 name: name.to_string(),
             })
         }
     }
 }
 
-#[test]
-fn part_1() {
-    let input = "$ cd /
+// File sizes are scaled so the disk is between 45M and 65M full, which leaves part 2 a directory
+// to delete.
+fn generate(rng: &mut Rng, size: usize) -> String {
+    enum GeneratedEntry {
+        File(String, u64),
+        Directory(String, Vec<GeneratedEntry>),
+    }
+
+    fn directory(rng: &mut Rng, budget: &mut usize, depth: usize) -> Vec<GeneratedEntry> {
+        let mut entries = Vec::new();
+        for i in 0..rng.below(4) + 1 {
+            entries.push(GeneratedEntry::File(
+                format!(
+                    "f{}.{}",
+                    i,
+                    rng.choose(&["txt", "dat", "log"]).unwrap_or(&"txt")
+                ),
+                rng.range(1..=1000) as u64,
+            ));
+        }
+        for i in 0..rng.below(4) {
+            if *budget == 0 || depth > 8 {
+                break;
+            }
+            *budget -= 1;
+            let entries_below = directory(rng, budget, depth + 1);
+            entries.push(GeneratedEntry::Directory(format!("d{}", i), entries_below));
+        }
+        entries
+    }
+
+    fn total(entries: &[GeneratedEntry]) -> u64 {
+        entries
+            .iter()
+            .map(|entry| match entry {
+                GeneratedEntry::File(_, size) => *size,
+                GeneratedEntry::Directory(_, entries) => total(entries),
+            })
+            .sum()
+    }
+
+    fn transcript(entries: &[GeneratedEntry], scale: (u64, u64), input: &mut String) {
+        input.push_str("$ ls\n");
+        for entry in entries {
+            match entry {
+                GeneratedEntry::File(name, size) => {
+                    input.push_str(&format!("{} {}\n", (size * scale.0 / scale.1).max(1), name))
+                }
+                GeneratedEntry::Directory(name, _) => input.push_str(&format!("dir {}\n", name)),
+            }
+        }
+        for entry in entries {
+            if let GeneratedEntry::Directory(name, entries) = entry {
+                input.push_str(&format!("$ cd {}\n", name));
+                transcript(entries, scale, input);
+                input.push_str("$ cd ..\n");
+            }
+        }
+    }
+
+    let mut budget = size;
+    // The solver only considers directories below the root, so keep the bulk of the files in one.
+    let root = vec![GeneratedEntry::Directory(
+        "a".to_string(),
+        directory(rng, &mut budget, 0),
+    )];
+    let used = rng.range(45_000_000..=65_000_000) as u64;
+    let mut input = "$ cd /\n".to_string();
+    transcript(&root, (used, total(&root)), &mut input);
+    input
+}
+
+// The size of every listed directory, keyed by its full path, found by adding each file to all of
+// the directories above it.
+fn naive_directory_sizes(input: &str) -> Result<HashMap<Vec<String>, u64>, Error> {
+    let mut working_directory = Vec::new();
+    let mut directories = vec![Vec::new()];
+    let mut files = HashMap::new();
+    for line in input.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["$", "cd", "/"] => working_directory.clear(),
+            ["$", "cd", ".."] => {
+                let _ = working_directory.pop();
+            }
+            ["$", "cd", name] => working_directory.push(name.to_string()),
+            ["$", "ls"] => (),
+            ["dir", name] => {
+                let mut path = working_directory.clone();
+                path.push(name.to_string());
+                directories.push(path);
+            }
+            [size, name] => {
+                let mut path = working_directory.clone();
+                path.push(name.to_string());
+                let _ = files.insert(path, size.parse::<u64>()?);
+            }
+            _ => return Err(anyhow!("unexpected line: {}", line)),
+        }
+    }
+    let mut sizes: HashMap<Vec<String>, u64> =
+        directories.into_iter().map(|path| (path, 0)).collect();
+    for (path, size) in files {
+        for i in 0..path.len() {
+            if let Some(total) = sizes.get_mut(&path[..i]) {
+                *total += size;
+            }
+        }
+    }
+    Ok(sizes)
+}
+
+pub const EXAMPLE: &str = "$ cd /
 $ ls
 dir a
 14848514 b.txt
@@ -339,42 +608,90 @@ $ ls
 8033020 d.log
 5626152 d.ext
 7214296 k";
+
+pub const EXAMPLES: [Example; 1] = [Example {
+    name: "example",
+    input: EXAMPLE,
+    parameters: &[],
+    part1: Some("95437"),
+    part2: Some("24933642"),
+}];
+
+#[test]
+fn part_1() {
+    let input = EXAMPLE;
     let mut filesystem = Filesystem::from_input(input).unwrap();
     assert_eq!(filesystem.total_size("/a/e").unwrap(), 584);
     assert_eq!(filesystem.total_size("/a").unwrap(), 94853);
     assert_eq!(filesystem.total_size("/d").unwrap(), 24933642);
     assert_eq!(filesystem.total_size("/").unwrap(), 48381165);
-    assert_eq!(filesystem.sum_of_total_sizes_at_most(100000), 95437);
+    assert_eq!(
+        filesystem.sum_of_total_sizes_at_most(100000).unwrap(),
+        95437
+    );
+    let mut filesystem =
+        Filesystem::from_input("$ cd /\n$ ls\n18446744073709551615 a\n1 b").unwrap();
+    assert!(filesystem.total_size("/").is_err());
 }
 
 #[test]
 fn part_2() {
-    let input = "$ cd /
-$ ls
-dir a
-14848514 b.txt
-8504156 c.dat
-dir d
-$ cd a
-$ ls
-dir e
-29116 f
-2557 g
-62596 h.lst
-$ cd e
-$ ls
-584 i
-$ cd ..
-$ cd ..
-$ cd d
-$ ls
-4060174 j
-8033020 d.log
-5626152 d.ext
-7214296 k";
+    let input = EXAMPLE;
     let mut filesystem = Filesystem::from_input(input).unwrap();
     let size = filesystem
         .smallest_directory_to_free_up_enough_space(DISK_SPACE_AVAILABLE, DISK_SPACE_REQUIRED)
         .unwrap();
     assert_eq!(size, 24933642);
+    // Every directory holds a subdirectory that's big enough, and the smallest of them is under a
+    // directory in the middle, so the answer can't depend on the order they're looked at in.
+    let mut input = String::from("$ cd /\n$ ls\n");
+    for i in 0..10 {
+        input.push_str(&format!("dir {}\n", i));
+    }
+    for i in 0..10 {
+        let size = if i == 5 { 500 } else { 600 + i };
+        input.push_str(&format!(
+            "$ cd {}\n$ ls\ndir sub\n$ cd sub\n$ ls\n{} file\n$ cd ..\n$ cd ..\n",
+            i, size
+        ));
+    }
+    let mut filesystem = Filesystem::from_input(&input).unwrap();
+    assert_eq!(
+        filesystem
+            .smallest_directory_to_free_up_enough_space(7000, 1300)
+            .unwrap(),
+        500
+    );
+}
+
+#[test]
+fn parameters() {
+    let mut solution = NoSpaceLeftOnDevice::DEFAULT;
+    solution.set("threshold", "1000").unwrap();
+    solution.set("disk-space-required", "21700000").unwrap();
+    let filesystem = solution.parse(EXAMPLE).unwrap();
+    assert_eq!(solution.part1(&filesystem).unwrap(), 584);
+    assert_eq!(solution.reference1(EXAMPLE).unwrap(), 584);
+    assert_eq!(solution.part2(&filesystem).unwrap(), 94853);
+    assert_eq!(solution.reference2(EXAMPLE).unwrap(), 94853);
+    // With exactly enough space free already, any directory will do, so the smallest is deleted.
+    solution.set("disk-space-required", "21618835").unwrap();
+    assert_eq!(solution.part2(&filesystem).unwrap(), 584);
+    assert_eq!(solution.reference2(EXAMPLE).unwrap(), 584);
+    assert!(solution.set("threshold", "-1").is_err());
+}
+
+#[test]
+fn errors() {
+    use crate::solution::Solver;
+    let input = "$ cd /\n$ ls\n12x a\n$ pwd\ndir b\n$ cd c\n14 d";
+    let errors = NoSpaceLeftOnDevice::DEFAULT.check(input);
+    let lines: Vec<_> = errors
+        .iter()
+        .map(|err| err.to_string().lines().next().unwrap_or("").to_string())
+        .collect();
+    assert_eq!(lines.len(), 3, "{:?}", lines);
+    assert!(lines[0].starts_with("line 3, column 1"));
+    assert!(lines[1].starts_with("line 4, column 3: unexpected command: pwd"));
+    assert!(lines[2].starts_with("line 7, column 1"));
 }
@@ -1,46 +1,119 @@
+use crate::{
+    solution::{Example, Solution},
+    trace::{self, Level},
+};
+use advent_of_code_2022::{
+    geometry::{Point, ORTHOGONAL},
+    grid::Grid,
+    image::{heat, Image, BLACK},
+    rng::Rng,
+};
 use anyhow::{anyhow, Error};
-use std::{collections::HashMap, str::FromStr};
+use std::str::FromStr;
 
-fn main() -> Result<(), Error> {
-    let forest: Forest = include_str!("08.txt").parse().unwrap();
-    println!("Part 1: {}", forest.number_of_visible_trees());
-    println!("Part 2: {}", forest.highest_scenic_score());
-    Ok(())
+#[derive(Debug, Clone)]
+pub struct TreetopTreeHouse;
+
+impl Solution for TreetopTreeHouse {
+    type Input = Forest;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(&self, input: &str) -> Result<Forest, Error> {
+        input.parse()
+    }
+
+    fn part1(&self, forest: &Forest) -> Result<u64, Error> {
+        Ok(forest.number_of_visible_trees())
+    }
+
+    fn part2(&self, forest: &Forest) -> Result<u64, Error> {
+        Ok(forest.highest_scenic_score())
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+
+    fn reference1(&self, input: &str) -> Result<u64, Error> {
+        let trees = naive_trees(input)?;
+        let mut count = 0;
+        for (r, row) in trees.iter().enumerate() {
+            for (c, &height) in row.iter().enumerate() {
+                let column: Vec<u32> = trees.iter().map(|row| row[c]).collect();
+                if row[..c].iter().all(|&other| other < height)
+                    || row[c + 1..].iter().all(|&other| other < height)
+                    || column[..r].iter().all(|&other| other < height)
+                    || column[r + 1..].iter().all(|&other| other < height)
+                {
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    fn reference2(&self, input: &str) -> Result<u64, Error> {
+        let trees = naive_trees(input)?;
+        let mut highest = 0;
+        for (r, row) in trees.iter().enumerate() {
+            for (c, &height) in row.iter().enumerate() {
+                let column: Vec<u32> = trees.iter().map(|row| row[c]).collect();
+                let left: Vec<u32> = row[..c].iter().rev().copied().collect();
+                let up: Vec<u32> = column[..r].iter().rev().copied().collect();
+                let score = naive_viewing_distance(height, &left)
+                    * naive_viewing_distance(height, &row[c + 1..])
+                    * naive_viewing_distance(height, &up)
+                    * naive_viewing_distance(height, &column[r + 1..]);
+                highest = highest.max(score);
+            }
+        }
+        Ok(highest)
+    }
+
+    fn render(&self, forest: &Forest) -> Result<Option<Image>, Error> {
+        forest.render().map(Some)
+    }
 }
 
 #[derive(Debug)]
-struct Forest {
-    trees: HashMap<(usize, usize), u32>,
-    height: usize,
-    width: usize,
+pub struct Forest {
+    trees: Grid<u32>,
 }
 
 impl Forest {
     fn number_of_visible_trees(&self) -> u64 {
         let mut count = 0;
-        for row in 0..self.height {
-            for col in 0..self.width {
-                if self.is_visible(row, col) {
-                    count += 1;
-                }
+        for position in self.trees.points() {
+            if self.is_visible(position) {
+                count += 1;
+                trace::event(
+                    Level::Steps,
+                    "visible",
+                    &[
+                        ("tree", &position),
+                        ("height", &self.trees[position]),
+                        ("count", &count),
+                    ],
+                );
             }
         }
         count
     }
 
-    fn scenic_score(&self, row: usize, col: usize) -> u64 {
-        let height = self.trees[&(row, col)];
-        self.count_visible_trees(height, (0..row).rev().map(|r| (r, col)))
-            * self.count_visible_trees(height, ((row + 1)..self.height).map(|r| (r, col)))
-            * self.count_visible_trees(height, (0..col).rev().map(|c| (row, c)))
-            * self.count_visible_trees(height, ((col + 1)..self.width).map(|c| (row, c)))
+    fn scenic_score(&self, position: Point) -> u64 {
+        let height = self.trees[position];
+        ORTHOGONAL
+            .into_iter()
+            .map(|direction| self.count_visible_trees(height, self.trees.ray(position, direction)))
+            .product()
     }
 
-    fn count_visible_trees(&self, height: u32, iter: impl Iterator<Item = (usize, usize)>) -> u64 {
+    fn count_visible_trees(&self, height: u32, iter: impl Iterator<Item = Point>) -> u64 {
         let mut count = 0;
-        for (row, col) in iter {
+        for position in iter {
             count += 1;
-            if self.trees[&(row, col)] >= height {
+            if self.trees[position] >= height {
                 return count;
             }
         }
@@ -48,96 +121,136 @@ impl Forest {
     }
 
     fn highest_scenic_score(&self) -> u64 {
-        let mut max = 0;
-        for row in 0..self.height {
-            for col in 0..self.width {
-                let scenic_score = self.scenic_score(row, col);
-                if scenic_score > max {
-                    max = scenic_score;
-                }
+        let mut highest = None;
+        for position in self.trees.points() {
+            let score = self.scenic_score(position);
+            trace::event(
+                Level::Steps,
+                "score",
+                &[("tree", &position), ("score", &score)],
+            );
+            if highest.is_none_or(|(_, highest)| score > highest) {
+                highest = Some((position, score));
             }
         }
-        max
+        let Some((position, score)) = highest else {
+            return 0;
+        };
+        trace::event(
+            Level::Summary,
+            "highest",
+            &[("tree", &position), ("score", &score)],
+        );
+        score
     }
 
-    fn is_visible(&self, row: usize, col: usize) -> bool {
-        self.is_visible_from_top(row, col)
-            || self.is_visible_from_right(row, col)
-            || self.is_visible_from_bottom(row, col)
-            || self.is_visible_from_left(row, col)
+    // Scenic scores on a log scale, since a handful of trees score far higher than the rest.
+    fn render(&self) -> Result<Image, Error> {
+        let highest = (self.highest_scenic_score() as f64).ln_1p();
+        let mut image = Image::new(self.trees.width(), self.trees.height(), BLACK)?;
+        for position in self.trees.points() {
+            let score = self.scenic_score(position) as f64;
+            image.set(position, heat(score.ln_1p() / highest));
+        }
+        image.scale(4)
     }
 
-    fn is_visible_from_top(&self, row: usize, col: usize) -> bool {
-        let height = self.trees[&(row, col)];
-        (0..row).all(|r| height > self.trees[&(r, col)])
+    fn is_visible(&self, position: Point) -> bool {
+        let height = self.trees[position];
+        ORTHOGONAL.into_iter().any(|direction| {
+            self.trees
+                .ray(position, direction)
+                .all(|other| height > self.trees[other])
+        })
     }
+}
 
-    fn is_visible_from_bottom(&self, row: usize, col: usize) -> bool {
-        let height = self.trees[&(row, col)];
-        ((row + 1)..self.height).all(|r| height > self.trees[&(r, col)])
+impl FromStr for Forest {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trees = Grid::parse(s.trim(), |_, c| {
+            c.to_digit(10)
+                .ok_or_else(|| anyhow!("could not convert char to height: {}", c))
+        })?;
+        Ok(Forest { trees })
     }
+}
 
-    fn is_visible_from_left(&self, row: usize, col: usize) -> bool {
-        let height = self.trees[&(row, col)];
-        (0..col).all(|c| height > self.trees[&(row, c)])
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut input = String::new();
+    for _ in 0..size.max(1) {
+        for _ in 0..size.max(1) {
+            input.push(char::from(b'0' + rng.below(10) as u8));
+        }
+        input.push('\n');
     }
+    input
+}
 
-    fn is_visible_from_right(&self, row: usize, col: usize) -> bool {
-        let height = self.trees[&(row, col)];
-        ((col + 1)..self.width).all(|c| height > self.trees[&(row, c)])
+// Rows of heights as plain vectors, so the references don't go through the grid.
+fn naive_trees(input: &str) -> Result<Vec<Vec<u32>>, Error> {
+    let trees = input
+        .trim()
+        .lines()
+        .map(|line| {
+            line.chars()
+                .map(|c| c.to_digit(10).ok_or_else(|| anyhow!("not a height: {}", c)))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if trees.is_empty() || trees.iter().any(|row| row.len() != trees[0].len()) {
+        Err(anyhow!("forest isn't rectangular"))
+    } else {
+        Ok(trees)
     }
 }
 
-impl FromStr for Forest {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let lines: Vec<_> = s.trim().lines().collect();
-        if lines.is_empty() {
-            return Err(anyhow!("empty input"));
-        }
-        let height = lines.len();
-        let width = lines[0].len();
-        let mut trees = HashMap::new();
-        for (row, line) in lines.into_iter().enumerate() {
-            if line.len() != width {
-                return Err(anyhow!("irregular widths"));
-            }
-            for (col, c) in line.chars().enumerate() {
-                trees.insert(
-                    (row, col),
-                    c.to_digit(10)
-                        .ok_or_else(|| anyhow!("could not convert char to height: {}", c))?,
-                );
-            }
+fn naive_viewing_distance(height: u32, trees: &[u32]) -> u64 {
+    let mut distance = 0;
+    for &tree in trees {
+        distance += 1;
+        if tree >= height {
+            break;
         }
-        Ok(Forest {
-            trees,
-            height,
-            width,
-        })
     }
+    distance
 }
 
-#[test]
-fn part_1() {
-    let input = "30373
+pub const EXAMPLE: &str = "30373
 25512
 65332
 33549
 35390";
+
+pub const EXAMPLES: [Example; 1] = [Example {
+    name: "example",
+    input: EXAMPLE,
+    parameters: &[],
+    part1: Some("21"),
+    part2: Some("8"),
+}];
+
+#[test]
+fn part_1() {
+    let input = EXAMPLE;
     let forest: Forest = input.parse().unwrap();
     assert_eq!(forest.number_of_visible_trees(), 21);
 }
 
 #[test]
 fn part_2() {
-    let input = "30373
-25512
-65332
-33549
-35390";
+    let input = EXAMPLE;
     let forest: Forest = input.parse().unwrap();
-    assert_eq!(forest.scenic_score(1, 2), 4);
-    assert_eq!(forest.scenic_score(3, 2), 8);
+    assert_eq!(forest.scenic_score(Point::new(2, 1)), 4);
+    assert_eq!(forest.scenic_score(Point::new(2, 3)), 8);
     assert_eq!(forest.highest_scenic_score(), 8);
 }
+
+#[test]
+fn errors() {
+    use crate::solution::Solver;
+    let errors = TreetopTreeHouse.check("30373\n2x512\n6533y");
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].to_string().starts_with("line 2, column 2"));
+    assert!(errors[1].to_string().starts_with("line 3, column 5"));
+}
@@ -1,52 +1,166 @@
+use crate::{
+    animation::Animation,
+    solution::{self, Example, Solution},
+    trace::{self, Level},
+};
+use advent_of_code_2022::{
+    error,
+    geometry::{BoundingBox, Direction, Point},
+    grid::MAX_CELLS,
+    image::{Image, BLACK, WHITE},
+    rng::Rng,
+    stream, SpanContext, Tokens,
+};
 use anyhow::{anyhow, Error, Result};
-use std::{collections::HashSet, str::FromStr};
+use std::{collections::HashSet, io::BufRead, str::FromStr};
 
-fn main() -> Result<()> {
-    let input = include_str!("09.txt");
-    println!(
-        "Part 1: {}",
-        number_of_positions_the_tail_visited(input, 2)?
-    );
-    println!(
-        "Part 2: {}",
-        number_of_positions_the_tail_visited(input, 10)?
-    );
-    Ok(())
+// How many knots are on each part's rope.
+#[derive(Debug, Clone)]
+pub struct RopeBridge {
+    pub short_rope: usize,
+    pub long_rope: usize,
 }
 
-fn number_of_positions_the_tail_visited(input: &str, knots: usize) -> Result<usize> {
-    let mut map = Map::new(knots)?;
-    for line in input.lines() {
-        let instruction: Instruction = line.parse()?;
-        map.execute(instruction);
+// Every knot moves on every step, so longer ropes take proportionally longer.
+const MAX_KNOTS: usize = 1000;
+
+impl RopeBridge {
+    pub const DEFAULT: RopeBridge = RopeBridge {
+        short_rope: 2,
+        long_rope: 10,
+    };
+}
+
+impl Solution for RopeBridge {
+    type Input = Vec<Instruction>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(&self, input: &str) -> Result<Vec<Instruction>> {
+        error::collect(input.lines().map(|line| line.parse()))
+    }
+
+    fn part1(&self, instructions: &Vec<Instruction>) -> Result<usize> {
+        number_of_positions_the_tail_visited(instructions, self.short_rope)
+    }
+
+    fn part2(&self, instructions: &Vec<Instruction>) -> Result<usize> {
+        number_of_positions_the_tail_visited(instructions, self.long_rope)
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+
+    fn reference1(&self, input: &str) -> Result<usize> {
+        naive_number_of_positions_the_tail_visited(input, self.short_rope)
+    }
+
+    fn reference2(&self, input: &str) -> Result<usize> {
+        naive_number_of_positions_the_tail_visited(input, self.long_rope)
+    }
+
+    fn render(&self, instructions: &Vec<Instruction>) -> Result<Option<Image>> {
+        render(instructions, self.short_rope, self.long_rope).map(Some)
+    }
+
+    // Memory grows with the squares the tails visit, not with the number of instructions.
+    fn stream(&self, input: &mut dyn BufRead) -> Result<Option<(usize, usize)>> {
+        let mut short = Map::new(self.short_rope)?;
+        let mut long = Map::new(self.long_rope)?;
+        stream::for_each_line(input, |line| {
+            let instruction = line.parse()?;
+            short.execute(instruction);
+            long.execute(instruction);
+            Ok(())
+        })?;
+        Ok(Some((
+            short.tail_positions.len(),
+            long.tail_positions.len(),
+        )))
+    }
+
+    fn animate(&self, instructions: &Vec<Instruction>, animation: &mut Animation) -> Result<bool> {
+        let mut map = Map::new(self.long_rope)?;
+        let mut bounds = BoundingBox::new(Point::default());
+        for instruction in instructions {
+            for _ in 0..instruction.count {
+                map.step(instruction.direction);
+                for &knot in &map.knots {
+                    bounds.include(knot);
+                }
+                animation.frame(bounds, map.knots[0], |point| map.cell(point))?;
+            }
+        }
+        animation.finish(bounds, map.knots[0], |point| map.cell(point))?;
+        Ok(true)
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("short-rope", self.short_rope.to_string()),
+            ("long-rope", self.long_rope.to_string()),
+        ]
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        match name {
+            "short-rope" => self.short_rope = solution::parameter(name, value, 2..=MAX_KNOTS)?,
+            "long-rope" => self.long_rope = solution::parameter(name, value, 2..=MAX_KNOTS)?,
+            _ => return Err(anyhow!("unknown parameter: {}", name)),
+        }
+        Ok(())
     }
-    Ok(map.tail_positions.len())
 }
 
-#[derive(Debug)]
-struct Map {
-    knots: Vec<Position>,
-    tail_positions: HashSet<Position>,
+fn number_of_positions_the_tail_visited(
+    instructions: &[Instruction],
+    knots: usize,
+) -> Result<usize> {
+    tail_positions(instructions, knots).map(|positions| positions.len())
+}
+
+fn tail_positions(instructions: &[Instruction], knots: usize) -> Result<HashSet<Point>> {
+    let mut map = Map::new(knots)?;
+    for &instruction in instructions {
+        map.execute(instruction);
+    }
+    Ok(map.tail_positions)
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Default, Clone, Copy)]
-struct Position {
-    x: i64,
-    y: i64,
+// Squares the short rope's tail visited in orange, the long rope's in white and the start in
+// green.
+fn render(instructions: &[Instruction], short: usize, long: usize) -> Result<Image> {
+    let short = tail_positions(instructions, short)?;
+    let long = tail_positions(instructions, long)?;
+    let bounds = BoundingBox::from_points(short.iter().chain(&long).copied())
+        .ok_or_else(|| anyhow!("the tail never moved"))?;
+    let mut image = Image::new(
+        bounds.width().try_into()?,
+        bounds.height().try_into()?,
+        BLACK,
+    )?;
+    let origin = bounds.min - Point::default();
+    for &position in &short {
+        image.set(position - origin, [255, 140, 0]);
+    }
+    for &position in &long {
+        image.set(position - origin, WHITE);
+    }
+    image.set(Point::default() - origin, [0, 255, 0]);
+    image.scale(2)
 }
 
 #[derive(Debug)]
-struct Instruction {
-    direction: Direction,
-    count: usize,
+struct Map {
+    knots: Vec<Point>,
+    tail_positions: HashSet<Point>,
 }
 
 #[derive(Debug, Clone, Copy)]
-enum Direction {
-    Left,
-    Right,
-    Up,
-    Down,
+pub struct Instruction {
+    direction: Direction,
+    count: usize,
 }
 
 impl Map {
@@ -54,117 +168,141 @@ impl Map {
         if knots < 2 {
             return Err(anyhow!("need at least two knots: {}", knots));
         }
-        let knots = vec![Position::default(); knots];
+        let knots = vec![Point::default(); knots];
         Ok(Map {
             knots,
-            tail_positions: HashSet::from_iter([Position::default()].into_iter()),
+            tail_positions: HashSet::from_iter([Point::default()]),
         })
     }
 
     fn execute(&mut self, instruction: Instruction) {
         for _ in 0..instruction.count {
-            self.move_head(instruction.direction);
-            self.move_tailing_knots();
+            self.step(instruction.direction);
+            if trace::enabled(Level::State) {
+                let knots: Vec<String> = self.knots.iter().map(Point::to_string).collect();
+                trace::event(Level::State, "knots", &[("knots", &knots.join(" "))]);
+            }
+        }
+        if trace::enabled(Level::Steps) {
+            trace::event(
+                Level::Steps,
+                "move",
+                &[
+                    ("rope", &self.knots.len()),
+                    ("direction", &format!("{:?}", instruction.direction)),
+                    ("count", &instruction.count),
+                    ("head", &self.knots[0]),
+                    ("tail", &self.knots[self.knots.len() - 1]),
+                    ("visited", &self.tail_positions.len()),
+                ],
+            );
         }
     }
 
+    fn step(&mut self, direction: Direction) {
+        self.move_head(direction);
+        self.move_tailing_knots();
+    }
+
     fn move_head(&mut self, direction: Direction) {
-        self.knots[0].move_in_direction(direction);
+        self.knots[0] += direction.vector();
     }
 
+    // A knot that's no longer touching the one ahead of it moves one step towards it, diagonally
+    // if they aren't in the same row or column.
     fn move_tailing_knots(&mut self) {
-        use Direction::*;
-        for i in 0..(self.knots.len() - 1) {
-            let first = self.knots[i];
-            let second = self.knots.get_mut(i + 1).unwrap();
-            let delta_x = first.x - second.x;
-            let delta_y = first.y - second.y;
-            if delta_x.abs() == 2 {
-                if delta_x == 2 {
-                    second.move_in_direction(Right);
-                } else {
-                    second.move_in_direction(Left)
-                }
-                if delta_y > 0 {
-                    second.move_in_direction(Up)
-                } else if delta_y < 0 {
-                    second.move_in_direction(Down)
-                }
-            } else if delta_y.abs() == 2 {
-                if delta_y == 2 {
-                    second.move_in_direction(Up);
-                } else {
-                    second.move_in_direction(Down);
-                }
-                if delta_x > 0 {
-                    second.move_in_direction(Right)
-                } else if delta_x < 0 {
-                    second.move_in_direction(Left)
-                }
+        for i in 1..self.knots.len() {
+            let ahead = self.knots[i - 1];
+            let knot = &mut self.knots[i];
+            if knot.chebyshev_distance(ahead) > 1 {
+                *knot += (ahead - *knot).signum();
             }
         }
-        self.tail_positions.insert(*self.knots.last().unwrap());
+        if let Some(&tail) = self.knots.last() {
+            self.tail_positions.insert(tail);
+        }
+    }
+
+    // The head is H and the knots behind it are numbered, over the squares the tail visited.
+    fn cell(&self, point: Point) -> char {
+        match self.knots.iter().position(|&knot| knot == point) {
+            Some(0) => 'H',
+            Some(i) => char::from_digit(i as u32 % 10, 10).unwrap_or('?'),
+            None if point == Point::default() => 's',
+            None if self.tail_positions.contains(&point) => '#',
+            None => '.',
+        }
     }
 }
 
 impl FromStr for Instruction {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut iter = s.split(" ");
-        let direction = iter
-            .next()
-            .ok_or_else(|| anyhow!("unexpected end of input"))
-            .and_then(|s| s.parse::<Direction>())?;
-        let count = iter
-            .next()
-            .ok_or_else(|| anyhow!("unexpected end of input"))
-            .and_then(|s| s.parse::<usize>().map_err(Error::from))?;
+        let mut tokens = Tokens::new(s);
+        let direction = tokens.parse()?;
+        let count_token = tokens.next_str()?;
+        let count = count_token.parse().at(count_token)?;
+        // The tail can visit a new square on every step, so a longer move could leave behind more
+        // of them than a grid is allowed to hold.
+        if count > MAX_CELLS {
+            return Err(error::at(
+                count_token,
+                format!("can't move {} steps at once", count),
+            ));
+        }
         Ok(Instruction { direction, count })
     }
 }
 
-impl FromStr for Direction {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use Direction::*;
-        match s {
-            "L" => Ok(Left),
-            "R" => Ok(Right),
-            "U" => Ok(Up),
-            "D" => Ok(Down),
-            _ => Err(anyhow!("unexpected direction: {}", s)),
-        }
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut input = String::new();
+    for _ in 0..size.max(1) {
+        let direction = rng.choose(&["U", "D", "L", "R"]).unwrap_or(&"U");
+        input.push_str(&format!("{} {}\n", direction, rng.range(1..=20)));
     }
+    input
 }
 
-impl Position {
-    fn move_in_direction(&mut self, direction: Direction) {
-        use Direction::*;
-        match direction {
-            Left => self.x -= 1,
-            Right => self.x += 1,
-            Up => self.y += 1,
-            Down => self.y -= 1,
+// Moves each knot that's fallen behind to whichever of the nine squares around it is closest to
+// the knot ahead, which is what the puzzle's rules work out to.
+fn naive_number_of_positions_the_tail_visited(input: &str, knots: usize) -> Result<usize> {
+    let mut rope = vec![(0i64, 0i64); knots];
+    let mut visited = HashSet::from([(0, 0)]);
+    for line in input.lines() {
+        let (direction, count) = line
+            .split_once(' ')
+            .ok_or_else(|| anyhow!("invalid line: {}", line))?;
+        let (dx, dy) = match direction {
+            "U" => (0, -1),
+            "R" => (1, 0),
+            "D" => (0, 1),
+            "L" => (-1, 0),
+            _ => return Err(anyhow!("unexpected direction: {}", direction)),
+        };
+        for _ in 0..count.parse::<usize>()? {
+            rope[0] = (rope[0].0 + dx, rope[0].1 + dy);
+            for i in 1..knots {
+                let (ax, ay) = rope[i - 1];
+                let (x, y) = rope[i];
+                if (ax - x).abs() <= 1 && (ay - y).abs() <= 1 {
+                    continue;
+                }
+                if let Some(closest) = (-1..=1)
+                    .flat_map(|dx| (-1..=1).map(move |dy| (x + dx, y + dy)))
+                    .min_by_key(|&(cx, cy)| (ax - cx).pow(2) + (ay - cy).pow(2))
+                {
+                    rope[i] = closest;
+                }
+            }
+            if let Some(&tail) = rope.last() {
+                let _ = visited.insert(tail);
+            }
         }
     }
+    Ok(visited.len())
 }
 
-#[test]
-fn part_1() {
-    let input = "R 4
-U 4
-L 3
-D 1
-R 4
-D 1
-L 5
-R 2";
-    assert_eq!(number_of_positions_the_tail_visited(input, 2).unwrap(), 13);
-}
-
-#[test]
-fn part_2() {
-    let input = "R 4
+pub const EXAMPLE: &str = "R 4
 U 4
 L 3
 D 1
@@ -172,15 +310,78 @@ R 4
 D 1
 L 5
 R 2";
-    assert_eq!(number_of_positions_the_tail_visited(input, 10).unwrap(), 1);
 
-    let input = "R 5
+pub const EXAMPLES: [Example; 2] = [
+    Example {
+        name: "example",
+        input: EXAMPLE,
+        parameters: &[],
+        part1: Some("13"),
+        part2: Some("1"),
+    },
+    Example {
+        name: "larger example",
+        input: "R 5
 U 8
 L 8
 D 3
 R 17
 D 10
 L 25
-U 20";
-    assert_eq!(number_of_positions_the_tail_visited(input, 10).unwrap(), 36);
+U 20",
+        parameters: &[],
+        part1: None,
+        part2: Some("36"),
+    },
+];
+
+#[test]
+fn part_1() {
+    let input = EXAMPLE;
+    let instructions = RopeBridge::DEFAULT.parse(input).unwrap();
+    assert_eq!(
+        number_of_positions_the_tail_visited(&instructions, 2).unwrap(),
+        13
+    );
+    let input = "R 4\nU 4000000000";
+    let err = error::locate(RopeBridge::DEFAULT.parse(input).unwrap_err(), input);
+    assert!(err
+        .to_string()
+        .starts_with("line 2, column 3: can't move 4000000000 steps at once"));
+}
+
+#[test]
+fn part_2() {
+    let input = EXAMPLE;
+    let instructions = RopeBridge::DEFAULT.parse(input).unwrap();
+    assert_eq!(
+        number_of_positions_the_tail_visited(&instructions, 10).unwrap(),
+        1
+    );
+
+    let instructions = RopeBridge::DEFAULT.parse(EXAMPLES[1].input).unwrap();
+    assert_eq!(
+        number_of_positions_the_tail_visited(&instructions, 10).unwrap(),
+        36
+    );
+}
+
+#[test]
+fn parameters() {
+    let mut solution = RopeBridge::DEFAULT;
+    solution.set("short-rope", "3").unwrap();
+    solution.set("long-rope", "4").unwrap();
+    let instructions = solution.parse(EXAMPLE).unwrap();
+    let answers = (
+        solution.part1(&instructions).unwrap(),
+        solution.part2(&instructions).unwrap(),
+    );
+    assert_eq!(answers.0, solution.reference1(EXAMPLE).unwrap());
+    assert_eq!(answers.1, solution.reference2(EXAMPLE).unwrap());
+    assert_eq!(
+        solution.stream(&mut EXAMPLE.as_bytes()).unwrap(),
+        Some(answers)
+    );
+    assert!(solution.set("short-rope", "1").is_err());
+    assert!(solution.set("long-rope", "1000000000000").is_err());
 }
@@ -1,73 +1,247 @@
+use crate::{
+    animation::Animation,
+    solution::{self, Example, Solution},
+    trace::{self, Level},
+};
+use advent_of_code_2022::{
+    error,
+    geometry::{BoundingBox, Point},
+    grid::MAX_CELLS,
+    rng::Rng,
+    stream, Tokens,
+};
 use anyhow::{anyhow, Error, Result};
 use std::{
     collections::{HashMap, VecDeque},
     fmt::Display,
+    io::BufRead,
     str::FromStr,
 };
 
-fn main() -> Result<()> {
-    let input = include_str!("10.txt");
-    println!("Part 1: {}", sum_of_signal_strengths(input, 20, 40)?);
+#[derive(Debug, Clone)]
+pub struct CathodeRayTube {
+    pub width: usize,
+    pub height: usize,
+    // Part one adds up the signal strength during this cycle and every interval after it.
+    pub first_signal: u32,
+    pub signal_interval: u32,
+}
 
-    let mut computer = Computer::with_input(input)?;
-    computer.run()?;
-    println!("Part 2:\n{}", computer);
-    Ok(())
+impl CathodeRayTube {
+    pub const DEFAULT: CathodeRayTube = CathodeRayTube {
+        width: 40,
+        height: 6,
+        first_signal: 20,
+        signal_interval: 40,
+    };
 }
 
-fn sum_of_signal_strengths(input: &str, start: u32, stride: u32) -> Result<i64> {
-    let mut computer = Computer::with_input(input)?;
-    let mut cycle = 0;
-    let mut sum = 0;
-    loop {
-        cycle += 1;
-        let value = computer.tick()?;
-        if (cycle + start) % stride == 0 {
-            sum += value * i64::from(cycle);
+impl Solution for CathodeRayTube {
+    type Input = Computer;
+    type Answer1 = i64;
+    type Answer2 = String;
+
+    fn parse(&self, input: &str) -> Result<Computer> {
+        Computer::with_input(input, self.width, self.height)
+    }
+
+    fn part1(&self, computer: &Computer) -> Result<i64> {
+        sum_of_signal_strengths(computer.clone(), self.first_signal, self.signal_interval)
+    }
+
+    fn part2(&self, computer: &Computer) -> Result<String> {
+        let mut computer = computer.clone();
+        computer.run()?;
+        Ok(computer.to_string())
+    }
+
+    fn state(&self, computer: &Computer) -> Result<Option<String>> {
+        self.part2(computer).map(Some)
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+
+    fn reference1(&self, input: &str) -> Result<i64> {
+        Ok(naive_register_values(input)?
+            .into_iter()
+            .enumerate()
+            .map(|(i, x)| (i as i64 + 1, x))
+            .filter(|&(cycle, _)| {
+                let first = i64::from(self.first_signal);
+                cycle >= first && (cycle - first) % i64::from(self.signal_interval) == 0
+            })
+            .map(|(cycle, x)| cycle * x)
+            .sum())
+    }
+
+    fn reference2(&self, input: &str) -> Result<String> {
+        let (width, height) = (self.width, self.height);
+        let mut screen = vec![vec!['.'; width]; height];
+        for (i, x) in naive_register_values(input)?.into_iter().enumerate() {
+            let (row, col) = ((i % (width * height)) / width, i % width);
+            if (x - col as i64).abs() <= 1 {
+                screen[row][col] = '#';
+            }
+        }
+        Ok(screen
+            .into_iter()
+            .map(|row| row.into_iter().collect::<String>() + "\n")
+            .collect())
+    }
+
+    // Feeds the computer one instruction at a time, so it never has more than one queued.
+    fn stream(&self, input: &mut dyn BufRead) -> Result<Option<(i64, String)>> {
+        let mut computer = Computer::new(self.width, self.height);
+        let mut signal = Signal::new(self.first_signal, self.signal_interval);
+        stream::for_each_line(input, |line| {
+            computer.add_instruction(line.parse()?);
+            while !computer.instructions.is_empty() {
+                signal.tick(&mut computer)?;
+            }
+            Ok(())
+        })?;
+        // Part 1 always runs at least one cycle, so an empty input is an error here too.
+        while signal.cycle == 0 || !computer.is_out_of_instructions() {
+            signal.tick(&mut computer)?;
         }
+        Ok(Some((signal.sum, computer.to_string())))
+    }
+
+    fn animate(&self, computer: &Computer, animation: &mut Animation) -> Result<bool> {
+        let mut computer = computer.clone();
+        // The screen, with the sprite's position on an extra row underneath.
+        let bounds = BoundingBox {
+            min: Point::new(0, 0),
+            max: Point::new(self.width as i64 - 1, self.height as i64),
+        };
+        while !computer.is_out_of_instructions() {
+            let _ = computer.tick()?;
+            let focus = Point::new(computer.pixel_col as i64, computer.pixel_row as i64);
+            animation.frame(bounds, focus, |point| computer.cell(point))?;
+        }
+        animation.finish(bounds, Point::new(0, 0), |point| computer.cell(point))?;
+        Ok(true)
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("width", self.width.to_string()),
+            ("height", self.height.to_string()),
+            ("first-signal", self.first_signal.to_string()),
+            ("signal-interval", self.signal_interval.to_string()),
+        ]
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        match name {
+            "width" => self.width = solution::parameter(name, value, 1..=MAX_CELLS / self.height)?,
+            "height" => self.height = solution::parameter(name, value, 1..=MAX_CELLS / self.width)?,
+            "first-signal" => self.first_signal = solution::parameter(name, value, 1..=u32::MAX)?,
+            "signal-interval" => {
+                self.signal_interval = solution::parameter(name, value, 1..=u32::MAX)?
+            }
+            _ => return Err(anyhow!("unknown parameter: {}", name)),
+        }
+        Ok(())
+    }
+}
+
+fn sum_of_signal_strengths(mut computer: Computer, first: u32, interval: u32) -> Result<i64> {
+    let mut signal = Signal::new(first, interval);
+    loop {
+        signal.tick(&mut computer)?;
         if computer.is_out_of_instructions() {
             break;
         }
     }
-    Ok(sum)
+    Ok(signal.sum)
+}
+
+// Adds up the signal strength during the `first` cycle and every `interval` cycles after it.
+struct Signal {
+    cycle: u32,
+    first: u32,
+    interval: u32,
+    sum: i64,
+}
+
+impl Signal {
+    fn new(first: u32, interval: u32) -> Signal {
+        Signal {
+            cycle: 0,
+            first,
+            interval,
+            sum: 0,
+        }
+    }
+
+    fn tick(&mut self, computer: &mut Computer) -> Result<()> {
+        self.cycle += 1;
+        let value = computer.tick()?;
+        if self.cycle >= self.first && (self.cycle - self.first).is_multiple_of(self.interval) {
+            let strength = value
+                .checked_mul(i64::from(self.cycle))
+                .ok_or_else(|| anyhow!("signal strength overflows at cycle {}", self.cycle))?;
+            self.sum = self
+                .sum
+                .checked_add(strength)
+                .ok_or_else(|| anyhow!("signal strengths overflow at cycle {}", self.cycle))?;
+            trace::event(
+                Level::Summary,
+                "signal",
+                &[
+                    ("cycle", &self.cycle),
+                    ("x", &value),
+                    ("strength", &strength),
+                    ("sum", &self.sum),
+                ],
+            );
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
-struct Computer {
+#[derive(Debug, Clone)]
+pub struct Computer {
     currently_executing: Option<(Instruction, u64)>,
     instructions: VecDeque<Instruction>,
     registers: HashMap<String, i64>,
-    screen: [[bool; 40]; 6],
+    screen: Vec<Vec<bool>>,
     pixel_row: usize,
     pixel_col: usize,
+    cycle: u64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum Instruction {
     Addx(i64),
     Noop,
 }
 
 impl Computer {
-    fn with_input(input: &str) -> Result<Computer> {
-        let mut computer = Computer::new();
-        for line in input.lines() {
-            let instruction = line.parse()?;
+    fn with_input(input: &str, width: usize, height: usize) -> Result<Computer> {
+        let instructions: Vec<Instruction> =
+            error::collect(input.lines().map(|line| line.parse()))?;
+        let mut computer = Computer::new(width, height);
+        for instruction in instructions {
             computer.add_instruction(instruction);
         }
         Ok(computer)
     }
 
-    fn new() -> Computer {
+    fn new(width: usize, height: usize) -> Computer {
         let mut registers = HashMap::new();
         registers.insert("X".to_string(), 1);
         Computer {
             currently_executing: None,
             instructions: VecDeque::new(),
             registers,
-            screen: [[false; 40]; 6],
+            screen: vec![vec![false; width]; height],
             pixel_row: 0,
             pixel_col: 0,
+            cycle: 0,
         }
     }
 
@@ -90,6 +264,20 @@ impl Computer {
         self.screen[row][col]
     }
 
+    fn cell(&self, point: Point) -> char {
+        match (usize::try_from(point.y), usize::try_from(point.x)) {
+            (Ok(row), Ok(col)) if row < self.screen.len() && col < self.screen[0].len() => {
+                if self.is_lit(row, col) {
+                    '#'
+                } else {
+                    '.'
+                }
+            }
+            _ if self.sprite_position().abs_diff(point.x) <= 1 => '^',
+            _ => ' ',
+        }
+    }
+
     fn tick(&mut self) -> Result<i64> {
         let (instruction, cycles_left) =
             if let Some((currently_executing, cycles_left)) = self.currently_executing.take() {
@@ -102,10 +290,28 @@ impl Computer {
                     return Err(anyhow!("unexpectedly out of instructions"));
                 }
             };
+        self.cycle += 1;
         let sprite_position = self.sprite_position();
-        if (sprite_position - i64::try_from(self.pixel_col)?).abs() <= 1 {
+        let lit = sprite_position.abs_diff(i64::try_from(self.pixel_col)?) <= 1;
+        if lit {
             self.draw();
         }
+        if trace::enabled(Level::Steps) {
+            trace::event(
+                Level::Steps,
+                "cycle",
+                &[
+                    ("cycle", &self.cycle),
+                    ("x", &sprite_position),
+                    (
+                        "pixel",
+                        &Point::new(self.pixel_col as i64, self.pixel_row as i64),
+                    ),
+                    ("lit", &lit),
+                ],
+            );
+            trace::event(Level::State, "screen", &[("screen", self)]);
+        }
         self.pixel_col += 1;
         if self.pixel_col >= self.screen[0].len() {
             self.pixel_col = 0;
@@ -116,26 +322,29 @@ impl Computer {
             self.pixel_row = 0;
         }
         if cycles_left == 1 {
-            self.execute(instruction);
+            self.execute(instruction)?;
         } else {
             self.currently_executing = Some((instruction, cycles_left - 1));
         }
         Ok(sprite_position)
     }
 
-    fn execute(&mut self, instruction: Instruction) {
+    fn execute(&mut self, instruction: Instruction) -> Result<()> {
         use Instruction::*;
         match instruction {
             Noop => {}
             Addx(value) => {
                 let register_value = self.sprite_position_mut();
-                *register_value += value;
+                *register_value = register_value
+                    .checked_add(value)
+                    .ok_or_else(|| anyhow!("addx {} overflows the X register", value))?;
             }
         }
+        Ok(())
     }
 
     fn is_out_of_instructions(&self) -> bool {
-        self.instructions.is_empty()
+        self.instructions.is_empty() && self.currently_executing.is_none()
     }
 
     fn sprite_position(&self) -> i64 {
@@ -157,7 +366,7 @@ impl Display for Computer {
                     write!(f, ".")?;
                 }
             }
-            writeln!(f, "")?;
+            writeln!(f)?;
         }
         Ok(())
     }
@@ -177,68 +386,63 @@ impl FromStr for Instruction {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let mut iter = s.split_ascii_whitespace();
-        let instruction = iter
-            .next()
-            .ok_or_else(|| anyhow!("unexpected end of input"))?;
+        let mut tokens = Tokens::new(s);
+        let instruction = tokens.next_str()?;
         match instruction {
             "noop" => Ok(Instruction::Noop),
             "addx" => {
-                let value = iter
-                    .next()
-                    .ok_or_else(|| anyhow!("unexpected end of input"))
-                    .and_then(|s| s.parse::<i64>().map_err(Error::from))?;
-
+                let value = tokens.parse()?;
                 Ok(Instruction::Addx(value))
             }
-            _ => Err(anyhow!("unexpected instruction: {}", instruction)),
+            _ => Err(error::at(
+                instruction,
+                format!("unexpected instruction: {}", instruction),
+            )),
         }
     }
 }
 
-#[test]
-fn part_1a() {
-    let input = "noop
-addx 3
-addx -5";
-    let mut computer = Computer::with_input(input).unwrap();
-    assert_eq!(computer.sprite_position(), 1);
-    computer.tick().unwrap();
-    assert_eq!(computer.sprite_position(), 1);
-    computer.tick().unwrap();
-    assert_eq!(computer.sprite_position(), 1);
-    computer.tick().unwrap();
-    assert_eq!(computer.sprite_position(), 4);
-    computer.tick().unwrap();
-    assert_eq!(computer.sprite_position(), 4);
-    computer.tick().unwrap();
-    assert_eq!(computer.sprite_position(), -1);
-}
-
-#[test]
-fn part_1b() {
-    assert_eq!(
-        sum_of_signal_strengths(test_input(), 20, 40).unwrap(),
-        13140
-    );
+// Keeps the X register near the screen so the sprite is usually visible.
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut input = String::new();
+    let mut x = 1;
+    for _ in 0..size.max(1) {
+        if rng.chance(1, 3) {
+            input.push_str("noop\n");
+        } else {
+            let value = rng.range((-5 - x).max(-20)..=(45 - x).min(20));
+            x += value;
+            input.push_str(&format!("addx {}\n", value));
+        }
+    }
+    input
 }
 
-#[test]
-fn part_2() {
-    let output = "##..##..##..##..##..##..##..##..##..##..
-###...###...###...###...###...###...###.
-####....####....####....####....####....
-#####.....#####.....#####.....#####.....
-######......######......######......####
-#######.......#######.......#######.....\n";
-    let mut computer = Computer::with_input(test_input()).unwrap();
-    computer.run().unwrap();
-    assert_eq!(format!("{}", computer), output);
+// The value of the X register during each cycle, written out one cycle at a time.
+fn naive_register_values(input: &str) -> Result<Vec<i64>> {
+    let mut x = 1;
+    let mut values = Vec::new();
+    for line in input.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["noop"] => values.push(x),
+            ["addx", value] => {
+                values.push(x);
+                values.push(x);
+                x = i64::checked_add(x, value.parse()?)
+                    .ok_or_else(|| anyhow!("addx {} overflows the X register", value))?;
+            }
+            _ => return Err(anyhow!("unexpected instruction: {}", line)),
+        }
+    }
+    if values.is_empty() {
+        Err(anyhow!("no instructions"))
+    } else {
+        Ok(values)
+    }
 }
 
-#[cfg(test)]
-fn test_input() -> &'static str {
-    "addx 15
+pub const EXAMPLE: &str = "addx 15
 addx -11
 addx 6
 addx -3
@@ -383,5 +587,94 @@ addx -6
 addx -11
 noop
 noop
-noop"
+noop";
+
+pub const EXAMPLES: [Example; 1] = [Example {
+    name: "example",
+    input: EXAMPLE,
+    parameters: &[],
+    part1: Some("13140"),
+    part2: Some(
+        "##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######.....\n",
+    ),
+}];
+
+#[test]
+fn part_1a() {
+    let input = "noop
+addx 3
+addx -5";
+    let mut computer = Computer::with_input(input, 40, 6).unwrap();
+    assert_eq!(computer.sprite_position(), 1);
+    computer.tick().unwrap();
+    assert_eq!(computer.sprite_position(), 1);
+    computer.tick().unwrap();
+    assert_eq!(computer.sprite_position(), 1);
+    computer.tick().unwrap();
+    assert_eq!(computer.sprite_position(), 4);
+    computer.tick().unwrap();
+    assert_eq!(computer.sprite_position(), 4);
+    computer.tick().unwrap();
+    assert_eq!(computer.sprite_position(), -1);
+}
+
+#[test]
+fn part_1b() {
+    assert_eq!(
+        sum_of_signal_strengths(Computer::with_input(EXAMPLE, 40, 6).unwrap(), 20, 40).unwrap(),
+        13140
+    );
+}
+
+#[test]
+fn part_2() {
+    let mut computer = Computer::with_input(EXAMPLE, 40, 6).unwrap();
+    computer.run().unwrap();
+    crate::snapshot::assert_snapshot("10-crt", &computer.to_string());
+}
+
+#[test]
+fn parameters() {
+    let mut solution = CathodeRayTube::DEFAULT;
+    solution.set("width", "20").unwrap();
+    solution.set("height", "2").unwrap();
+    solution.set("first-signal", "1").unwrap();
+    solution.set("signal-interval", "100").unwrap();
+    let computer = solution.parse(EXAMPLE).unwrap();
+    // The first 40 pixels wrap onto two rows, and the rest draw over them.
+    let screen = solution.part2(&computer).unwrap();
+    assert_eq!(screen, solution.reference2(EXAMPLE).unwrap());
+    assert_eq!(screen.lines().map(str::len).collect::<Vec<_>>(), [20, 20]);
+    assert_eq!(solution.part1(&computer).unwrap(), 2020);
+    assert_eq!(
+        solution.part1(&computer).unwrap(),
+        solution.reference1(EXAMPLE).unwrap()
+    );
+    assert!(solution.set("width", "0").is_err());
+    assert!(solution.set("width", "18446744073709551615").is_err());
+    // The screen can't have more than `MAX_CELLS` pixels, whichever side is set first.
+    solution.set("width", "134217728").unwrap();
+    assert!(solution.set("height", "3").is_err());
+    assert!(solution.set("height", "2").is_ok());
+    assert_eq!(
+        solution.set("width", "134217729").unwrap_err().to_string(),
+        "width must be between 1 and 134217728"
+    );
+}
+
+#[test]
+fn huge_values() {
+    let solution = CathodeRayTube::DEFAULT;
+    let computer = solution.parse("addx 9223372036854775807\naddx 1").unwrap();
+    assert!(solution.part1(&computer).is_err());
+    assert!(solution.part2(&computer).is_err());
+    // The signal strength during cycle 20 is too big, even though X isn't.
+    let input = format!("addx 9223372036854775806{}", "\nnoop".repeat(20));
+    let computer = solution.parse(&input).unwrap();
+    assert!(solution.part1(&computer).is_err());
 }
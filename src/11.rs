@@ -1,30 +1,165 @@
+use crate::{
+    solution::{self, Example, Solution},
+    trace::{self, Level},
+};
+use advent_of_code_2022::{error, rng::Rng, SpanContext, Tokens};
 use anyhow::{anyhow, Error, Result};
 use std::{
     collections::HashMap,
     str::{FromStr, Lines},
 };
 
-fn main() -> Result<()> {
-    let input = include_str!("11.txt");
-    let mut monkey_business = MonkeyBusiness::from_input(input)?;
-    monkey_business.execute_many(20);
-    println!("Part 1: {}", monkey_business.level()?);
-    let mut monkey_business = MonkeyBusiness::from_input(input)?;
-    monkey_business.divide_by_three = false;
-    monkey_business.execute_many(10_000);
-    println!("Part 2: {}", monkey_business.level()?);
-    Ok(())
+// How many rounds each part plays, with the worry level divided by three after each inspection
+// in the first and not in the second.
+#[derive(Debug, Clone)]
+pub struct MonkeyInTheMiddle {
+    pub rounds_with_relief: usize,
+    pub rounds_without_relief: usize,
+}
+
+const MAX_ROUNDS: usize = 1_000_000;
+
+impl MonkeyInTheMiddle {
+    pub const DEFAULT: MonkeyInTheMiddle = MonkeyInTheMiddle {
+        rounds_with_relief: 20,
+        rounds_without_relief: 10_000,
+    };
 }
 
-#[derive(Debug)]
-struct MonkeyBusiness {
+impl Solution for MonkeyInTheMiddle {
+    type Input = MonkeyBusiness;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(&self, input: &str) -> Result<MonkeyBusiness> {
+        MonkeyBusiness::from_input(input)
+    }
+
+    fn part1(&self, monkey_business: &MonkeyBusiness) -> Result<u64> {
+        let mut monkey_business = monkey_business.clone();
+        monkey_business.execute_many(self.rounds_with_relief)?;
+        monkey_business.level()
+    }
+
+    fn part2(&self, monkey_business: &MonkeyBusiness) -> Result<u64> {
+        let mut monkey_business = monkey_business.clone();
+        monkey_business.divide_by_three = false;
+        monkey_business.execute_many(self.rounds_without_relief)?;
+        monkey_business.level()
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+
+    fn reference1(&self, input: &str) -> Result<u64> {
+        let monkey_business = MonkeyBusiness::from_input(input)?;
+        let (mut monkeys, targets) = naive_monkeys(&monkey_business)?;
+        let mut inspections = vec![0; monkeys.len()];
+        for _ in 0..self.rounds_with_relief {
+            for i in 0..monkeys.len() {
+                let monkey = &mut monkeys[i];
+                let items = std::mem::take(&mut monkey.items);
+                inspections[i] += items.len() as u64;
+                let mut throws = Vec::new();
+                for item in items {
+                    let item = naive_inspect(&monkey.operation, item)
+                        .ok_or_else(|| anyhow!("worry level overflowed"))?
+                        / 3;
+                    let (if_true, if_false) = targets[i];
+                    let target = if item % monkey.test.divisible_by == 0 {
+                        if_true
+                    } else {
+                        if_false
+                    };
+                    throws.push((target, item));
+                }
+                for (target, item) in throws {
+                    monkeys[target].items.push(item);
+                }
+            }
+        }
+        naive_level(inspections)
+    }
+
+    fn reference2(&self, input: &str) -> Result<u64> {
+        let monkey_business = MonkeyBusiness::from_input(input)?;
+        let (monkeys, targets) = naive_monkeys(&monkey_business)?;
+        let divisors: Vec<i64> = monkeys
+            .iter()
+            .map(|monkey| monkey.test.divisible_by)
+            .collect();
+        let mut items: Vec<Vec<Vec<i64>>> = monkeys
+            .iter()
+            .map(|monkey| {
+                monkey
+                    .items
+                    .iter()
+                    .map(|item| divisors.iter().map(|d| item.rem_euclid(*d)).collect())
+                    .collect()
+            })
+            .collect();
+        let mut inspections = vec![0; monkeys.len()];
+        for _ in 0..self.rounds_without_relief {
+            for (i, monkey) in monkeys.iter().enumerate() {
+                let thrown = std::mem::take(&mut items[i]);
+                inspections[i] += thrown.len() as u64;
+                for remainders in thrown {
+                    let remainders = remainders
+                        .into_iter()
+                        .zip(&divisors)
+                        .map(|(remainder, d)| {
+                            naive_inspect(&monkey.operation, remainder)
+                                .map(|item| item.rem_euclid(*d))
+                                .ok_or_else(|| anyhow!("worry level overflowed"))
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    let (if_true, if_false) = targets[i];
+                    let target = if remainders[i] == 0 {
+                        if_true
+                    } else {
+                        if_false
+                    };
+                    items[target].push(remainders);
+                }
+            }
+        }
+        naive_level(inspections)
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("rounds-with-relief", self.rounds_with_relief.to_string()),
+            (
+                "rounds-without-relief",
+                self.rounds_without_relief.to_string(),
+            ),
+        ]
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        match name {
+            "rounds-with-relief" => {
+                self.rounds_with_relief = solution::parameter(name, value, 0..=MAX_ROUNDS)?
+            }
+            "rounds-without-relief" => {
+                self.rounds_without_relief = solution::parameter(name, value, 0..=MAX_ROUNDS)?
+            }
+            _ => return Err(anyhow!("unknown parameter: {}", name)),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MonkeyBusiness {
     monkeys: HashMap<u8, Monkey>,
     numbers: Vec<u8>,
     divide_by_three: bool,
     least_common_multiple: i64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Monkey {
     number: u8,
     items: Vec<i64>,
@@ -33,26 +168,26 @@ struct Monkey {
     inspections: u64,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Operation {
     a: Operand,
     b: Operand,
     function: Function,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum Operand {
     Old,
     Const(i64),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum Function {
     Add,
     Mul,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Test {
     divisible_by: i64,
     if_true: u8,
@@ -62,9 +197,24 @@ struct Test {
 impl MonkeyBusiness {
     fn from_input(input: &str) -> Result<MonkeyBusiness> {
         let mut monkey_business = MonkeyBusiness::new();
-        for lines in input.split("\n\n") {
-            monkey_business.add_monkey(lines.parse()?)?;
-        }
+        error::collect::<(), ()>(input.split("\n\n").map(|lines| {
+            let monkey = lines.parse()?;
+            monkey_business.add_monkey(monkey).at(lines)
+        }))?;
+        let mut monkeys: Vec<&Monkey> = monkey_business.monkeys.values().collect();
+        monkeys.sort_by_key(|monkey| monkey.number);
+        error::collect::<(), ()>(monkeys.into_iter().flat_map(|monkey| {
+            [monkey.test.if_true, monkey.test.if_false]
+                .into_iter()
+                .filter(|target| !monkey_business.monkeys.contains_key(target))
+                .map(|target| {
+                    Err(anyhow!(
+                        "monkey {} throws to missing monkey {}",
+                        monkey.number,
+                        target
+                    ))
+                })
+        }))?;
         Ok(monkey_business)
     }
 
@@ -87,30 +237,75 @@ impl MonkeyBusiness {
             self.least_common_multiple = self
                 .monkeys
                 .values()
-                .map(|monkey| monkey.test.divisible_by)
-                .product();
+                .try_fold(1i64, |product, monkey| {
+                    product.checked_mul(monkey.test.divisible_by)
+                })
+                .ok_or_else(|| anyhow!("product of divisors is too large"))?;
             Ok(())
         }
     }
 
-    fn execute_many(&mut self, count: usize) {
-        for _ in 0..count {
-            self.execute();
+    fn execute_many(&mut self, count: usize) -> Result<()> {
+        for round in 1..=count {
+            self.execute()?;
+            if trace::enabled(Level::Steps) {
+                trace::event(
+                    Level::Steps,
+                    "round",
+                    &[
+                        ("round", &round),
+                        (
+                            "holdings",
+                            &self.describe(|monkey| {
+                                let items: Vec<String> =
+                                    monkey.items.iter().map(i64::to_string).collect();
+                                items.join(",")
+                            }),
+                        ),
+                        (
+                            "inspections",
+                            &self.describe(|monkey| monkey.inspections.to_string()),
+                        ),
+                    ],
+                );
+            }
         }
+        Ok(())
     }
 
-    fn execute(&mut self) {
+    // Each monkey's number and whatever `f` says about it, like `0:79,98 1:54`.
+    fn describe(&self, f: impl Fn(&Monkey) -> String) -> String {
+        let monkeys: Vec<String> = self
+            .numbers
+            .iter()
+            .filter_map(|number| self.monkeys.get(number))
+            .map(|monkey| format!("{}:{}", monkey.number, f(monkey)))
+            .collect();
+        monkeys.join(" ")
+    }
+
+    fn execute(&mut self) -> Result<()> {
         for number in &self.numbers {
             for (item, target) in self
                 .monkeys
                 .get_mut(number)
-                .unwrap()
-                .inspect_and_throw(self.divide_by_three)
+                .ok_or_else(|| anyhow!("no monkey: {}", number))?
+                .inspect_and_throw(self.divide_by_three)?
             {
-                let item = item % self.least_common_multiple;
-                self.monkeys.get_mut(&target).unwrap().items.push(item);
+                // Only safe without the division by three, which doesn't respect the modulus.
+                let item = if self.divide_by_three {
+                    item
+                } else {
+                    item % self.least_common_multiple
+                };
+                self.monkeys
+                    .get_mut(&target)
+                    .ok_or_else(|| anyhow!("no monkey to throw to: {}", target))?
+                    .items
+                    .push(item);
             }
         }
+        Ok(())
     }
 
     fn level(&self) -> Result<u64> {
@@ -119,32 +314,51 @@ impl MonkeyBusiness {
             Err(anyhow!("too few monkeys: {}", monkeys.len()))
         } else {
             monkeys.sort_by_key(|monkey| monkey.inspections);
-            Ok(monkeys[monkeys.len() - 2].inspections * monkeys[monkeys.len() - 1].inspections)
+            let (second, first) = (monkeys[monkeys.len() - 2], monkeys[monkeys.len() - 1]);
+            trace::event(
+                Level::Summary,
+                "busiest",
+                &[
+                    ("first", &first.number),
+                    ("inspections", &first.inspections),
+                    ("second", &second.number),
+                    ("inspections", &second.inspections),
+                ],
+            );
+            Ok(second.inspections * first.inspections)
         }
     }
 }
 
 impl Monkey {
-    fn inspect_and_throw(&mut self, divide_by_three: bool) -> Vec<(i64, u8)> {
+    fn inspect_and_throw(&mut self, divide_by_three: bool) -> Result<Vec<(i64, u8)>> {
         let mut throws = Vec::new();
         let items = std::mem::take(&mut self.items);
         for item in items {
-            let mut item = self.inspect(item);
+            let mut item = self.inspect(item)?;
             if divide_by_three {
-                item = item / 3;
+                item /= 3;
             }
             let target = self.test(item);
+            trace::event(
+                Level::State,
+                "throw",
+                &[("from", &self.number), ("to", &target), ("item", &item)],
+            );
             throws.push((item, target));
             self.inspections += 1;
         }
-        throws
+        Ok(throws)
     }
 
-    fn inspect(&self, item: i64) -> i64 {
-        self.operation.function.call(
-            self.operation.a.resolve(item),
-            self.operation.b.resolve(item),
-        )
+    fn inspect(&self, item: i64) -> Result<i64> {
+        self.operation
+            .function
+            .call(
+                self.operation.a.resolve(item),
+                self.operation.b.resolve(item),
+            )
+            .ok_or_else(|| anyhow!("worry level overflowed for monkey {}", self.number))
     }
 
     fn test(&self, item: i64) -> u8 {
@@ -159,80 +373,54 @@ impl Monkey {
 impl FromStr for Monkey {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let ref mut lines = s.lines();
-        let line = next_line(lines)?;
+        let lines = &mut s.lines();
 
         // Monkey
-        let mut words = line.split_ascii_whitespace();
-        expect_next(&mut words, "Monkey")?;
-        let number_str = next_str(words)?;
-        let number: u8 = if number_str.ends_with(':') {
-            number_str[0..(number_str.len() - 1)].parse()?
-        } else {
-            return Err(anyhow!("invalid number str: {}", number_str));
-        };
+        let mut tokens = Tokens::new(next_line(s, lines)?);
+        tokens.expect("Monkey")?;
+        let number: u8 = tokens.parse_terminated(':')?;
 
         // Starting items
-        let line = next_line(lines)?.trim();
-        let mut words = line.split_ascii_whitespace();
-        expect_next(&mut words, "Starting")?;
-        expect_next(&mut words, "items:")?;
+        let mut tokens = Tokens::new(next_line(s, lines)?);
+        tokens.expect("Starting items:")?;
         let mut items = Vec::new();
-        for mut word in words {
+        for mut word in tokens {
             if word.ends_with(',') {
                 word = &word[0..(word.len() - 1)];
             }
-            let item: i64 = word.parse()?;
+            let item: i64 = word.parse().at(word)?;
             items.push(item);
         }
 
         // Opeartion
-        let line = next_line(lines)?.trim();
-        let mut words = line.split_ascii_whitespace();
-        expect_next(&mut words, "Operation:")?;
-        expect_next(&mut words, "new")?;
-        expect_next(&mut words, "=")?;
-        let a: Operand = next(&mut words)?;
-        let function: Function = next(&mut words)?;
-        let b: Operand = next(words)?;
+        let mut tokens = Tokens::new(next_line(s, lines)?);
+        tokens.expect("Operation: new =")?;
+        let a: Operand = tokens.parse()?;
+        let function: Function = tokens.parse()?;
+        let b: Operand = tokens.parse()?;
         let operation = Operation { a, function, b };
 
         // Test
-        let line = next_line(lines)?.trim();
-        let mut words = line.split_ascii_whitespace();
-        expect_next(&mut words, "Test:")?;
-        expect_next(&mut words, "divisible")?;
-        expect_next(&mut words, "by")?;
-        let divisible_by: i64 = words
-            .next()
-            .ok_or_else(|| anyhow!("unexpected end of input"))
-            .and_then(|s| s.parse().map_err(Error::from))?;
+        let line = next_line(s, lines)?;
+        let mut tokens = Tokens::new(line);
+        tokens.expect("Test: divisible by")?;
+        let divisible_by: i64 = tokens.parse()?;
+        if divisible_by <= 0 {
+            return Err(error::at(
+                line,
+                format!("invalid divisor: {}", divisible_by),
+            ));
+        }
 
         // If true
-        let line = next_line(lines)?.trim();
-        let mut words = line.split_ascii_whitespace();
-        expect_next(&mut words, "If")?;
-        expect_next(&mut words, "true:")?;
-        expect_next(&mut words, "throw")?;
-        expect_next(&mut words, "to")?;
-        expect_next(&mut words, "monkey")?;
-        let if_true: u8 = words
-            .next()
-            .ok_or_else(|| anyhow!("unexpected end of input"))
-            .and_then(|s| s.parse().map_err(Error::from))?;
+        let mut tokens = Tokens::new(next_line(s, lines)?);
+        tokens.expect("If true: throw to monkey")?;
+        let if_true: u8 = tokens.parse()?;
 
         // If false
-        let line = next_line(lines)?.trim();
-        let mut words = line.split_ascii_whitespace();
-        expect_next(&mut words, "If")?;
-        expect_next(&mut words, "false:")?;
-        expect_next(&mut words, "throw")?;
-        expect_next(&mut words, "to")?;
-        expect_next(&mut words, "monkey")?;
-        let if_false: u8 = words
-            .next()
-            .ok_or_else(|| anyhow!("unexpected end of input"))
-            .and_then(|s| s.parse().map_err(Error::from))?;
+        let mut tokens = Tokens::new(next_line(s, lines)?);
+        tokens.expect("If false: throw to monkey")?;
+        let if_false: u8 = tokens.parse()?;
 
         let test = Test {
             divisible_by,
@@ -265,20 +453,17 @@ impl FromStr for Operand {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "old" => Ok(Operand::Old),
-            _ => s
-                .parse::<i64>()
-                .map(|v| Operand::Const(v))
-                .map_err(Error::from),
+            _ => s.parse::<i64>().map(Operand::Const).map_err(Error::from),
         }
     }
 }
 
 impl Function {
-    fn call(&self, a: i64, b: i64) -> i64 {
+    fn call(&self, a: i64, b: i64) -> Option<i64> {
         use Function::*;
         match self {
-            Add => a + b,
-            Mul => a * b,
+            Add => a.checked_add(b),
+            Mul => a.checked_mul(b),
         }
     }
 }
@@ -294,57 +479,97 @@ impl FromStr for Function {
     }
 }
 
-fn next<'a, I, T>(iter: I) -> Result<T>
-where
-    I: Iterator<Item = &'a str>,
-    T: FromStr<Err = Error>,
-{
-    let s = next_str(iter)?;
-    s.parse()
-}
-
-fn next_str<'a, I>(mut iter: I) -> Result<&'a str>
-where
-    I: Iterator<Item = &'a str>,
-{
+fn next_line<'a>(block: &'a str, iter: &'_ mut Lines<'a>) -> Result<&'a str> {
     iter.next()
-        .ok_or_else(|| anyhow!("unexpected end of iterator"))
+        .ok_or_else(|| error::at(&block[block.len()..], "expected another line"))
 }
 
-fn expect_next<'a, I>(iter: I, expected: &str) -> Result<()>
-where
-    I: Iterator<Item = &'a str>,
-{
-    let actual: &str = next_str(iter)?;
-    if actual != expected {
-        Err(anyhow!("actual={}, expected={}", actual, expected))
-    } else {
-        Ok(())
+// The monkeys' divisors are distinct small primes, so the modulus in part 2 stays small. Worry
+// levels are only ever multiplied by two or three, because part 1 divides by three after each
+// inspection and anything larger can overflow over twenty rounds.
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut primes = [2, 3, 5, 7, 11, 13, 17, 19];
+    rng.shuffle(&mut primes);
+    let number_of_monkeys = size.clamp(2, primes.len());
+    let mut blocks = Vec::new();
+    for (number, divisible_by) in primes.iter().take(number_of_monkeys).enumerate() {
+        let items: Vec<String> = (0..rng.below(5))
+            .map(|_| rng.range(50..=99).to_string())
+            .collect();
+        let operation = if rng.chance(1, 2) {
+            format!("old * {}", rng.range(2..=3))
+        } else {
+            format!("old + {}", rng.range(1..=8))
+        };
+        let mut other = || (number + 1 + rng.below(number_of_monkeys - 1)) % number_of_monkeys;
+        let if_true = other();
+        let if_false = other();
+        blocks.push(format!(
+            "Monkey {}:
+  Starting items: {}
+  Operation: new = {}
+  Test: divisible by {}
+    If true: throw to monkey {}
+    If false: throw to monkey {}
+",
+            number,
+            items.join(", "),
+            operation,
+            divisible_by,
+            if_true,
+            if_false
+        ));
     }
+    blocks.join("\n")
 }
 
-fn next_line<'a>(iter: &'_ mut Lines<'a>) -> Result<&'a str> {
-    iter.next().ok_or_else(|| anyhow!("expected another line"))
+// Positions of the monkeys thrown to when the test passes and when it fails.
+type Targets = Vec<(usize, usize)>;
+
+// The monkeys in order, along with where they throw to. The references keep worry levels without
+// the shared modulus that `MonkeyBusiness::execute` relies on.
+fn naive_monkeys(monkey_business: &MonkeyBusiness) -> Result<(Vec<Monkey>, Targets)> {
+    let monkeys: Vec<Monkey> = monkey_business
+        .numbers
+        .iter()
+        .filter_map(|number| monkey_business.monkeys.get(number).cloned())
+        .collect();
+    let position = |number: u8| {
+        monkeys
+            .iter()
+            .position(|monkey| monkey.number == number)
+            .ok_or_else(|| anyhow!("no monkey to throw to: {}", number))
+    };
+    let mut targets = Vec::new();
+    for monkey in &monkeys {
+        if monkey.test.divisible_by <= 0 {
+            return Err(anyhow!("invalid divisor: {}", monkey.test.divisible_by));
+        }
+        targets.push((
+            position(monkey.test.if_true)?,
+            position(monkey.test.if_false)?,
+        ));
+    }
+    Ok((monkeys, targets))
 }
 
-#[test]
-fn part_1() {
-    let mut monkey_business = MonkeyBusiness::from_input(test_input()).unwrap();
-    monkey_business.execute_many(20);
-    assert_eq!(monkey_business.level().unwrap(), 10605);
+fn naive_inspect(operation: &Operation, old: i64) -> Option<i64> {
+    let (a, b) = (operation.a.resolve(old), operation.b.resolve(old));
+    match operation.function {
+        Function::Add => a.checked_add(b),
+        Function::Mul => a.checked_mul(b),
+    }
 }
 
-#[test]
-fn part_2() {
-    let mut monkey_business = MonkeyBusiness::from_input(test_input()).unwrap();
-    monkey_business.divide_by_three = false;
-    monkey_business.execute_many(10_000);
-    assert_eq!(monkey_business.level().unwrap(), 2713310158);
+fn naive_level(mut inspections: Vec<u64>) -> Result<u64> {
+    inspections.sort();
+    match inspections.as_slice() {
+        [.., a, b] => Ok(a * b),
+        _ => Err(anyhow!("too few monkeys: {}", inspections.len())),
+    }
 }
 
-#[cfg(test)]
-fn test_input() -> &'static str {
-    "Monkey 0:
+pub const EXAMPLE: &str = "Monkey 0:
 Starting items: 79, 98
 Operation: new = old * 19
 Test: divisible by 23
@@ -370,5 +595,67 @@ Starting items: 74
 Operation: new = old + 3
 Test: divisible by 17
     If true: throw to monkey 0
-    If false: throw to monkey 1"
+    If false: throw to monkey 1";
+
+pub const EXAMPLES: [Example; 1] = [Example {
+    name: "example",
+    input: EXAMPLE,
+    parameters: &[],
+    part1: Some("10605"),
+    part2: Some("2713310158"),
+}];
+
+#[test]
+fn part_1() {
+    let mut monkey_business = MonkeyBusiness::from_input(EXAMPLE).unwrap();
+    monkey_business.execute_many(20).unwrap();
+    assert_eq!(monkey_business.level().unwrap(), 10605);
+}
+
+#[test]
+fn part_2() {
+    let mut monkey_business = MonkeyBusiness::from_input(EXAMPLE).unwrap();
+    monkey_business.divide_by_three = false;
+    monkey_business.execute_many(10_000).unwrap();
+    assert_eq!(monkey_business.level().unwrap(), 2713310158);
+}
+
+#[test]
+fn parameters() {
+    let mut solution = MonkeyInTheMiddle::DEFAULT;
+    solution.set("rounds-with-relief", "1").unwrap();
+    solution.set("rounds-without-relief", "20").unwrap();
+    let monkey_business = solution.parse(EXAMPLE).unwrap();
+    assert_eq!(solution.part1(&monkey_business).unwrap(), 5 * 4);
+    assert_eq!(solution.part2(&monkey_business).unwrap(), 103 * 99);
+    assert_eq!(solution.reference2(EXAMPLE).unwrap(), 103 * 99);
+    assert!(solution.set("rounds", "1").is_err());
+    assert!(solution
+        .set("rounds-without-relief", "1000000000000")
+        .is_err());
+}
+
+#[test]
+fn errors() {
+    use crate::solution::Solver;
+    let lines = |input: &str| -> Vec<String> {
+        MonkeyInTheMiddle::DEFAULT
+            .check(input)
+            .iter()
+            .map(|err| err.to_string().lines().next().unwrap_or("").to_string())
+            .collect()
+    };
+    let input = EXAMPLE.replace("79, 98", "79, x").replace("by 13", "by 0");
+    let errors = lines(&input);
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    assert!(errors[0].starts_with("line 2, column 21"));
+    assert!(errors[1].starts_with("line 18, column 1: invalid divisor: 0"));
+    let errors = lines(&EXAMPLE.replace("monkey 0", "monkey 5"));
+    assert_eq!(
+        errors,
+        [
+            "monkey 1 throws to missing monkey 5",
+            "monkey 3 throws to missing monkey 5"
+        ]
+    );
 }
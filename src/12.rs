@@ -1,171 +1,289 @@
+use crate::{
+    solution::{Example, Solution},
+    trace::{self, Level},
+};
+use advent_of_code_2022::{
+    geometry::Point,
+    grid::Grid,
+    image::{heat, Image, BLACK},
+    rng::Rng,
+};
 use anyhow::{anyhow, Error, Result};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashSet, VecDeque},
     str::FromStr,
 };
 
-fn main() -> Result<()> {
-    let input = include_str!("12.txt");
-    println!("Part 1: {}", length_of_shortest_path(input)?);
-    println!(
-        "Part 2: {}",
-        length_of_shortest_path_from_base_height(input)?
-    );
-    Ok(())
-}
+#[derive(Debug, Clone)]
+pub struct HillClimbingAlgorithm;
 
-fn length_of_shortest_path(input: &str) -> Result<usize> {
-    let map: Map = input.parse()?;
-    Ok(map.shortest_path()?.len() - 1)
-}
+impl Solution for HillClimbingAlgorithm {
+    type Input = Map;
+    type Answer1 = usize;
+    type Answer2 = usize;
 
-fn length_of_shortest_path_from_base_height(input: &str) -> Result<usize> {
-    let mut map: Map = input.parse()?;
-    let mut path_lengths = Vec::new();
-    for (&position, &height) in map.map.iter() {
-        if height == 0 {
-            map.starting_position = position;
-            if let Ok(path) = map.shortest_path() {
-                path_lengths.push(path.len() - 1);
-            }
-        }
+    fn parse(&self, input: &str) -> Result<Map> {
+        input.parse()
+    }
+
+    fn part1(&self, map: &Map) -> Result<usize> {
+        length_of_shortest_path(map)
+    }
+
+    fn part2(&self, map: &Map) -> Result<usize> {
+        length_of_shortest_path_from_base_height(map)
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+
+    fn reference1(&self, input: &str) -> Result<usize> {
+        naive_shortest_path_length(input, false)
+    }
+
+    fn reference2(&self, input: &str) -> Result<usize> {
+        naive_shortest_path_length(input, true)
+    }
+
+    fn render(&self, map: &Map) -> Result<Option<Image>> {
+        map.render().map(Some)
     }
-    path_lengths
-        .into_iter()
-        .min()
-        .ok_or_else(|| anyhow!("no paths found anywhere"))
 }
 
-#[derive(Debug)]
-struct Map {
-    map: HashMap<Position, i8>,
-    starting_position: Position,
-    ending_position: Position,
+fn length_of_shortest_path(map: &Map) -> Result<usize> {
+    map.shortest_path_length([map.starting_position])
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-struct Position {
-    row: i64,
-    col: i64,
+fn length_of_shortest_path_from_base_height(map: &Map) -> Result<usize> {
+    let starts = map
+        .heights
+        .iter()
+        .filter(|(_, &height)| height == 0)
+        .map(|(point, _)| point);
+    map.shortest_path_length(starts)
+        .map_err(|_| anyhow!("no paths found anywhere"))
+}
+
+#[derive(Debug, Clone)]
+pub struct Map {
+    heights: Grid<i8>,
+    starting_position: Point,
+    ending_position: Point,
 }
 
 impl Map {
-    fn shortest_path(&self) -> Result<Vec<Position>> {
-        let mut paths = vec![vec![self.starting_position]];
-        let mut new_paths = Vec::new();
-        let mut seen = HashSet::new();
-        while !paths.is_empty() {
-            for path in paths.drain(..) {
-                let position = path.last().expect("path should have an end");
-                for neighbor in position.neighbors() {
-                    if !seen.contains(&neighbor) && self.is_legal_move(*position, neighbor) {
-                        let mut new_path = path.clone();
-                        new_path.push(neighbor);
-                        if neighbor == self.ending_position {
-                            return Ok(new_path);
-                        } else {
-                            new_paths.push(new_path);
-                            seen.insert(neighbor);
-                        }
+    // Breadth-first search outward from every start at once.
+    fn shortest_path_length(&self, starts: impl IntoIterator<Item = Point>) -> Result<usize> {
+        let mut seen = self.heights.map(|_| false);
+        let mut positions = Vec::new();
+        for start in starts {
+            seen[start] = true;
+            positions.push(start);
+        }
+        trace::event(Level::Summary, "starts", &[("count", &positions.len())]);
+        let mut new_positions = Vec::new();
+        let mut length = 0;
+        while !positions.is_empty() {
+            trace::event(
+                Level::Steps,
+                "frontier",
+                &[("length", &length), ("size", &positions.len())],
+            );
+            if trace::enabled(Level::State) {
+                let frontier: Vec<String> = positions.iter().map(Point::to_string).collect();
+                trace::event(
+                    Level::State,
+                    "positions",
+                    &[("positions", &frontier.join(" "))],
+                );
+            }
+            for position in positions.drain(..) {
+                if position == self.ending_position {
+                    trace::event(Level::Summary, "reached", &[("length", &length)]);
+                    return Ok(length);
+                }
+                for neighbor in self.heights.neighbors4(position) {
+                    if !seen[neighbor] && self.is_legal_move(position, neighbor) {
+                        seen[neighbor] = true;
+                        new_positions.push(neighbor);
                     }
                 }
             }
-            std::mem::swap(&mut new_paths, &mut paths);
+            std::mem::swap(&mut new_positions, &mut positions);
+            length += 1;
         }
         Err(anyhow!("no paths found"))
     }
 
-    fn is_legal_move(&self, from: Position, to: Position) -> bool {
-        if let Some(to) = self.map.get(&to) {
-            if let Some(from) = self.map.get(&from) {
-                to - from <= 1
-            } else {
-                false
+    // Every square's distance from the start, or None if it can't be reached.
+    fn distances(&self) -> Grid<Option<usize>> {
+        let mut distances = self.heights.map(|_| None);
+        let mut queue = VecDeque::from([(self.starting_position, 0)]);
+        distances[self.starting_position] = Some(0);
+        while let Some((position, distance)) = queue.pop_front() {
+            for neighbor in self.heights.neighbors4(position) {
+                if distances[neighbor].is_none() && self.is_legal_move(position, neighbor) {
+                    distances[neighbor] = Some(distance + 1);
+                    queue.push_back((neighbor, distance + 1));
+                }
             }
-        } else {
-            false
         }
+        distances
+    }
+
+    // Distance from the start as heat, with unreachable squares left black and the goal in blue.
+    fn render(&self) -> Result<Image> {
+        let distances = self.distances();
+        let furthest = distances.iter().filter_map(|(_, &d)| d).max().unwrap_or(0);
+        let mut image = Image::from_grid(&distances, |distance| match distance {
+            Some(distance) => heat(0.1 + 0.9 * *distance as f64 / furthest.max(1) as f64),
+            None => BLACK,
+        });
+        image.set(self.ending_position, [0, 128, 255]);
+        image.scale(4)
+    }
+
+    fn is_legal_move(&self, from: Point, to: Point) -> bool {
+        self.heights[to] - self.heights[from] <= 1
     }
 }
 
 impl FromStr for Map {
     type Err = Error;
     fn from_str(s: &str) -> Result<Map> {
-        let mut map = HashMap::new();
         let mut starting_position = None;
         let mut ending_position = None;
-        for (row, line) in s.lines().enumerate() {
-            for (col, c) in line.chars().enumerate() {
-                let position = Position {
-                    row: row.try_into()?,
-                    col: col.try_into()?,
-                };
-                if c == 'S' {
-                    starting_position = Some(position);
-                } else if c == 'E' {
-                    ending_position = Some(position);
-                }
-                map.insert(position, height(c)?);
+        let heights = Grid::parse(s, |position, c| {
+            if c == 'S' {
+                starting_position = Some(position);
+            } else if c == 'E' {
+                ending_position = Some(position);
             }
-        }
+            height(c)
+        })?;
         Ok(Map {
-            map,
+            heights,
             starting_position: starting_position.ok_or_else(|| anyhow!("no starting position"))?,
             ending_position: ending_position.ok_or_else(|| anyhow!("no ending position"))?,
         })
     }
 }
 
-impl Position {
-    fn neighbors(&self) -> [Position; 4] {
-        [
-            Position {
-                row: self.row - 1,
-                col: self.col,
-            },
-            Position {
-                row: self.row + 1,
-                col: self.col,
-            },
-            Position {
-                row: self.row,
-                col: self.col - 1,
-            },
-            Position {
-                row: self.row,
-                col: self.col + 1,
-            },
-        ]
-    }
-}
-
 fn height(mut c: char) -> Result<i8> {
     if c == 'S' {
         return Ok(0);
     } else if c == 'E' {
         c = 'z'
+    } else if !c.is_ascii_lowercase() {
+        return Err(anyhow!("unexpected height: {}", c));
     }
     (u32::from(c) - u32::from('a'))
         .try_into()
         .map_err(Error::from)
 }
 
-#[test]
-fn part_1() {
-    let input = "Sabqponm
+// Heights rise evenly from S in the top left to E in the bottom right. The top row and right
+// column are left as they are so there's always a path, and every other square is lowered by a
+// random amount.
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let width = size.max(14);
+    let height = size.max(14);
+    let steps = width + height - 2;
+    let mut input = String::new();
+    for y in 0..height {
+        for x in 0..width {
+            let level = 25 * (x + y) / steps;
+            let c = if (x, y) == (0, 0) {
+                'S'
+            } else if (x, y) == (width - 1, height - 1) {
+                'E'
+            } else if y == 0 || x == width - 1 {
+                char::from(b'a' + level as u8)
+            } else {
+                char::from(b'a' + level.saturating_sub(rng.below(4)) as u8)
+            };
+            input.push(c);
+        }
+        input.push('\n');
+    }
+    input
+}
+
+// A separate breadth-first search from every possible start.
+fn naive_shortest_path_length(input: &str, from_any_base: bool) -> Result<usize> {
+    let map: Map = input.parse()?;
+    let starts: Vec<Point> = if from_any_base {
+        map.heights
+            .iter()
+            .filter(|(_, &height)| height == 0)
+            .map(|(point, _)| point)
+            .collect()
+    } else {
+        vec![map.starting_position]
+    };
+    starts
+        .into_iter()
+        .filter_map(|start| {
+            let mut seen = HashSet::from([start]);
+            let mut queue = VecDeque::from([(start, 0)]);
+            while let Some((position, length)) = queue.pop_front() {
+                if position == map.ending_position {
+                    return Some(length);
+                }
+                for neighbor in position.neighbors4() {
+                    if let (Some(from), Some(to)) =
+                        (map.heights.get(position), map.heights.get(neighbor))
+                    {
+                        if to - from <= 1 && seen.insert(neighbor) {
+                            queue.push_back((neighbor, length + 1));
+                        }
+                    }
+                }
+            }
+            None
+        })
+        .min()
+        .ok_or_else(|| anyhow!("no paths found"))
+}
+
+pub const EXAMPLE: &str = "Sabqponm
 abcryxxl
 accszExk
 acctuvwj
 abdefghi";
-    assert_eq!(length_of_shortest_path(input).unwrap(), 31);
+
+pub const EXAMPLES: [Example; 1] = [Example {
+    name: "example",
+    input: EXAMPLE,
+    parameters: &[],
+    part1: Some("31"),
+    part2: Some("29"),
+}];
+
+#[test]
+fn part_1() {
+    let input = EXAMPLE;
+    assert_eq!(
+        length_of_shortest_path(&input.parse().unwrap()).unwrap(),
+        31
+    );
 }
 
 #[test]
 fn part_2() {
-    let input = "Sabqponm
-abcryxxl
-accszExk
-acctuvwj
-abdefghi";
-    assert_eq!(length_of_shortest_path_from_base_height(input).unwrap(), 29);
+    let input = EXAMPLE;
+    assert_eq!(
+        length_of_shortest_path_from_base_height(&input.parse().unwrap()).unwrap(),
+        29
+    );
+}
+
+#[test]
+fn errors() {
+    use crate::solution::Solver;
+    let errors = HillClimbingAlgorithm.check("SaB\nAbE");
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].to_string().starts_with("line 1, column 3"));
+    assert!(errors[1].to_string().starts_with("line 2, column 1"));
 }
@@ -1,26 +1,77 @@
+use crate::{
+    solution::{Example, Solution},
+    trace::{self, Level},
+};
+use advent_of_code_2022::{error, rng::Rng, SpanContext};
 use anyhow::{anyhow, Error, Result};
 use std::{
     cmp::Ordering,
+    collections::VecDeque,
     iter::Peekable,
-    str::{Chars, FromStr},
+    str::{CharIndices, FromStr},
 };
 
-fn main() -> Result<()> {
-    let input = include_str!("13.txt");
-    println!("Part 1: {}", sum_of_indices_in_correct_order(input)?);
-    println!("Part 2: {}", decoder_key(input)?);
-    Ok(())
+#[derive(Debug, Clone)]
+pub struct DistressSignal;
+
+impl Solution for DistressSignal {
+    type Input = Vec<(Packet, Packet)>;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(&self, input: &str) -> Result<Vec<(Packet, Packet)>> {
+        pairs(input)
+    }
+
+    fn part1(&self, pairs: &Vec<(Packet, Packet)>) -> Result<usize> {
+        Ok(sum_of_indices_in_correct_order(pairs))
+    }
+
+    fn part2(&self, pairs: &Vec<(Packet, Packet)>) -> Result<usize> {
+        decoder_key(pairs)
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+
+    fn reference1(&self, input: &str) -> Result<usize> {
+        let packets = naive_packets(input);
+        if !packets.len().is_multiple_of(2) {
+            return Err(anyhow!("odd number of packets"));
+        }
+        let mut sum = 0;
+        for (i, pair) in packets.chunks(2).enumerate() {
+            if naive_cmp(pair[0], pair[1])? == Ordering::Less {
+                sum += i + 1;
+            }
+        }
+        Ok(sum)
+    }
+
+    fn reference2(&self, input: &str) -> Result<usize> {
+        // Counts the packets that sort before each divider instead of sorting them all.
+        let mut positions = [1, 2];
+        for packet in naive_packets(input) {
+            for (divider, position) in ["[[2]]", "[[6]]"].into_iter().zip(&mut positions) {
+                match naive_cmp(packet, divider)? {
+                    Ordering::Less => *position += 1,
+                    Ordering::Equal => return Err(anyhow!("divider packet in input: {}", packet)),
+                    Ordering::Greater => (),
+                }
+            }
+        }
+        Ok(positions[0] * positions[1])
+    }
 }
 
-fn decoder_key(input: &str) -> Result<usize> {
+fn decoder_key(pairs: &[(Packet, Packet)]) -> Result<usize> {
     let first: Packet = "[[2]]".parse()?;
     let second: Packet = "[[6]]".parse()?;
     let mut packets: Vec<Packet> = vec![first.clone(), second.clone()];
-    for line in input.lines() {
-        if line.is_empty() {
-            continue;
-        }
-        packets.push(line.parse()?);
+    for (left, right) in pairs {
+        packets.push(left.clone());
+        packets.push(right.clone());
     }
     packets.sort();
     let mut first_position = None;
@@ -32,30 +83,50 @@ fn decoder_key(input: &str) -> Result<usize> {
             second_position = Some(i + 1);
         }
     }
-    Ok(first_position.unwrap() * second_position.unwrap())
+    let first_position = first_position.ok_or_else(|| anyhow!("first divider packet missing"))?;
+    let second_position =
+        second_position.ok_or_else(|| anyhow!("second divider packet missing"))?;
+    trace::event(
+        Level::Summary,
+        "dividers",
+        &[("first", &first_position), ("second", &second_position)],
+    );
+    Ok(first_position * second_position)
 }
 
-fn sum_of_indices_in_correct_order(input: &str) -> Result<usize> {
-    let mut sum = 0;
-    for (i, lines) in input.split("\n\n").enumerate() {
+fn pairs(input: &str) -> Result<Vec<(Packet, Packet)>> {
+    error::collect(input.split("\n\n").map(|lines| {
         let mut iter = lines.lines();
         let left: Packet = iter
             .next()
-            .ok_or_else(|| anyhow!("missing first packet"))?
+            .ok_or_else(|| error::at(lines, "missing first packet"))?
             .parse()?;
         let right: Packet = iter
             .next()
-            .ok_or_else(|| anyhow!("missing second packet"))?
+            .ok_or_else(|| error::at(&lines[lines.len()..], "missing second packet"))?
             .parse()?;
-        if left < right {
+        Ok((left, right))
+    }))
+}
+
+fn sum_of_indices_in_correct_order(pairs: &[(Packet, Packet)]) -> usize {
+    let mut sum = 0;
+    for (i, (left, right)) in pairs.iter().enumerate() {
+        let ordered = left < right;
+        if ordered {
             sum += i + 1;
         }
+        trace::event(
+            Level::Steps,
+            "pair",
+            &[("pair", &(i + 1)), ("ordered", &ordered), ("sum", &sum)],
+        );
     }
-    Ok(sum)
+    sum
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-struct Packet(Vec<Value>);
+pub struct Packet(Vec<Value>);
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 enum Value {
@@ -79,11 +150,11 @@ impl FromStr for Packet {
     type Err = Error;
     fn from_str(s: &str) -> Result<Packet> {
         if !s.starts_with('[') {
-            Err(anyhow!("packet must start with a '["))
+            Err(error::at(s, "packet must start with a '["))
         } else if !s.ends_with(']') {
-            Err(anyhow!("packet must end with a ']'"))
+            Err(error::at(&s[s.len()..], "packet must end with a ']'"))
         } else {
-            let list = parse_list(&mut s.chars().peekable())?;
+            let list = parse_list(s, &mut s.char_indices().peekable())?;
             Ok(Packet(list))
         }
     }
@@ -111,50 +182,52 @@ impl Ord for Value {
     }
 }
 
-fn parse_list(chars: &mut Peekable<Chars<'_>>) -> Result<Vec<Value>> {
+fn parse_list(s: &str, chars: &mut Peekable<CharIndices<'_>>) -> Result<Vec<Value>> {
     use Value::*;
     let mut values = Vec::new();
-    while let Some(peeked) = chars.peek() {
-        if *peeked == ']' {
+    while let Some(&(_, peeked)) = chars.peek() {
+        if peeked == ']' {
             break;
-        } else if *peeked == '[' {
+        } else if peeked == '[' {
             let _ = chars.next().unwrap();
-            let list = parse_list(chars)?;
-            let c = chars
+            let list = parse_list(s, chars)?;
+            let (i, c) = chars
                 .next()
-                .ok_or_else(|| anyhow!("unexpected end of input in parse_list"))?;
+                .ok_or_else(|| error::at(&s[s.len()..], "unexpected end of input in parse_list"))?;
             if c != ']' {
-                return Err(anyhow!("unclosed list"));
+                return Err(error::at(&s[i..], "unclosed list"));
             }
             values.push(List(list));
-        } else if *peeked == ',' {
+        } else if peeked == ',' {
             let _ = chars.next().unwrap();
         } else {
-            let integer = parse_integer(chars)?;
+            let integer = parse_integer(s, chars)?;
             values.push(Integer(integer));
         }
     }
     Ok(values)
 }
 
-fn parse_integer(chars: &mut Peekable<Chars<'_>>) -> Result<i64> {
-    let mut value = String::new();
-    while let Some(peeked) = chars.peek() {
-        if *peeked == ']' || *peeked == ',' {
+fn parse_integer(s: &str, chars: &mut Peekable<CharIndices<'_>>) -> Result<i64> {
+    let start = chars.peek().map(|&(i, _)| i).unwrap_or(s.len());
+    let mut end = start;
+    while let Some(&(_, peeked)) = chars.peek() {
+        if peeked == ']' || peeked == ',' {
             break;
         } else {
-            let c = chars.next().unwrap();
-            value.push(c);
+            let (i, c) = chars.next().unwrap();
+            end = i + c.len_utf8();
         }
     }
-    value.parse().map_err(Error::from)
+    let value = &s[start..end];
+    value.parse().at(value)
 }
 
 fn cmp_list(left: &[Value], right: &[Value]) -> Ordering {
     use Ordering::*;
-    let mut left_iter = left.iter();
+    let left_iter = left.iter();
     let mut right_iter = right.iter();
-    while let Some(left) = left_iter.next() {
+    for left in left_iter {
         if let Some(right) = right_iter.next() {
             let ordering = left.cmp(right);
             if !matches!(ordering, Equal) {
@@ -171,6 +244,120 @@ fn cmp_list(left: &[Value], right: &[Value]) -> Ordering {
     }
 }
 
+fn generate(rng: &mut Rng, size: usize) -> String {
+    fn packet(rng: &mut Rng, depth: usize) -> String {
+        let items: Vec<String> = (0..rng.below(5))
+            .map(|_| {
+                if depth < 4 && rng.chance(1, 3) {
+                    packet(rng, depth + 1)
+                } else {
+                    rng.range(0..=10).to_string()
+                }
+            })
+            .collect();
+        format!("[{}]", items.join(","))
+    }
+
+    let pairs: Vec<String> = (0..size.max(1))
+        .map(|_| format!("{}\n{}", packet(rng, 0), packet(rng, 0)))
+        .collect();
+    pairs.join("\n\n") + "\n"
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Integer(i64),
+}
+
+fn naive_packets(input: &str) -> Vec<&str> {
+    input.lines().filter(|line| !line.is_empty()).collect()
+}
+
+fn naive_tokens(packet: &str) -> Result<VecDeque<Token>> {
+    let mut tokens = VecDeque::new();
+    let mut digits = String::new();
+    for c in packet.chars().chain([',']) {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        if !digits.is_empty() {
+            tokens.push_back(Token::Integer(digits.parse()?));
+            digits.clear();
+        }
+        match c {
+            '[' => tokens.push_back(Token::Open),
+            ']' => tokens.push_back(Token::Close),
+            ',' => (),
+            _ => return Err(anyhow!("unexpected character in packet: {}", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+// Compares packets a token at a time straight from the text, wrapping an integer in brackets
+// whenever it meets a list.
+fn naive_cmp(left: &str, right: &str) -> Result<Ordering> {
+    use Token::*;
+    let mut left = naive_tokens(left)?;
+    let mut right = naive_tokens(right)?;
+    loop {
+        match (left.pop_front(), right.pop_front()) {
+            (None, None) => return Ok(Ordering::Equal),
+            (Some(Open), Some(Open)) | (Some(Close), Some(Close)) => (),
+            (None, _) | (Some(Close), _) => return Ok(Ordering::Less),
+            (_, None) | (_, Some(Close)) => return Ok(Ordering::Greater),
+            (Some(Integer(a)), Some(Integer(b))) => {
+                if a != b {
+                    return Ok(a.cmp(&b));
+                }
+            }
+            (Some(Integer(a)), Some(Open)) => {
+                left.push_front(Close);
+                left.push_front(Integer(a));
+            }
+            (Some(Open), Some(Integer(b))) => {
+                right.push_front(Close);
+                right.push_front(Integer(b));
+            }
+        }
+    }
+}
+
+pub const EXAMPLE: &str = "[1,1,3,1,1]
+[1,1,5,1,1]
+
+[[1],[2,3,4]]
+[[1],4]
+
+[9]
+[[8,7,6]]
+
+[[4,4],4,4]
+[[4,4],4,4,4]
+
+[7,7,7,7]
+[7,7,7]
+
+[]
+[3]
+
+[[[]]]
+[[]]
+
+[1,[2,[3,[4,[5,6,7]]]],8,9]
+[1,[2,[3,[4,[5,6,0]]]],8,9]";
+
+pub const EXAMPLES: [Example; 1] = [Example {
+    name: "example",
+    input: EXAMPLE,
+    parameters: &[],
+    part1: Some("13"),
+    part2: Some("140"),
+}];
+
 #[test]
 fn part_1_1() {
     let left: Packet = "[1,1,3,1,1]".parse().unwrap();
@@ -229,37 +416,12 @@ fn part_1_8() {
 
 #[test]
 fn part_1() {
-    assert_eq!(sum_of_indices_in_correct_order(test_input()).unwrap(), 13);
+    let pairs = pairs(EXAMPLE).unwrap();
+    assert_eq!(sum_of_indices_in_correct_order(&pairs), 13);
 }
 
 #[test]
 fn part_2() {
-    assert_eq!(decoder_key(test_input()).unwrap(), 140);
-}
-
-#[cfg(test)]
-fn test_input() -> &'static str {
-    "[1,1,3,1,1]
-[1,1,5,1,1]
-
-[[1],[2,3,4]]
-[[1],4]
-
-[9]
-[[8,7,6]]
-
-[[4,4],4,4]
-[[4,4],4,4,4]
-
-[7,7,7,7]
-[7,7,7]
-
-[]
-[3]
-
-[[[]]]
-[[]]
-
-[1,[2,[3,[4,[5,6,7]]]],8,9]
-[1,[2,[3,[4,[5,6,0]]]],8,9]"
+    let pairs = pairs(EXAMPLE).unwrap();
+    assert_eq!(decoder_key(&pairs).unwrap(), 140);
 }
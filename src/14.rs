@@ -1,91 +1,313 @@
+use crate::{
+    animation::Animation,
+    solution::{Example, Solution},
+    trace::{self, Level},
+};
+use advent_of_code_2022::{
+    error,
+    geometry::{BoundingBox, Point, Vector},
+    grid::Grid,
+    image::{gray, Image, Rgb, BLACK},
+    rng::Rng,
+    SpanContext,
+};
 use anyhow::{anyhow, Error, Result};
-use std::{collections::HashMap, fmt::Display, str::FromStr};
+use std::{collections::HashSet, convert::Infallible, fmt::Display, str::FromStr};
 
-const STARTING_POSITION: Position = Position { x: 500, y: 0 };
+const STARTING_POSITION: Point = Point::new(500, 0);
+const FALLS: [Vector; 3] = [Vector::new(0, 1), Vector::new(-1, 1), Vector::new(1, 1)];
+const SAND: Rgb = [230, 190, 80];
 
-fn main() -> Result<()> {
-    let input = include_str!("14.txt");
-    let mut cave: Cave = input.parse()?;
-    cave.simulate(false);
-    println!("Part 1: {}", cave.iter_sand().count());
-    let mut cave: Cave = input.parse()?;
-    cave.simulate(true);
-    println!("Part 2: {}", cave.iter_sand().count());
-    Ok(())
+// The floor's pile holds about the square of its depth in grains, each falling about that far,
+// so the time taken grows with the cube of the depth.
+const MAX_DEPTH: i64 = 1000;
+
+#[derive(Debug, Clone)]
+pub struct RegolithReservoir {
+    // Where the sand pours in.
+    pub source: Point,
+}
+
+impl RegolithReservoir {
+    pub const DEFAULT: RegolithReservoir = RegolithReservoir {
+        source: STARTING_POSITION,
+    };
+}
+
+impl Solution for RegolithReservoir {
+    type Input = Cave;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(&self, input: &str) -> Result<Cave> {
+        Cave::new(input, self.source)
+    }
+
+    fn part1(&self, cave: &Cave) -> Result<usize> {
+        let mut cave = cave.clone();
+        cave.simulate(false);
+        Ok(cave.iter_sand().count())
+    }
+
+    fn part2(&self, cave: &Cave) -> Result<usize> {
+        let mut cave = cave.clone();
+        cave.simulate(true);
+        Ok(cave.iter_sand().count())
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+
+    fn reference1(&self, input: &str) -> Result<usize> {
+        naive_resting_sand(input, self.source, false)
+    }
+
+    fn reference2(&self, input: &str) -> Result<usize> {
+        naive_resting_sand(input, self.source, true)
+    }
+
+    // The cave after each part, without and then with the floor.
+    fn state(&self, cave: &Cave) -> Result<Option<String>> {
+        let mut without_floor = cave.clone();
+        without_floor.simulate(false);
+        let mut with_floor = cave.clone();
+        with_floor.simulate(true);
+        Ok(Some(format!("{}\n{}", without_floor, with_floor)))
+    }
+
+    fn render(&self, cave: &Cave) -> Result<Option<Image>> {
+        let mut cave = cave.clone();
+        cave.simulate(true);
+        cave.render().map(Some)
+    }
+
+    fn animate(&self, cave: &Cave, animation: &mut Animation) -> Result<bool> {
+        let mut cave = cave.clone();
+        cave.simulate_with(true, |cave, falling| {
+            animation.frame(cave.bounds(), falling, |point| cave.cell(point, falling))
+        })?;
+        animation.finish(cave.bounds(), cave.source, |point| {
+            cave.cell(point, cave.source)
+        })?;
+        Ok(true)
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![("source", format!("{},{}", self.source.x, self.source.y))]
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        match name {
+            "source" => {
+                self.source =
+                    point(value).map_err(|_| anyhow!("invalid value for {}: {}", name, value))?
+            }
+            _ => return Err(anyhow!("unknown parameter: {}", name)),
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
-struct Cave {
-    positions: HashMap<Position, bool>,
+#[derive(Debug, Clone)]
+pub struct Cave {
+    tiles: Grid<Tile>,
+    origin: Vector,
+    source: Point,
     min_x: i64,
     max_x: i64,
     max_y: i64,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
-struct Position {
-    x: i64,
-    y: i64,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Air,
+    Rock,
+    Sand,
 }
 
 impl Cave {
+    fn new(input: &str, source: Point) -> Result<Cave> {
+        let paths: Vec<Vec<(Point, Point)>> = error::collect(input.lines().map(path))?;
+        let lines: Vec<(Point, Point)> = paths.into_iter().flatten().collect();
+        let bounds = BoundingBox::from_points(lines.iter().flat_map(|&(start, end)| [start, end]))
+            .ok_or_else(|| anyhow!("no rock paths"))?;
+        if bounds.min.y < source.y {
+            return Err(anyhow!("rock above the sand source at y={}", bounds.min.y));
+        }
+        let too_big = || anyhow!("cave is too big: {} to {}", bounds.min, bounds.max);
+        // Sand comes to rest at most one tile above the floor, so it can't spread further than
+        // that from the source in either direction.
+        let spread = bounds
+            .max
+            .y
+            .checked_sub(source.y)
+            .and_then(|depth| depth.checked_add(2))
+            .filter(|&spread| spread <= MAX_DEPTH)
+            .ok_or_else(|| {
+                anyhow!(
+                    "cave is too deep: rock at y={} is more than {} below the source",
+                    bounds.max.y,
+                    MAX_DEPTH - 2
+                )
+            })?;
+        let min_x = bounds
+            .min
+            .x
+            .min(source.x.checked_sub(spread).ok_or_else(too_big)?);
+        let max_x = bounds
+            .max
+            .x
+            .max(source.x.checked_add(spread).ok_or_else(too_big)?);
+        let width = max_x
+            .checked_sub(min_x)
+            .and_then(|width| width.checked_add(1))
+            .ok_or_else(too_big)?;
+        let mut cave = Cave {
+            tiles: Grid::new(
+                width.try_into()?,
+                spread.checked_add(1).ok_or_else(too_big)?.try_into()?,
+                Tile::Air,
+            )?,
+            origin: Vector::new(min_x, source.y),
+            source,
+            min_x: bounds.min.x,
+            max_x: bounds.max.x,
+            max_y: bounds.max.y,
+        };
+        for rock in lines
+            .into_iter()
+            .flat_map(|(start, end)| start.line_to(end))
+        {
+            if let Some(tile) = cave.tiles.get_mut(rock - cave.origin) {
+                *tile = Tile::Rock;
+            }
+        }
+        Ok(cave)
+    }
+
     fn simulate(&mut self, with_floor: bool) {
-        let mut sand = STARTING_POSITION;
+        let Ok(()) = self.simulate_with::<Infallible>(with_floor, |_, _| Ok(()));
+        if trace::enabled(Level::Summary) {
+            trace::event(
+                Level::Summary,
+                "rested",
+                &[("count", &self.iter_sand().count())],
+            );
+        }
+        trace::event(Level::State, "cave", &[("cave", self)]);
+    }
+
+    // Calls `step` with the falling grain each time it moves or comes to rest.
+    fn simulate_with<E>(
+        &mut self,
+        with_floor: bool,
+        mut step: impl FnMut(&Cave, Point) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut sand = self.source;
         loop {
-            if self.positions.contains_key(&STARTING_POSITION) {
+            if self.is_blocked(self.source) {
                 break;
             }
-            sand = self.simulate_one(sand);
+            let falling = self.simulate_one(sand);
+            step(
+                self,
+                if falling == self.source {
+                    sand
+                } else {
+                    falling
+                },
+            )?;
+            sand = falling;
             if with_floor {
-                if sand.y >= self.max_y + 1 {
+                if sand.y > self.max_y {
                     sand = self.insert_sand(sand);
                 }
             } else if sand.y > self.max_y {
                 break;
             }
         }
+        Ok(())
     }
 
-    fn simulate_one(&mut self, sand: Position) -> Position {
-        let below = Position {
-            x: sand.x,
-            y: sand.y + 1,
-        };
-        if !self.positions.contains_key(&below) {
-            return below;
-        }
-        let down_left = Position {
-            x: sand.x - 1,
-            y: sand.y + 1,
-        };
-        if !self.positions.contains_key(&down_left) {
-            return down_left;
-        }
-        let down_right = Position {
-            x: sand.x + 1,
-            y: sand.y + 1,
-        };
-        if !self.positions.contains_key(&down_right) {
-            return down_right;
+    fn simulate_one(&mut self, sand: Point) -> Point {
+        for fall in FALLS {
+            if !self.is_blocked(sand + fall) {
+                return sand + fall;
+            }
         }
         self.insert_sand(sand)
     }
 
-    fn insert_sand(&mut self, sand: Position) -> Position {
+    fn insert_sand(&mut self, sand: Point) -> Point {
         if sand.x < self.min_x {
             self.min_x = sand.x;
         }
         if sand.x > self.max_x {
             self.max_x = sand.x;
         }
-        self.positions.insert(sand, true);
-        STARTING_POSITION
+        if let Some(tile) = self.tiles.get_mut(sand - self.origin) {
+            *tile = Tile::Sand;
+        }
+        trace::event(Level::Steps, "rest", &[("sand", &sand)]);
+        self.source
     }
 
-    fn iter_sand(&self) -> impl Iterator<Item = &Position> {
-        self.positions
+    fn iter_sand(&self) -> impl Iterator<Item = Point> + '_ {
+        self.tiles
             .iter()
-            .filter_map(|(position, &is_sand)| if is_sand { Some(position) } else { None })
+            .filter(|(_, &tile)| tile == Tile::Sand)
+            .map(|(point, _)| point + self.origin)
+    }
+
+    // Rock in grey and sand in yellow, with the floor along the bottom.
+    fn render(&self) -> Result<Image> {
+        let mut image = Image::from_grid(&self.tiles, |tile| match tile {
+            Tile::Air => BLACK,
+            Tile::Rock => gray(128),
+            Tile::Sand => SAND,
+        });
+        for x in 0..image.width() {
+            image.set(
+                Point::new(x as i64, self.max_y + 2 - self.origin.y),
+                gray(128),
+            );
+        }
+        image.scale(2)
+    }
+
+    // The part of the cave the sand has reached so far, down to the floor.
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox {
+            min: Point::new(self.min_x - 1, self.source.y),
+            max: Point::new(self.max_x + 1, self.max_y + 2),
+        }
+    }
+
+    fn cell(&self, point: Point, falling: Point) -> char {
+        if point == falling {
+            'o'
+        } else if point.y == self.max_y + 2 {
+            '#'
+        } else {
+            match self.tile(point) {
+                Tile::Air if point == self.source => '+',
+                Tile::Air => '.',
+                Tile::Rock => '#',
+                Tile::Sand => 'o',
+            }
+        }
+    }
+
+    fn is_blocked(&self, position: Point) -> bool {
+        self.tile(position) != Tile::Air
+    }
+
+    fn tile(&self, position: Point) -> Tile {
+        self.tiles
+            .get(position - self.origin)
+            .copied()
+            .unwrap_or(Tile::Air)
     }
 }
 
@@ -93,121 +315,221 @@ impl FromStr for Cave {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Cave> {
-        let mut map = HashMap::new();
-        let mut min_x = i64::MAX;
-        let mut max_x = i64::MIN;
-        let mut max_y = i64::MIN;
-        for line in s.lines() {
-            let positions = line
-                .split(" -> ")
-                .map(|s| s.parse::<Position>())
-                .collect::<Result<Vec<_>>>()?;
-            for (start, end) in positions.iter().zip(positions.iter().skip(1)) {
-                for position in start.line_to(end)? {
-                    if position.x < min_x {
-                        min_x = position.x;
-                    }
-                    if position.x > max_x {
-                        max_x = position.x;
-                    }
-                    if position.y > max_y {
-                        max_y = position.y;
-                    }
-                    map.insert(position, false);
-                }
-            }
+        Cave::new(s, STARTING_POSITION)
+    }
+}
+
+// The straight lines between each corner and the next, which are only drawn once the cave is known
+// to be small enough to hold them.
+fn path(line: &str) -> Result<Vec<(Point, Point)>> {
+    let segments: Vec<_> = line.split(" -> ").collect();
+    let points = segments
+        .iter()
+        .map(|s| point(s))
+        .collect::<Result<Vec<_>>>()?;
+    let mut path = Vec::new();
+    for (i, (&start, &end)) in points.iter().zip(points.iter().skip(1)).enumerate() {
+        if start.x != end.x && start.y != end.y {
+            return Err(error::at(
+                segments[i],
+                format!("diagonal line: {:?} -> {:?}", start, end),
+            ));
         }
-        Ok(Cave {
-            positions: map,
-            min_x,
-            max_x,
-            max_y,
-        })
+        path.push((start, end));
     }
+    Ok(path)
+}
+
+fn point(s: &str) -> Result<Point> {
+    let mut iter = s.split(',');
+    let x = iter
+        .next()
+        .ok_or_else(|| error::at(s, "no x coordinate"))
+        .and_then(|s| s.parse::<i64>().at(s))?;
+    let y = iter
+        .next()
+        .ok_or_else(|| error::at(&s[s.len()..], "no y coordinate"))
+        .and_then(|s| s.parse::<i64>().at(s))?;
+    Ok(Point::new(x, y))
 }
 
 impl Display for Cave {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for y in 0..=(self.max_y + 2) {
+        for y in self.source.y..=(self.max_y + 2) {
             for x in self.min_x..=self.max_x {
-                let position = Position { x, y };
-                if let Some(&is_sand) = self.positions.get(&position) {
-                    if is_sand {
-                        write!(f, "o")?;
-                    } else {
-                        write!(f, "#")?;
-                    }
-                } else {
-                    write!(f, ".")?;
-                }
+                write!(f, "{}", self.tile(Point::new(x, y)))?;
             }
-            writeln!(f, "")?;
+            writeln!(f)?;
         }
         Ok(())
     }
 }
 
-impl Position {
-    fn line_to(&self, other: &Position) -> Result<Vec<Position>> {
-        let mut line = Vec::new();
-        if self.y == other.y {
-            let (start, end) = if self.x > other.x {
-                (other.x, self.x)
+impl Display for Tile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tile::Air => write!(f, "."),
+            Tile::Rock => write!(f, "#"),
+            Tile::Sand => write!(f, "o"),
+        }
+    }
+}
+
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let depth = 10 + size as i64;
+    let mut input = String::new();
+    for _ in 0..size.max(1) {
+        let mut point = Point::new(rng.range(480..=520), rng.range(2..=depth));
+        let mut points = vec![format!("{},{}", point.x, point.y)];
+        for i in 0..rng.below(4) + 1 {
+            let length = rng.range(1..=8) * if rng.chance(1, 2) { 1 } else { -1 };
+            if i % 2 == 0 {
+                point.x += length;
             } else {
-                (self.x, other.x)
-            };
-            for x in start..(end + 1) {
-                line.push(Position { x, y: self.y });
+                point.y = (point.y + length).max(1);
             }
-        } else if self.x == other.x {
-            let (start, end) = if self.y > other.y {
-                (other.y, self.y)
+            points.push(format!("{},{}", point.x, point.y));
+        }
+        input.push_str(&points.join(" -> "));
+        input.push('\n');
+    }
+    input
+}
+
+// Drops one grain at a time through a set of blocked points.
+fn naive_resting_sand(input: &str, source: Point, with_floor: bool) -> Result<usize> {
+    let mut blocked = HashSet::new();
+    for line in input.lines() {
+        let points = line
+            .split(" -> ")
+            .map(|s| {
+                let (x, y) = s
+                    .split_once(',')
+                    .ok_or_else(|| anyhow!("invalid point: {}", s))?;
+                Ok((x.parse::<i64>()?, y.parse::<i64>()?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for pair in points.windows(2) {
+            let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+            if x1 != x2 && y1 != y2 {
+                return Err(anyhow!("diagonal line: {}", line));
+            }
+            for x in x1.min(x2)..=x1.max(x2) {
+                for y in y1.min(y2)..=y1.max(y2) {
+                    let _ = blocked.insert((x, y));
+                }
+            }
+        }
+    }
+    let min_y = blocked.iter().map(|&(_, y)| y).min();
+    let floor = match (min_y, blocked.iter().map(|&(_, y)| y).max()) {
+        (Some(min_y), Some(max_y)) if min_y >= source.y => max_y + 2,
+        _ => return Err(anyhow!("no rock below the source")),
+    };
+    let rocks = blocked.len();
+    while !blocked.contains(&(source.x, source.y)) {
+        let (mut x, mut y) = (source.x, source.y);
+        loop {
+            if y + 1 == floor {
+                if with_floor {
+                    break;
+                } else {
+                    return Ok(blocked.len() - rocks);
+                }
+            }
+            if let Some(dx) = [0, -1, 1]
+                .into_iter()
+                .find(|&dx| !blocked.contains(&(x + dx, y + 1)))
+            {
+                x += dx;
+                y += 1;
             } else {
-                (self.y, other.y)
-            };
-            for y in start..(end + 1) {
-                line.push(Position { x: self.x, y });
+                break;
             }
-        } else {
-            return Err(anyhow!("diagonal line: {:?} -> {:?}", self, other));
         }
-        Ok(line)
+        let _ = blocked.insert((x, y));
     }
+    Ok(blocked.len() - rocks)
 }
 
-impl FromStr for Position {
-    type Err = Error;
+pub const EXAMPLE: &str = "498,4 -> 498,6 -> 496,6
+503,4 -> 502,4 -> 502,9 -> 494,9";
 
-    fn from_str(s: &str) -> Result<Position> {
-        let mut iter = s.split(',');
-        let x = iter
-            .next()
-            .ok_or_else(|| anyhow!("no x coordinate"))
-            .and_then(|s| s.parse::<i64>().map_err(Error::from))?;
-        let y = iter
-            .next()
-            .ok_or_else(|| anyhow!("no y coordinate"))
-            .and_then(|s| s.parse::<i64>().map_err(Error::from))?;
-        Ok(Position { x, y })
-    }
-}
+pub const EXAMPLES: [Example; 1] = [Example {
+    name: "example",
+    input: EXAMPLE,
+    parameters: &[],
+    part1: Some("24"),
+    part2: Some("93"),
+}];
 
 #[test]
 fn part_1() {
-    let mut cave: Cave = test_input().parse().unwrap();
+    let mut cave: Cave = EXAMPLE.parse().unwrap();
     cave.simulate(false);
     assert_eq!(cave.iter_sand().count(), 24);
 }
 
 #[test]
 fn part_2() {
-    let mut cave: Cave = test_input().parse().unwrap();
+    let mut cave: Cave = EXAMPLE.parse().unwrap();
     cave.simulate(true);
     assert_eq!(cave.iter_sand().count(), 93);
 }
 
-#[cfg(test)]
-fn test_input() -> &'static str {
-    "498,4 -> 498,6 -> 496,6
-503,4 -> 502,4 -> 502,9 -> 494,9"
+#[test]
+fn huge_cave() {
+    let err = RegolithReservoir::DEFAULT
+        .parse("4000000000,4 -> 4000000000,4")
+        .unwrap_err();
+    assert!(err.to_string().starts_with("grid is too big"), "{}", err);
+    let err = RegolithReservoir::DEFAULT
+        .parse("500,4000000000 -> 500,4000000000")
+        .unwrap_err();
+    assert!(err.to_string().starts_with("cave is too deep"), "{}", err);
+    let err = RegolithReservoir::DEFAULT
+        .parse("500,9223372036854775807 -> 500,9223372036854775806")
+        .unwrap_err();
+    assert!(err.to_string().starts_with("cave is too deep"), "{}", err);
+    let err = RegolithReservoir::DEFAULT
+        .parse("-9223372036854775807,4 -> 9223372036854775807,4")
+        .unwrap_err();
+    assert!(err.to_string().starts_with("cave is too big"), "{}", err);
+}
+
+#[test]
+fn state() {
+    let cave: Cave = EXAMPLE.parse().unwrap();
+    let state = RegolithReservoir::DEFAULT.state(&cave).unwrap().unwrap();
+    crate::snapshot::assert_snapshot("14-cave", &state);
+}
+
+#[test]
+fn render() {
+    let cave: Cave = EXAMPLE.parse().unwrap();
+    let image = RegolithReservoir::DEFAULT.render(&cave).unwrap().unwrap();
+    let sand = (0..image.height() as i64)
+        .flat_map(|y| (0..image.width() as i64).map(move |x| Point::new(x, y)))
+        .filter(|&point| image.get(point) == Some(SAND))
+        .count();
+    assert_eq!(sand, 93 * 4);
+}
+
+#[test]
+fn parameters() {
+    let mut solution = RegolithReservoir::DEFAULT;
+    solution.set("source", "497,2").unwrap();
+    let cave = solution.parse(EXAMPLE).unwrap();
+    let answers = (
+        solution.part1(&cave).unwrap(),
+        solution.part2(&cave).unwrap(),
+    );
+    assert_eq!(answers.0, solution.reference1(EXAMPLE).unwrap());
+    assert_eq!(answers.1, solution.reference2(EXAMPLE).unwrap());
+    assert_eq!(answers.1, 50);
+    solution.set("source", "500,5").unwrap();
+    assert!(solution.parse(EXAMPLE).is_err());
+    solution.set("source", "500,-1000000").unwrap();
+    assert!(solution.parse(EXAMPLE).is_err());
+    assert!(solution.set("source", "500").is_err());
 }
@@ -1,89 +1,297 @@
-use anyhow::{anyhow, Error, Result};
-use std::{collections::HashMap, ops::RangeInclusive};
-
-fn main() -> Result<()> {
-    let input = include_str!("15.txt");
-    let map = Map::new(input, 4_000_000)?;
-    println!(
-        "Part 1: {}",
-        map.number_of_positions_without_beacon_in_row(2_000_000)
-    );
-    println!(
-        "Part 2: {}",
-        map.distress_beacon_tuning_frequency(4_000_000)?
-    );
-    Ok(())
+use crate::{
+    solution::{self, Example, Solution},
+    trace::{self, Level},
+};
+use advent_of_code_2022::{
+    error,
+    geometry::{BoundingBox, Point},
+    grid::{Grid, MAX_CELLS},
+    image::{heat, Image, BLACK},
+    rng::Rng,
+    Tokens,
+};
+use anyhow::{anyhow, Result};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::RangeInclusive,
+};
+
+#[derive(Debug, Clone)]
+pub struct BeaconExclusionZone {
+    // The row part one counts positions in.
+    pub row: i64,
+    // Part two searches for the distress beacon with both coordinates between zero and this.
+    pub max_coordinate: i64,
 }
 
-#[derive(Debug)]
-struct Map {
-    rows: HashMap<i64, Row>,
+impl BeaconExclusionZone {
+    pub const DEFAULT: BeaconExclusionZone = BeaconExclusionZone {
+        row: 2_000_000,
+        max_coordinate: 4_000_000,
+    };
+}
+
+impl Solution for BeaconExclusionZone {
+    type Input = Map;
+    type Answer1 = i64;
+    type Answer2 = i64;
+
+    fn parse(&self, input: &str) -> Result<Map> {
+        Map::new(input, self.row.min(0)..=self.row.max(self.max_coordinate))
+    }
+
+    fn part1(&self, map: &Map) -> Result<i64> {
+        map.number_of_positions_without_beacon_in_row(self.row)
+    }
+
+    fn part2(&self, map: &Map) -> Result<i64> {
+        map.distress_beacon_tuning_frequency(self.max_coordinate)
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        generate(rng, size)
+    }
+
+    fn reference1(&self, input: &str) -> Result<i64> {
+        naive_number_of_positions_without_beacon_in_row(input, self.row)
+    }
+
+    fn reference2(&self, input: &str) -> Result<i64> {
+        naive_distress_beacon_tuning_frequency(input, self.max_coordinate)
+    }
+
+    fn render(&self, map: &Map) -> Result<Option<Image>> {
+        map.render(self.max_coordinate).map(Some)
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("row", self.row.to_string()),
+            ("max-coordinate", self.max_coordinate.to_string()),
+        ]
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        match name {
+            "row" => {
+                self.row = solution::parameter(
+                    name,
+                    value,
+                    -MAX_DISTANCE..=self.max_coordinate + MAX_DISTANCE,
+                )?
+            }
+            "max-coordinate" => {
+                self.max_coordinate = solution::parameter(
+                    name,
+                    value,
+                    (self.row - MAX_DISTANCE).max(0)..=MAX_DISTANCE,
+                )?
+            }
+            _ => return Err(anyhow!("unknown parameter: {}", name)),
+        }
+        Ok(())
+    }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-struct Position {
-    x: i64,
-    y: i64,
+// Parsing works out the coverage of every row from part one's to the far side of part two's
+// search area. The search area is at most this tall, and the row at most this far outside it.
+const MAX_DISTANCE: i64 = MAX_CELLS as i64;
+
+// The distress beacon's tuning frequency is its x coordinate times this, plus its y coordinate.
+const TUNING_FREQUENCY_FACTOR: i64 = 4_000_000;
+
+// The longest side of a rendered map, in pixels.
+const IMAGE_SIZE: i64 = 800;
+
+#[derive(Debug)]
+pub struct Map {
+    rows: HashMap<i64, Row>,
+    beacons: HashSet<Point>,
+    sensors: Vec<(Point, i64)>,
 }
 
 #[derive(Debug, Default)]
 struct Row(Vec<RangeInclusive<i64>>);
 
 impl Map {
-    fn new(input: &str, max_y: i64) -> Result<Map> {
-        let mut rows: HashMap<i64, Row> = HashMap::new();
-        for line in input.lines() {
-            let mut iter = line.split_ascii_whitespace();
-            expect_word(&mut iter, "Sensor")?;
-            expect_word(&mut iter, "at")?;
-            let sensor_x = next_as_i64(&mut iter, "x", ',')?;
-            let sensor_y = next_as_i64(&mut iter, "y", ':')?;
-            let sensor = Position::from((sensor_x, sensor_y));
-            expect_word(&mut iter, "closest")?;
-            expect_word(&mut iter, "beacon")?;
-            expect_word(&mut iter, "is")?;
-            expect_word(&mut iter, "at")?;
-            let beacon_x = next_as_i64(&mut iter, "x", ',')?;
-            let beacon_y = next_as_i64(&mut iter, "y", None)?;
-            let beacon = Position::from((beacon_x, beacon_y));
-            for (row, range) in sensor.ranges_at_least_as_close_as(beacon, max_y) {
-                let entry = rows.entry(row).or_default();
+    // Only works out which positions the sensors cover in `rows`.
+    fn new(input: &str, rows: RangeInclusive<i64>) -> Result<Map> {
+        let mut covered: HashMap<i64, Row> = HashMap::new();
+        let mut beacons = HashSet::new();
+        let mut sensors = Vec::new();
+        let reports: Vec<(Point, Point)> = error::collect(input.lines().map(report))?;
+        for (sensor, beacon) in reports {
+            let distance = reach(sensor, beacon).ok_or_else(|| {
+                anyhow!(
+                    "sensor at {} is too far from its beacon at {}",
+                    sensor,
+                    beacon
+                )
+            })?;
+            for (row, range) in ranges_at_least_as_close_as(sensor, distance, &rows) {
+                let entry = covered.entry(row).or_default();
                 entry.add(range);
             }
+            let _ = beacons.insert(beacon);
+            trace::event(
+                Level::Steps,
+                "sensor",
+                &[
+                    ("sensor", &sensor),
+                    ("beacon", &beacon),
+                    ("distance", &distance),
+                ],
+            );
+            sensors.push((sensor, distance));
         }
-        Ok(Map { rows })
+        Ok(Map {
+            rows: covered,
+            beacons,
+            sensors,
+        })
     }
 
-    fn number_of_positions_without_beacon_in_row(&self, row: i64) -> i64 {
-        if let Some(row) = self.rows.get(&row) {
-            let mut count = 0;
-            for range in &row.0 {
-                count += range.end() - range.start();
+    fn number_of_positions_without_beacon_in_row(&self, row: i64) -> Result<i64> {
+        if let Some(ranges) = self.rows.get(&row) {
+            let mut count: i64 = 0;
+            for range in &ranges.0 {
+                count = range
+                    .end()
+                    .checked_sub(*range.start())
+                    .and_then(|length| length.checked_add(1))
+                    .and_then(|length| count.checked_add(length))
+                    .ok_or_else(|| anyhow!("too many positions in row {}", row))?;
+            }
+            let beacons = self.beacons.iter().filter(|beacon| beacon.y == row).count() as i64;
+            if trace::enabled(Level::Summary) {
+                let ranges: Vec<String> = ranges
+                    .0
+                    .iter()
+                    .map(|range| format!("{}..={}", range.start(), range.end()))
+                    .collect();
+                trace::event(
+                    Level::Summary,
+                    "row",
+                    &[
+                        ("row", &row),
+                        ("ranges", &ranges.join(" ")),
+                        ("beacons", &beacons),
+                    ],
+                );
             }
-            count
+            Ok(count - beacons)
         } else {
-            0
+            Ok(0)
         }
     }
 
     fn distress_beacon_tuning_frequency(&self, max_coordinate: i64) -> Result<i64> {
         for i in 0..=max_coordinate {
-            if let Some(row) = self.rows.get(&i) {
-                for range in &row.0 {
-                    if *range.end() >= 0 && *range.end() <= max_coordinate {
-                        return Ok((range.end() + 1) * 4_000_000 + i);
-                    }
-                }
+            // Ranges in a row never touch, so the first gap is either at zero or just after the
+            // range covering it.
+            let x = match self
+                .rows
+                .get(&i)
+                .and_then(|row| row.0.iter().find(|range| range.contains(&0)))
+            {
+                Some(range) => range.end().checked_add(1),
+                None => Some(0),
+            };
+            if let Some(x) = x.filter(|&x| x <= max_coordinate) {
+                let beacon = Point::new(x, i);
+                trace::event(Level::Summary, "beacon", &[("beacon", &beacon)]);
+                return x
+                    .checked_mul(TUNING_FREQUENCY_FACTOR)
+                    .and_then(|frequency| frequency.checked_add(i))
+                    .ok_or_else(|| anyhow!("the tuning frequency of {} is too big", beacon));
             }
         }
         Err(anyhow!("no row found without a beacon"))
     }
+
+    // How many sensors cover each part of the search area, from dark red for one to white for the
+    // most overlap. Each pixel stands for a square of positions on big inputs and is sampled at
+    // its top left corner, so sensors are drawn in blue and the distress beacon, if there is one,
+    // as a green cross to keep them visible.
+    fn render(&self, max_coordinate: i64) -> Result<Image> {
+        let bounds =
+            BoundingBox::from_points(self.sensors.iter().flat_map(|&(sensor, distance)| {
+                [
+                    Point::new(sensor.x - distance, sensor.y - distance),
+                    Point::new(sensor.x + distance, sensor.y + distance),
+                ]
+            }))
+            .ok_or_else(|| anyhow!("no sensors"))?;
+        let min = Point::new(bounds.min.x.max(0), bounds.min.y.max(0));
+        let max = Point::new(
+            bounds.max.x.min(max_coordinate),
+            bounds.max.y.min(max_coordinate),
+        );
+        if min.x > max.x || min.y > max.y {
+            return Err(anyhow!("no sensors reach the search area"));
+        }
+        // One less than the positions across, so it can't overflow, which makes the positions
+        // each pixel covers, rounded up, one more than it divided by the image size.
+        let span = (max.x - min.x).max(max.y - min.y);
+        let step = span / IMAGE_SIZE + 1;
+        let width = (max.x - min.x) / step + 1;
+        let height = (max.y - min.y) / step + 1;
+        let pixel = |point: Point| Point::new((point.x - min.x) / step, (point.y - min.y) / step);
+        let mut counts = Grid::new(width.try_into()?, height.try_into()?, 0)?;
+        for y in 0..height {
+            for x in 0..width {
+                let point = Point::new(min.x + x * step, min.y + y * step);
+                counts[Point::new(x, y)] = self
+                    .sensors
+                    .iter()
+                    .filter(|&&(sensor, distance)| {
+                        sensor
+                            .checked_manhattan_distance(point)
+                            .is_some_and(|to_point| to_point <= distance)
+                    })
+                    .count();
+            }
+        }
+        let most = counts
+            .iter()
+            .map(|(_, &count)| count)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let mut image = Image::from_grid(&counts, |&count| {
+            if count == 0 {
+                BLACK
+            } else {
+                heat(0.2 + 0.8 * count as f64 / most as f64)
+            }
+        });
+        for &(sensor, _) in &self.sensors {
+            image.set(pixel(sensor), [0, 128, 255]);
+        }
+        if let Ok(frequency) = self.distress_beacon_tuning_frequency(max_coordinate) {
+            let beacon = pixel(Point::new(
+                frequency / TUNING_FREQUENCY_FACTOR,
+                frequency % TUNING_FREQUENCY_FACTOR,
+            ));
+            for offset in -3..=3 {
+                image.set(Point::new(beacon.x + offset, beacon.y), [0, 255, 0]);
+                image.set(Point::new(beacon.x, beacon.y + offset), [0, 255, 0]);
+            }
+        }
+        if step == 1 {
+            image.scale(8)
+        } else {
+            Ok(image)
+        }
+    }
 }
 
 impl Row {
     fn add(&mut self, mut range: RangeInclusive<i64>) {
         for old in std::mem::take(&mut self.0) {
-            if old.end() < range.start() || range.end() < old.start() {
+            if old.end().saturating_add(1) < *range.start()
+                || range.end().saturating_add(1) < *old.start()
+            {
                 self.0.push(old);
             } else {
                 range = (std::cmp::min(*range.start(), *old.start()))
@@ -94,86 +302,128 @@ impl Row {
     }
 }
 
-impl Position {
-    fn ranges_at_least_as_close_as(
-        &self,
-        other: Position,
-        max_y: i64,
-    ) -> Vec<(i64, RangeInclusive<i64>)> {
-        let distance = self.manhattan_distance(other);
-        let y_min = std::cmp::max(self.y - distance, 0);
-        let y_max = std::cmp::min(self.y + distance, max_y);
-        let mut ranges = Vec::new();
-        for y in y_min..=y_max {
-            let remainder = distance - (self.y - y).abs();
-            let x_min = self.x - remainder;
-            let x_max = self.x + remainder;
-            ranges.push((y, x_min..=x_max));
-        }
-        ranges
+fn report(line: &str) -> Result<(Point, Point)> {
+    let mut tokens = Tokens::new(line);
+    tokens.expect("Sensor at")?;
+    let sensor_x = tokens.field("x", ',')?;
+    let sensor_y = tokens.field("y", ':')?;
+    tokens.expect("closest beacon is at")?;
+    let beacon_x = tokens.field("x", ',')?;
+    let beacon_y = tokens.field("y", None)?;
+    Ok((
+        Point::from((sensor_x, sensor_y)),
+        Point::from((beacon_x, beacon_y)),
+    ))
+}
+
+// The distance from the sensor to its beacon, as long as every position that close fits in an i64.
+fn reach(sensor: Point, beacon: Point) -> Option<i64> {
+    let distance = sensor.checked_manhattan_distance(beacon)?;
+    for coordinate in [sensor.x, sensor.y] {
+        let _ = coordinate.checked_sub(distance)?;
+        let _ = coordinate.checked_add(distance)?;
     }
+    Some(distance)
+}
 
-    fn manhattan_distance(&self, other: Position) -> i64 {
-        (self.x - other.x).abs() + (self.y - other.y).abs()
+fn ranges_at_least_as_close_as(
+    sensor: Point,
+    distance: i64,
+    rows: &RangeInclusive<i64>,
+) -> Vec<(i64, RangeInclusive<i64>)> {
+    let y_min = std::cmp::max(sensor.y - distance, *rows.start());
+    let y_max = std::cmp::min(sensor.y + distance, *rows.end());
+    let mut ranges = Vec::new();
+    for y in y_min..=y_max {
+        let remainder = distance - (sensor.y - y).abs();
+        let x_min = sensor.x - remainder;
+        let x_max = sensor.x + remainder;
+        ranges.push((y, x_min..=x_max));
     }
+    ranges
 }
 
-impl From<(i64, i64)> for Position {
-    fn from((x, y): (i64, i64)) -> Self {
-        Position { x, y }
+fn generate(rng: &mut Rng, size: usize) -> String {
+    let mut input = String::new();
+    for _ in 0..size.max(1) {
+        let sensor = Point::new(rng.range(0..=4_000_000), rng.range(0..=4_000_000));
+        let beacon = Point::new(
+            sensor.x + rng.range(-20_000..=20_000),
+            sensor.y + rng.range(-20_000..=20_000),
+        );
+        input.push_str(&format!(
+            "Sensor at x={}, y={}: closest beacon is at x={}, y={}\n",
+            sensor.x, sensor.y, beacon.x, beacon.y
+        ));
     }
+    input
 }
 
-fn expect_word<'a>(iter: impl Iterator<Item = &'a str>, word: &str) -> Result<()> {
-    next(iter).and_then(|s| {
-        if s == word {
-            Ok(())
-        } else {
-            Err(anyhow!("unexpected word, expected={}, actual={}", word, s))
+// Tries every x the sensors could reach on the row, one at a time.
+fn naive_number_of_positions_without_beacon_in_row(input: &str, row: i64) -> Result<i64> {
+    let reports: Vec<(Point, Point)> = error::collect(input.lines().map(report))?;
+    let reaches = |point: Point| {
+        reports.iter().any(|&(sensor, beacon)| {
+            sensor.manhattan_distance(point) <= sensor.manhattan_distance(beacon)
+        })
+    };
+    let mut xs = Vec::new();
+    for &(sensor, beacon) in &reports {
+        let remainder = sensor.manhattan_distance(beacon) - (sensor.y - row).abs();
+        if remainder >= 0 {
+            xs.extend([sensor.x - remainder, sensor.x + remainder]);
         }
-    })
-}
-
-fn next<'a>(mut iter: impl Iterator<Item = &'a str>) -> Result<&'a str> {
-    iter.next()
-        .ok_or_else(|| anyhow!("unexpected end of input"))
-}
-
-fn next_as_i64<'a>(
-    iter: impl Iterator<Item = &'a str>,
-    name: &str,
-    trailing_char: impl Into<Option<char>>,
-) -> Result<i64> {
-    let s = next(iter)?;
-    let mut iter = s.split('=');
-    expect_word(&mut iter, name)?;
-    let s = next(iter)?;
-    if let Some(trailing_char) = trailing_char.into() {
-        if !s.ends_with(trailing_char) {
-            return Err(anyhow!("{}=n, should end in a comma: {}", name, s));
-        } else {
-            s[0..(s.len() - 1)].parse().map_err(Error::from)
+    }
+    let (Some(&min_x), Some(&max_x)) = (xs.iter().min(), xs.iter().max()) else {
+        return Ok(0);
+    };
+    let mut count = 0;
+    for x in min_x..=max_x {
+        let point = Point::new(x, row);
+        if reaches(point) && !reports.iter().any(|&(_, beacon)| beacon == point) {
+            count += 1;
         }
-    } else {
-        s.parse().map_err(Error::from)
     }
+    Ok(count)
 }
 
-#[test]
-fn part_1() {
-    let map = Map::new(test_input(), 10).unwrap();
-    assert_eq!(map.number_of_positions_without_beacon_in_row(10), 26);
-}
-
-#[test]
-fn part_2() {
-    let map: Map = Map::new(test_input(), 20).unwrap();
-    assert_eq!(map.distress_beacon_tuning_frequency(20).unwrap(), 56000011);
+// Walks the squares just out of each sensor's reach, since a lone gap has to border one of them.
+fn naive_distress_beacon_tuning_frequency(input: &str, max_coordinate: i64) -> Result<i64> {
+    let reports: Vec<(Point, Point)> = error::collect(input.lines().map(report))?;
+    let reaches = |point: Point| {
+        reports.iter().any(|&(sensor, beacon)| {
+            sensor.manhattan_distance(point) <= sensor.manhattan_distance(beacon)
+        })
+    };
+    let mut gap = None;
+    for &(sensor, beacon) in &reports {
+        let distance = sensor.manhattan_distance(beacon) + 1;
+        for dx in -distance..=distance {
+            let dy = distance - dx.abs();
+            for point in [
+                Point::new(sensor.x + dx, sensor.y - dy),
+                Point::new(sensor.x + dx, sensor.y + dy),
+            ] {
+                if (0..=max_coordinate).contains(&point.x)
+                    && (0..=max_coordinate).contains(&point.y)
+                    && !reaches(point)
+                {
+                    match gap {
+                        None => gap = Some(point),
+                        Some(gap) if gap != point => {
+                            return Err(anyhow!("more than one gap: {:?}, {:?}", gap, point))
+                        }
+                        Some(_) => (),
+                    }
+                }
+            }
+        }
+    }
+    gap.map(|point| point.x * TUNING_FREQUENCY_FACTOR + point.y)
+        .ok_or_else(|| anyhow!("no gaps"))
 }
 
-#[cfg(test)]
-fn test_input() -> &'static str {
-    "Sensor at x=2, y=18: closest beacon is at x=-2, y=15
+pub const EXAMPLE: &str = "Sensor at x=2, y=18: closest beacon is at x=-2, y=15
 Sensor at x=9, y=16: closest beacon is at x=10, y=16
 Sensor at x=13, y=2: closest beacon is at x=15, y=3
 Sensor at x=12, y=14: closest beacon is at x=10, y=16
@@ -186,5 +436,67 @@ Sensor at x=20, y=14: closest beacon is at x=25, y=17
 Sensor at x=17, y=20: closest beacon is at x=21, y=22
 Sensor at x=16, y=7: closest beacon is at x=15, y=3
 Sensor at x=14, y=3: closest beacon is at x=15, y=3
-Sensor at x=20, y=1: closest beacon is at x=15, y=3"
+Sensor at x=20, y=1: closest beacon is at x=15, y=3";
+
+pub const EXAMPLES: [Example; 1] = [Example {
+    name: "example",
+    input: EXAMPLE,
+    parameters: &[("row", "10"), ("max-coordinate", "20")],
+    part1: Some("26"),
+    part2: Some("56000011"),
+}];
+
+#[test]
+fn part_1() {
+    let map = Map::new(EXAMPLE, 0..=10).unwrap();
+    assert_eq!(
+        map.number_of_positions_without_beacon_in_row(10).unwrap(),
+        26
+    );
+}
+
+#[test]
+fn part_2() {
+    let map: Map = Map::new(EXAMPLE, 0..=20).unwrap();
+    assert_eq!(map.distress_beacon_tuning_frequency(20).unwrap(), 56000011);
+}
+
+#[test]
+fn parameters() {
+    let mut solution = BeaconExclusionZone::DEFAULT;
+    solution.set("row", "10").unwrap();
+    solution.set("max-coordinate", "20").unwrap();
+    let map = solution.parse(EXAMPLE).unwrap();
+    assert_eq!(solution.part1(&map).unwrap(), 26);
+    assert_eq!(solution.part2(&map).unwrap(), 56000011);
+    solution.set("row", "-2").unwrap();
+    let map = solution.parse(EXAMPLE).unwrap();
+    assert_eq!(
+        solution.part1(&map).unwrap(),
+        solution.reference1(EXAMPLE).unwrap()
+    );
+    assert!(solution.set("max-coordinate", "-1").is_err());
+    assert!(solution.set("row", "-1000000000000").is_err());
+    assert!(solution.set("row", "268435476").is_ok());
+    assert!(solution.set("row", "268435477").is_err());
+    // The search area can't shrink away from the row either.
+    assert!(solution.set("max-coordinate", "19").is_err());
+    assert!(solution.set("max-coordinate", "1000000000000").is_err());
+}
+
+#[test]
+fn huge_coordinates() {
+    let error = Map::new(
+        "Sensor at x=9223372036854775807, y=0: closest beacon is at x=-9223372036854775807, y=0",
+        0..=0,
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("too far from its beacon"));
+    let map = Map::new(
+        "Sensor at x=-4611686018427387904, y=0: closest beacon is at x=0, y=0\n\
+         Sensor at x=4611686018427387903, y=0: closest beacon is at x=0, y=0",
+        0..=0,
+    )
+    .unwrap();
+    assert!(map.number_of_positions_without_beacon_in_row(0).is_err());
 }
@@ -0,0 +1,225 @@
+use advent_of_code_2022::geometry::{BoundingBox, Point};
+use anyhow::{anyhow, Error, Result};
+use std::{io::Write, str::FromStr, time::Duration};
+
+pub const DEFAULT_DELAY: Duration = Duration::from_millis(50);
+
+// Used when the terminal size isn't given and COLUMNS and LINES aren't set.
+const DEFAULT_VIEWPORT: Viewport = Viewport {
+    width: 80,
+    height: 24,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Options {
+    pub every: usize,
+    pub delay: Duration,
+    pub viewport: Option<Viewport>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Viewport {
+    pub width: usize,
+    pub height: usize,
+}
+
+// Redraws a simulation in place with ANSI escapes. Solutions call `frame` after every step and
+// only every nth one is drawn, cropped to the part of the state around `focus` that fits.
+pub struct Animation<'a> {
+    out: Box<dyn Write + 'a>,
+    options: Options,
+    viewport: Viewport,
+    title: String,
+    steps: usize,
+    started: bool,
+}
+
+impl<'a> Animation<'a> {
+    pub fn new(
+        out: Box<dyn Write + 'a>,
+        title: impl Into<String>,
+        options: Options,
+    ) -> Animation<'a> {
+        let viewport = options.viewport.unwrap_or_else(Viewport::terminal);
+        Animation {
+            out,
+            options,
+            viewport,
+            title: title.into(),
+            steps: 0,
+            started: false,
+        }
+    }
+
+    pub fn frame(
+        &mut self,
+        bounds: BoundingBox,
+        focus: Point,
+        cell: impl Fn(Point) -> char,
+    ) -> Result<()> {
+        self.steps += 1;
+        if self.steps.is_multiple_of(self.options.every.max(1)) {
+            self.draw(bounds, focus, cell)?;
+            std::thread::sleep(self.options.delay);
+        }
+        Ok(())
+    }
+
+    // Draws the final state whether or not it landed on an nth step.
+    pub fn finish(
+        &mut self,
+        bounds: BoundingBox,
+        focus: Point,
+        cell: impl Fn(Point) -> char,
+    ) -> Result<()> {
+        self.draw(bounds, focus, cell)?;
+        write!(self.out, "\x1b[?25h")?;
+        self.out.flush()?;
+        self.started = false;
+        Ok(())
+    }
+
+    fn draw(
+        &mut self,
+        bounds: BoundingBox,
+        focus: Point,
+        cell: impl Fn(Point) -> char,
+    ) -> Result<()> {
+        if !self.started {
+            // Clear the screen and hide the cursor.
+            write!(self.out, "\x1b[2J\x1b[?25l")?;
+            self.started = true;
+        }
+        let window = window(bounds, focus, self.viewport);
+        let mut frame = format!("\x1b[H{} (step {})\x1b[K\n", self.title, self.steps);
+        for y in window.min.y..=window.max.y {
+            frame.extend((window.min.x..=window.max.x).map(|x| cell(Point::new(x, y))));
+            frame.push_str("\x1b[K\n");
+        }
+        frame.push_str("\x1b[J");
+        self.out.write_all(frame.as_bytes())?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for Animation<'_> {
+    fn drop(&mut self) {
+        // Don't leave the cursor hidden if the simulation bailed out halfway.
+        if self.started {
+            let _ = write!(self.out, "\x1b[?25h");
+            let _ = self.out.flush();
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            every: 1,
+            delay: DEFAULT_DELAY,
+            viewport: None,
+        }
+    }
+}
+
+impl Viewport {
+    fn terminal() -> Viewport {
+        let size = |name, default| {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default)
+        };
+        Viewport {
+            width: size("COLUMNS", DEFAULT_VIEWPORT.width),
+            height: size("LINES", DEFAULT_VIEWPORT.height),
+        }
+    }
+}
+
+impl FromStr for Viewport {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Viewport> {
+        let (width, height) = s
+            .split_once('x')
+            .ok_or_else(|| anyhow!("invalid viewport, expected <width>x<height>: {}", s))?;
+        let viewport = Viewport {
+            width: width.parse()?,
+            height: height.parse()?,
+        };
+        if viewport.width == 0 || viewport.height < 2 {
+            Err(anyhow!("viewport is too small: {}", s))
+        } else {
+            Ok(viewport)
+        }
+    }
+}
+
+// The part of `bounds` that fits in the viewport, below the title line, as close to centred on
+// `focus` as the edges allow.
+fn window(bounds: BoundingBox, focus: Point, viewport: Viewport) -> BoundingBox {
+    let span = |min: i64, max: i64, focus: i64, size: usize| {
+        let size = (size.max(1) as i64).min(max - min + 1);
+        let start = (focus - size / 2).clamp(min, max - size + 1);
+        (start, start + size - 1)
+    };
+    let (min_x, max_x) = span(bounds.min.x, bounds.max.x, focus.x, viewport.width);
+    let (min_y, max_y) = span(bounds.min.y, bounds.max.y, focus.y, viewport.height - 1);
+    BoundingBox {
+        min: Point::new(min_x, min_y),
+        max: Point::new(max_x, max_y),
+    }
+}
+
+#[test]
+fn cropping() {
+    let bounds = BoundingBox {
+        min: Point::new(0, 0),
+        max: Point::new(99, 49),
+    };
+    let viewport = Viewport {
+        width: 10,
+        height: 6,
+    };
+    let around = |x, y| window(bounds, Point::new(x, y), viewport);
+    assert_eq!(around(50, 25).min, Point::new(45, 23));
+    assert_eq!(around(50, 25).max, Point::new(54, 27));
+    assert_eq!(around(0, 0).min, Point::new(0, 0));
+    assert_eq!(around(99, 49).max, Point::new(99, 49));
+    let small = BoundingBox {
+        min: Point::new(-2, -1),
+        max: Point::new(2, 1),
+    };
+    assert_eq!(window(small, Point::new(0, 0), viewport), small);
+    assert_eq!("80x24".parse::<Viewport>().unwrap().height, 24);
+    assert!("80".parse::<Viewport>().is_err());
+    assert!("80x1".parse::<Viewport>().is_err());
+}
+
+#[test]
+fn every_nth_step() {
+    let mut out = Vec::new();
+    {
+        let options = Options {
+            every: 3,
+            delay: Duration::ZERO,
+            viewport: Some(Viewport {
+                width: 3,
+                height: 2,
+            }),
+        };
+        let mut animation = Animation::new(Box::new(&mut out), "Test", options);
+        let bounds = BoundingBox::new(Point::new(0, 0));
+        for _ in 0..7 {
+            animation.frame(bounds, bounds.min, |_| '#').unwrap();
+        }
+        animation.finish(bounds, bounds.min, |_| '#').unwrap();
+    }
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(out.matches("\x1b[H").count(), 3);
+    assert!(out.contains("Test (step 3)\x1b[K\n#\x1b[K\n"));
+    assert!(out.contains("Test (step 6)"));
+    assert!(out.contains("Test (step 7)"));
+    assert!(out.ends_with("\x1b[?25h"));
+}
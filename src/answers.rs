@@ -0,0 +1,157 @@
+use crate::solution::Part;
+use advent_of_code_2022::normalize;
+use anyhow::{anyhow, Error, Result};
+use std::{collections::BTreeMap, fmt::Display, path::Path, str::FromStr};
+
+pub const DEFAULT_PATH: &str = "answers.txt";
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Answers(BTreeMap<Key, String>);
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct Key {
+    pub day: u8,
+    pub part: Part,
+    pub fingerprint: String,
+}
+
+impl Answers {
+    pub fn load(path: &Path) -> Result<Answers> {
+        if path.exists() {
+            std::fs::read_to_string(path)
+                .map_err(|err| anyhow!("could not read {}: {}", path.display(), err))?
+                .parse()
+        } else {
+            Ok(Answers::default())
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_string())
+            .map_err(|err| anyhow!("could not write {}: {}", path.display(), err))
+    }
+
+    pub fn get(&self, key: &Key) -> Option<&str> {
+        self.0.get(key).map(|answer| answer.as_str())
+    }
+
+    pub fn insert(&mut self, key: Key, answer: String) {
+        let _ = self.0.insert(key, answer);
+    }
+}
+
+impl Key {
+    pub fn new(day: u8, part: Part, input: &str) -> Key {
+        Key {
+            day,
+            part,
+            fingerprint: fingerprint(&normalize::input(input)),
+        }
+    }
+}
+
+// FNV-1a, so fingerprints stay the same across Rust versions.
+pub fn fingerprint(input: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in input.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+// Answers are stored one per line, so multi-line answers (like day 10's screen) are escaped.
+fn escape(answer: &str) -> String {
+    answer.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(s: &str) -> Result<String> {
+    let mut answer = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => answer.push('\n'),
+                Some('\\') => answer.push('\\'),
+                other => return Err(anyhow!("invalid escape in answer: \\{:?}", other)),
+            }
+        } else {
+            answer.push(c);
+        }
+    }
+    Ok(answer)
+}
+
+impl FromStr for Answers {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Answers> {
+        let mut answers = Answers::default();
+        for (i, line) in s.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.splitn(4, ' ');
+            let mut field = |name| {
+                fields
+                    .next()
+                    .ok_or_else(|| anyhow!("line {}: missing {}", i + 1, name))
+            };
+            let day = field("day")?;
+            let part = field("part")?;
+            let fingerprint = field("fingerprint")?;
+            let answer = field("answer")?;
+            let key = Key {
+                day: day
+                    .parse()
+                    .map_err(|_| anyhow!("line {}: invalid day: {}", i + 1, day))?,
+                part: part.parse()?,
+                fingerprint: fingerprint.to_string(),
+            };
+            answers.insert(key, unescape(answer)?);
+        }
+        Ok(answers)
+    }
+}
+
+impl Display for Answers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# day part fingerprint answer")?;
+        for (key, answer) in &self.0 {
+            writeln!(
+                f,
+                "{:02} {} {} {}",
+                key.day,
+                key.part.number(),
+                key.fingerprint,
+                escape(answer)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn round_trip() {
+    let mut answers = Answers::default();
+    answers.insert(Key::new(1, Part::One, "1000\n2000"), "24000".to_string());
+    answers.insert(Key::new(10, Part::Two, "noop"), "#.\\\n.#\n".to_string());
+    let text = answers.to_string();
+    assert_eq!(text.lines().count(), 3);
+    assert_eq!(text.parse::<Answers>().unwrap(), answers);
+    assert_eq!(
+        answers.get(&Key::new(1, Part::One, "1000\n2000")),
+        Some("24000")
+    );
+    assert_eq!(answers.get(&Key::new(1, Part::One, "1000\n2001")), None);
+}
+
+#[test]
+fn parse_errors() {
+    assert!("01 1 abc".parse::<Answers>().is_err());
+    assert!("xx 1 abc 42".parse::<Answers>().is_err());
+    assert!("01 3 abc 42".parse::<Answers>().is_err());
+    assert_eq!(
+        "# comment\n\n".parse::<Answers>().unwrap(),
+        Answers::default()
+    );
+}
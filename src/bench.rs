@@ -0,0 +1,67 @@
+use crate::solution::Timings;
+use std::time::Duration;
+
+pub const DEFAULT_WARMUP: usize = 1;
+pub const DEFAULT_ITERATIONS: usize = 10;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+#[derive(Debug)]
+pub struct Report {
+    pub parse: Stats,
+    pub part1: Stats,
+    pub part2: Stats,
+}
+
+impl Stats {
+    pub fn new(mut durations: Vec<Duration>) -> Option<Stats> {
+        durations.sort();
+        let min = *durations.first()?;
+        let max = *durations.last()?;
+        let middle = durations.len() / 2;
+        let median = if durations.len().is_multiple_of(2) {
+            (durations[middle - 1] + durations[middle]) / 2
+        } else {
+            durations[middle]
+        };
+        Some(Stats { min, median, max })
+    }
+}
+
+impl Report {
+    pub fn new(timings: &[Timings]) -> Option<Report> {
+        let stats = |f: fn(&Timings) -> Duration| Stats::new(timings.iter().map(f).collect());
+        Some(Report {
+            parse: stats(|timings| timings.parse)?,
+            part1: stats(|timings| timings.part1)?,
+            part2: stats(|timings| timings.part2)?,
+        })
+    }
+}
+
+pub fn format(duration: Duration) -> String {
+    format!("{:.2?}", duration)
+}
+
+#[test]
+fn stats() {
+    let ms = Duration::from_millis;
+    assert_eq!(
+        Stats::new(vec![ms(3), ms(1), ms(2)]).unwrap(),
+        Stats {
+            min: ms(1),
+            median: ms(2),
+            max: ms(3)
+        }
+    );
+    assert_eq!(
+        Stats::new(vec![ms(4), ms(1), ms(2), ms(8)]).unwrap().median,
+        ms(3)
+    );
+    assert_eq!(Stats::new(Vec::new()), None);
+}
@@ -0,0 +1,125 @@
+use crate::solution::{Part, Solver};
+use advent_of_code_2022::rng::Rng;
+use std::{
+    ops::Range,
+    panic::{self, AssertUnwindSafe},
+};
+
+pub const DEFAULT_SEEDS: u64 = 100;
+pub const DEFAULT_SIZE: usize = 10;
+
+#[derive(Debug)]
+pub struct Disagreement {
+    pub seed: u64,
+    pub part: Part,
+    pub input: String,
+    pub answer: String,
+    pub reference: String,
+}
+
+// Runs the solution and the reference on a generated input for each seed, stopping at the first
+// disagreement and shrinking its input as far as it'll go.
+pub fn run(solver: &dyn Solver, seeds: Range<u64>, size: usize) -> Option<Disagreement> {
+    for seed in seeds {
+        let input = solver.generate(&mut Rng::new(seed), size);
+        for part in [Part::One, Part::Two] {
+            if let Some(answers) = compare(solver, &input, part) {
+                let input = minimise(solver, part, &input);
+                let (answer, reference) = compare(solver, &input, part).unwrap_or(answers);
+                return Some(Disagreement {
+                    seed,
+                    part,
+                    input,
+                    answer,
+                    reference,
+                });
+            }
+        }
+    }
+    None
+}
+
+// Returns both answers if they differ. Inputs that the reference rejects don't keep the puzzle's
+// promises, so they're skipped rather than counted against the solution.
+fn compare(solver: &dyn Solver, input: &str, part: Part) -> Option<(String, String)> {
+    let reference = catch(|| solver.reference(input, part))?.ok()?;
+    let answer = match catch(|| solver.solve(input, &[part])) {
+        Some(Ok(answers)) => answers.concat(),
+        Some(Err(err)) => format!("error: {}", err),
+        None => "panicked".to_string(),
+    };
+    if answer == reference {
+        None
+    } else {
+        Some((answer, reference))
+    }
+}
+
+fn catch<T>(f: impl FnOnce() -> T) -> Option<T> {
+    panic::catch_unwind(AssertUnwindSafe(f)).ok()
+}
+
+// Drops lines and then characters for as long as the input still parses and the two still
+// disagree.
+fn minimise(solver: &dyn Solver, part: Part, input: &str) -> String {
+    let interesting = |lines: &[String]| {
+        let input: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+        solver.check(&input).is_empty() && compare(solver, &input, part).is_some()
+    };
+    let mut lines = reduce(input.lines().map(str::to_string).collect(), interesting);
+    for i in 0..lines.len() {
+        let chars = lines[i].chars().collect();
+        let chars = reduce(chars, |chars: &[char]| {
+            let mut candidate = lines.clone();
+            candidate[i] = chars.iter().collect();
+            interesting(&candidate)
+        });
+        lines[i] = chars.into_iter().collect();
+    }
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+// Removes ever smaller chunks of items while `interesting` still holds.
+pub fn reduce<T: Clone>(mut items: Vec<T>, mut interesting: impl FnMut(&[T]) -> bool) -> Vec<T> {
+    let mut chunk = items.len() / 2;
+    while chunk > 0 {
+        let mut removed = false;
+        let mut start = 0;
+        while start < items.len() {
+            let end = (start + chunk).min(items.len());
+            let candidate: Vec<T> = items[..start]
+                .iter()
+                .chain(&items[end..])
+                .cloned()
+                .collect();
+            if interesting(&candidate) {
+                items = candidate;
+                removed = true;
+            } else {
+                start += chunk;
+            }
+        }
+        if !removed {
+            chunk /= 2;
+        }
+    }
+    items
+}
+
+#[test]
+fn reduce_to_minimum() {
+    let items: Vec<u32> = (0..20).collect();
+    assert_eq!(
+        reduce(items, |items| items.contains(&3) && items.contains(&17)),
+        vec![3, 17]
+    );
+}
+
+#[test]
+fn references_agree() {
+    for day in &crate::DAYS {
+        if let Some(disagreement) = run(day.solver, 0..10, 5) {
+            panic!("day {}: {:?}", day.number, disagreement);
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use anyhow::{anyhow, Error};
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub struct ParseError {
+    message: String,
+    address: usize,
+    location: Option<Location>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+}
+
+// Every error found while parsing an input, in order.
+#[derive(Debug)]
+pub struct ParseErrors(Vec<Error>);
+
+pub trait SpanContext<T> {
+    fn at(self, span: &str) -> Result<T, Error>;
+}
+
+pub fn at(span: &str, message: impl Display) -> Error {
+    ParseError::new(span, message).into()
+}
+
+pub fn locate(err: Error, input: &str) -> Error {
+    let err = match err.downcast::<ParseErrors>() {
+        Ok(ParseErrors(errors)) => {
+            return ParseErrors(errors.into_iter().map(|err| locate(err, input)).collect()).into()
+        }
+        Err(err) => err,
+    };
+    match err.downcast::<ParseError>() {
+        Ok(mut parse_error) => {
+            parse_error.locate(input);
+            parse_error.into()
+        }
+        Err(err) => err,
+    }
+}
+
+// Like `locate`, for a line read on its own from a stream, `number` lines in.
+pub fn locate_line(err: Error, line: &str, number: usize) -> Error {
+    match locate(err, line).downcast::<ParseError>() {
+        Ok(mut parse_error) => {
+            if let Some(location) = &mut parse_error.location {
+                location.line = number;
+                parse_error.into()
+            } else {
+                anyhow!("line {}: {}", number, parse_error)
+            }
+        }
+        Err(err) => anyhow!("line {}: {}", number, err),
+    }
+}
+
+pub fn collect<T, C>(results: impl IntoIterator<Item = Result<T, Error>>) -> Result<C, Error>
+where
+    C: FromIterator<T>,
+{
+    let mut errors = Vec::new();
+    let values = results
+        .into_iter()
+        .filter_map(|result| result.map_err(|err| errors.extend(self::errors(err))).ok())
+        .collect();
+    if errors.is_empty() {
+        Ok(values)
+    } else {
+        Err(combine(errors))
+    }
+}
+
+// Like `collect`, for two parts of an input that parse to different types.
+pub fn both<A, B>(a: Result<A, Error>, b: Result<B, Error>) -> Result<(A, B), Error> {
+    match (a, b) {
+        (Ok(a), Ok(b)) => Ok((a, b)),
+        (a, b) => Err(combine(
+            [a.err(), b.err()]
+                .into_iter()
+                .flatten()
+                .flat_map(errors)
+                .collect(),
+        )),
+    }
+}
+
+fn combine(mut errors: Vec<Error>) -> Error {
+    if errors.len() == 1 {
+        errors.remove(0)
+    } else {
+        ParseErrors(errors).into()
+    }
+}
+
+pub fn errors(err: Error) -> Vec<Error> {
+    match err.downcast::<ParseErrors>() {
+        Ok(ParseErrors(errors)) => errors,
+        Err(err) => vec![err],
+    }
+}
+
+impl ParseError {
+    pub fn new(span: &str, message: impl Display) -> ParseError {
+        ParseError {
+            message: message.to_string(),
+            address: span.as_ptr() as usize,
+            location: None,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    // Spans are always slices of the input, so their address tells us where they are in it.
+    fn locate(&mut self, input: &str) {
+        let start = input.as_ptr() as usize;
+        if self.address < start || self.address > start + input.len() {
+            return;
+        }
+        let offset = self.address - start;
+        if let Some(before) = input.get(..offset) {
+            let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+            let line_end = input[offset..]
+                .find('\n')
+                .map(|i| offset + i)
+                .unwrap_or(input.len());
+            self.location = Some(Location {
+                line: before.matches('\n').count() + 1,
+                column: before[line_start..].chars().count() + 1,
+                text: input[line_start..line_end]
+                    .trim_end_matches('\r')
+                    .to_string(),
+            });
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(location) = &self.location {
+            let gutter = " ".repeat(location.line.to_string().len());
+            writeln!(
+                f,
+                "line {}, column {}: {}",
+                location.line, location.column, self.message
+            )?;
+            writeln!(f, "{} | {}", location.line, location.text)?;
+            write!(f, "{} | {}^", gutter, " ".repeat(location.column - 1))
+        } else {
+            write!(f, "{}", self.message)
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Display for ParseErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, err) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "\n\n")?;
+            }
+            write!(f, "{}", err)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseErrors {}
+
+impl<T, E> SpanContext<T> for Result<T, E>
+where
+    E: Into<Error>,
+{
+    fn at(self, span: &str) -> Result<T, Error> {
+        self.map_err(|err| {
+            let err = err.into();
+            if err.is::<ParseError>() {
+                err
+            } else {
+                at(span, err)
+            }
+        })
+    }
+}
+
+#[test]
+fn located() {
+    let input = "A Y\nB Q\nC Z";
+    let err = locate(at(&input[6..7], "invalid shape: Q"), input);
+    let parse_error = err.downcast_ref::<ParseError>().unwrap();
+    assert_eq!(
+        parse_error.location().unwrap(),
+        &Location {
+            line: 2,
+            column: 3,
+            text: "B Q".to_string()
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "line 2, column 3: invalid shape: Q\n2 | B Q\n  |   ^"
+    );
+}
+
+#[test]
+fn unrelated_span() {
+    let other = String::from("B Q");
+    let err = locate(at(&other, "invalid shape: Q"), "A Y\nB Q");
+    assert_eq!(err.to_string(), "invalid shape: Q");
+    let err = locate(anyhow::anyhow!("empty stack"), "A Y");
+    assert_eq!(err.to_string(), "empty stack");
+}
+
+#[test]
+fn innermost_span_wins() {
+    let input = "move 1 from x";
+    let result: Result<(), Error> = Err(at(&input[12..], "invalid stack"));
+    let err = locate(result.at(input).unwrap_err(), input);
+    assert!(err.to_string().starts_with("line 1, column 13"));
+}
+
+#[test]
+fn collected() {
+    let input = "1\nx\n3\ny";
+    let result: Result<Vec<i64>, Error> = collect(input.lines().map(|line| line.parse().at(line)));
+    let errors = errors(locate(result.unwrap_err(), input));
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].to_string().starts_with("line 2, column 1"));
+    assert!(errors[1].to_string().starts_with("line 4, column 1"));
+    let values: Vec<i64> =
+        collect(input.lines().take(1).map(|line| line.parse().at(line))).unwrap();
+    assert_eq!(values, vec![1]);
+}
+
+#[test]
+fn collected_from_parts() {
+    let input = "x\n2\ny z";
+    let lines: Result<Vec<Vec<i64>>, Error> = collect(
+        input
+            .lines()
+            .map(|line| collect(line.split(' ').map(|word| word.parse().at(word)))),
+    );
+    let word: Result<i64, Error> = Err(at(&input[2..3], "not wanted"));
+    let errors = errors(locate(both(lines, word).unwrap_err(), input));
+    assert_eq!(errors.len(), 4);
+    assert!(errors[2].to_string().starts_with("line 3, column 3"));
+    assert!(errors[3]
+        .to_string()
+        .starts_with("line 2, column 1: not wanted"));
+    assert_eq!(both(Ok(1), Ok("a")).unwrap(), (1, "a"));
+}
@@ -0,0 +1,129 @@
+use crate::{solution::Part, Day};
+
+#[derive(Debug)]
+pub struct Check {
+    pub example: &'static str,
+    pub part: Part,
+    pub expected: &'static str,
+    pub answer: Result<String, String>,
+}
+
+impl Check {
+    pub fn passed(&self) -> bool {
+        self.answer.as_deref() == Ok(self.expected)
+    }
+}
+
+// Solves each of the day's examples with its parameters and holds every part the puzzle gives an
+// answer for up against it.
+pub fn check(day: &Day) -> Vec<Check> {
+    let mut checks = Vec::new();
+    for example in day.examples {
+        let expected: Vec<(Part, &str)> = [(Part::One, example.part1), (Part::Two, example.part2)]
+            .into_iter()
+            .filter_map(|(part, expected)| Some((part, expected?)))
+            .collect();
+        let parts: Vec<Part> = expected.iter().map(|&(part, _)| part).collect();
+        let parameters: Vec<(String, String)> = example
+            .parameters
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let answers = day
+            .solver
+            .configure(&parameters)
+            .and_then(|solver| solver.run(example.input, &parts));
+        for (i, (part, expected)) in expected.into_iter().enumerate() {
+            let answer = match &answers {
+                Ok(outcomes) => match outcomes.get(i).map(|outcome| &outcome.answer) {
+                    Some(Ok(answer)) => Ok(answer.clone()),
+                    Some(Err(err)) => Err(err.to_string()),
+                    None => Err("no answer".to_string()),
+                },
+                Err(err) => Err(err.to_string()),
+            };
+            checks.push(Check {
+                example: example.name,
+                part,
+                expected,
+                answer,
+            });
+        }
+    }
+    checks
+}
+
+#[test]
+fn every_example_passes() {
+    for day in &crate::DAYS {
+        assert!(
+            !day.examples.is_empty(),
+            "day {} has no examples",
+            day.number
+        );
+        for check in check(day) {
+            assert!(
+                check.passed(),
+                "day {} {} {}: expected {}, got {:?}",
+                day.number,
+                check.example,
+                check.part,
+                check.expected,
+                check.answer
+            );
+        }
+    }
+}
+
+#[test]
+fn failures() {
+    use crate::solution::Example;
+    let day = Day {
+        number: 1,
+        title: "Calorie Counting",
+        embedded: None,
+        solver: &crate::day_01::CalorieCounting,
+        examples: &[
+            Example {
+                name: "wrong",
+                input: "1000\n\n2000",
+                parameters: &[],
+                part1: Some("1000"),
+                part2: None,
+            },
+            Example {
+                name: "broken",
+                input: "1000\nabc",
+                parameters: &[],
+                part1: Some("1000"),
+                part2: Some("1000"),
+            },
+            Example {
+                name: "unknown parameter",
+                input: "1000",
+                parameters: &[("rounds", "1")],
+                part1: None,
+                part2: Some("1000"),
+            },
+        ],
+    };
+    let checks = check(&day);
+    let summary: Vec<(&str, Part, bool)> = checks
+        .iter()
+        .map(|check| (check.example, check.part, check.passed()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("wrong", Part::One, false),
+            ("broken", Part::One, false),
+            ("broken", Part::Two, false),
+            ("unknown parameter", Part::Two, false),
+        ]
+    );
+    assert_eq!(checks[0].answer, Ok("2000".to_string()));
+    assert_eq!(
+        checks[3].answer,
+        Err("unknown parameter: rounds".to_string())
+    );
+}
@@ -0,0 +1,213 @@
+use crate::{
+    differential,
+    solution::{Part, Solver},
+    Day,
+};
+use advent_of_code_2022::rng::Rng;
+use anyhow::{anyhow, Result};
+use std::panic::{self, AssertUnwindSafe};
+
+pub const DEFAULT_CASES: usize = 1000;
+
+// Digits are left out so numbers only change by swapping digits or being replaced whole, with
+// anything from nothing to more than fits in an i64. Cases where deleting something glued two
+// numbers together are skipped, since those can grow into inputs that take forever to solve.
+const CHARACTERS: [char; 20] = [
+    'a', 'z', 'A', 'Z', 'S', 'E', '[', ']', ',', '-', '>', ':', '=', '$', '#', ' ', '\n', '\t',
+    'é', '\0',
+];
+const NUMBERS: [&str; 9] = [
+    "0",
+    "1",
+    "-1",
+    "",
+    "4000000000",
+    "9223372036854775806",
+    "9223372036854775807",
+    "-9223372036854775808",
+    "123456789012345678901234567890",
+];
+
+#[derive(Debug)]
+pub struct Crash {
+    pub input: String,
+    pub message: String,
+}
+
+// Feeds mutated copies of the day's first example through the parser and both parts, with the
+// example's parameters, returning the first input that panics, shrunk as far as it'll go.
+pub fn run(day: &Day, seed: u64, cases: usize) -> Result<Option<Crash>> {
+    let example = day
+        .examples
+        .first()
+        .ok_or_else(|| anyhow!("day {} has no examples", day.number))?;
+    let parameters: Vec<(String, String)> = example
+        .parameters
+        .iter()
+        .map(|&(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    let solver = day.solver.configure(&parameters)?;
+    let solver = solver.as_ref();
+    let mut rng = Rng::new(seed);
+    let digits = longest_number(example.input);
+    for _ in 0..cases {
+        let input = mutate(&mut rng, example.input);
+        if longest_number(&input) > digits {
+            continue;
+        }
+        if panic_message(solver, &input).is_some() {
+            let lines =
+                differential::reduce(input.lines().map(str::to_string).collect(), |lines| {
+                    panic_message(solver, &lines.join("\n")).is_some()
+                });
+            let input = lines.join("\n");
+            let chars = differential::reduce(input.chars().collect(), |chars: &[char]| {
+                panic_message(solver, &chars.iter().collect::<String>()).is_some()
+            });
+            let input: String = chars.into_iter().collect();
+            let message = panic_message(solver, &input);
+            return Ok(message.map(|message| Crash { input, message }));
+        }
+    }
+    Ok(None)
+}
+
+// The most digits in a row, leaving out the numbers the fuzzer puts in on purpose.
+fn longest_number(input: &str) -> usize {
+    input
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|number| {
+            !NUMBERS
+                .iter()
+                .any(|extreme| extreme.trim_start_matches('-') == *number)
+        })
+        .map(str::len)
+        .max()
+        .unwrap_or(0)
+}
+
+fn panic_message(solver: &dyn Solver, input: &str) -> Option<String> {
+    let payload = panic::catch_unwind(AssertUnwindSafe(|| {
+        solver.run(input, &[Part::One, Part::Two])
+    }))
+    .err()?;
+    Some(if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    })
+}
+
+pub fn mutate(rng: &mut Rng, example: &str) -> String {
+    let mut input = example.to_string();
+    for _ in 0..rng.range(1..=3) {
+        let mut chars: Vec<char> = input.chars().collect();
+        let mut lines: Vec<String> = input.split_inclusive('\n').map(str::to_string).collect();
+        let i = rng.below(chars.len());
+        let line = rng.below(lines.len());
+        input = match rng.below(9) {
+            0 => {
+                if i < chars.len() {
+                    let _ = chars.remove(i);
+                }
+                chars.into_iter().collect()
+            }
+            1 => {
+                chars.insert(i, *rng.choose(&CHARACTERS).unwrap_or(&' '));
+                chars.into_iter().collect()
+            }
+            2 => {
+                if let Some(c) = chars.get_mut(i) {
+                    *c = *rng.choose(&CHARACTERS).unwrap_or(&' ');
+                }
+                chars.into_iter().collect()
+            }
+            3 => {
+                let digits: Vec<usize> = (0..chars.len())
+                    .filter(|&i| chars[i].is_ascii_digit())
+                    .collect();
+                if let Some(&i) = rng.choose(&digits) {
+                    chars[i] = char::from(b'0' + rng.below(10) as u8);
+                }
+                chars.into_iter().collect()
+            }
+            4 => replace_number(rng, &input),
+            5 => {
+                if line < lines.len() {
+                    let _ = lines.remove(line);
+                }
+                lines.concat()
+            }
+            6 => {
+                if let Some(copy) = lines.get(line).cloned() {
+                    lines.insert(line, copy);
+                }
+                lines.concat()
+            }
+            7 => {
+                let other = rng.below(lines.len());
+                if line < lines.len() && other < lines.len() {
+                    lines.swap(line, other);
+                }
+                lines.concat()
+            }
+            _ => chars.into_iter().take(i).collect(),
+        };
+    }
+    input
+}
+
+fn replace_number(rng: &mut Rng, input: &str) -> String {
+    let mut numbers = Vec::new();
+    let mut start = None;
+    for (i, c) in input.char_indices().chain([(input.len(), ' ')]) {
+        match (start, c.is_ascii_digit()) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                numbers.push(s..i);
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    match rng.choose(&numbers) {
+        Some(range) => format!(
+            "{}{}{}",
+            &input[..range.start],
+            rng.choose(&NUMBERS).unwrap_or(&"0"),
+            &input[range.end..]
+        ),
+        None => input.to_string(),
+    }
+}
+
+#[test]
+fn mutations() {
+    let example = "move 1 from 2 to 1\nmove 3 from 1 to 3\n";
+    let mut a = Rng::new(0);
+    let mut b = Rng::new(0);
+    let mut changed = 0;
+    for _ in 0..1000 {
+        let input = mutate(&mut a, example);
+        assert_eq!(input, mutate(&mut b, example));
+        assert!(input.chars().count() <= 8 * example.len());
+        if input != example {
+            changed += 1;
+        }
+    }
+    assert!(changed > 900, "only {} of 1000 inputs changed", changed);
+}
+
+#[test]
+fn examples_do_not_panic() {
+    for day in &crate::DAYS {
+        if let Some(crash) = run(day, u64::from(day.number), 100).unwrap() {
+            panic!(
+                "day {} panicked: {}\n{}",
+                day.number, crash.message, crash.input
+            );
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use anyhow::{anyhow, Error, Result};
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign, Mul, Sub, SubAssign},
+    str::FromStr,
+};
+
+// Screen coordinates: x grows to the right and y grows downward, so `Up` is negative y.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Vector {
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+pub const ORTHOGONAL: [Vector; 4] = [
+    Vector { x: 0, y: -1 },
+    Vector { x: 1, y: 0 },
+    Vector { x: 0, y: 1 },
+    Vector { x: -1, y: 0 },
+];
+
+pub const ALL_DIRECTIONS: [Vector; 8] = [
+    Vector { x: 0, y: -1 },
+    Vector { x: 1, y: -1 },
+    Vector { x: 1, y: 0 },
+    Vector { x: 1, y: 1 },
+    Vector { x: 0, y: 1 },
+    Vector { x: -1, y: 1 },
+    Vector { x: -1, y: 0 },
+    Vector { x: -1, y: -1 },
+];
+
+impl Point {
+    pub const fn new(x: i64, y: i64) -> Point {
+        Point { x, y }
+    }
+
+    pub fn manhattan_distance(&self, other: Point) -> i64 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    // Like `manhattan_distance`, but `None` if it doesn't fit in an i64.
+    pub fn checked_manhattan_distance(&self, other: Point) -> Option<i64> {
+        let dx = self.x.checked_sub(other.x)?.checked_abs()?;
+        let dy = self.y.checked_sub(other.y)?.checked_abs()?;
+        dx.checked_add(dy)
+    }
+
+    pub fn chebyshev_distance(&self, other: Point) -> i64 {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
+    }
+
+    pub fn neighbors4(self) -> impl Iterator<Item = Point> {
+        ORTHOGONAL.into_iter().map(move |vector| self + vector)
+    }
+
+    pub fn neighbors8(self) -> impl Iterator<Item = Point> {
+        ALL_DIRECTIONS.into_iter().map(move |vector| self + vector)
+    }
+
+    // Every point on the line from `self` to `other`, both ends included. Uses Bresenham's
+    // algorithm, so horizontal, vertical and 45° lines are exact.
+    pub fn line_to(self, other: Point) -> impl Iterator<Item = Point> {
+        let dx = (other.x - self.x).abs();
+        let dy = -(other.y - self.y).abs();
+        let step = (other - self).signum();
+        let mut point = self;
+        let mut error = dx + dy;
+        let mut done = false;
+        std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            let current = point;
+            if point == other {
+                done = true;
+            } else {
+                let doubled = 2 * error;
+                if doubled >= dy {
+                    error += dy;
+                    point.x += step.x;
+                }
+                if doubled <= dx {
+                    error += dx;
+                    point.y += step.y;
+                }
+            }
+            Some(current)
+        })
+    }
+}
+
+impl Vector {
+    pub const fn new(x: i64, y: i64) -> Vector {
+        Vector { x, y }
+    }
+
+    pub fn signum(&self) -> Vector {
+        Vector {
+            x: self.x.signum(),
+            y: self.y.signum(),
+        }
+    }
+
+    pub fn manhattan_length(&self) -> i64 {
+        self.x.abs() + self.y.abs()
+    }
+
+    pub fn chebyshev_length(&self) -> i64 {
+        self.x.abs().max(self.y.abs())
+    }
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    pub fn vector(&self) -> Vector {
+        use Direction::*;
+        match self {
+            Up => Vector::new(0, -1),
+            Right => Vector::new(1, 0),
+            Down => Vector::new(0, 1),
+            Left => Vector::new(-1, 0),
+        }
+    }
+}
+
+impl BoundingBox {
+    pub fn new(point: Point) -> BoundingBox {
+        BoundingBox {
+            min: point,
+            max: point,
+        }
+    }
+
+    pub fn from_points(points: impl IntoIterator<Item = Point>) -> Option<BoundingBox> {
+        let mut points = points.into_iter();
+        let mut bounding_box = BoundingBox::new(points.next()?);
+        for point in points {
+            bounding_box.include(point);
+        }
+        Some(bounding_box)
+    }
+
+    pub fn include(&mut self, point: Point) {
+        self.min.x = self.min.x.min(point.x);
+        self.min.y = self.min.y.min(point.y);
+        self.max.x = self.max.x.max(point.x);
+        self.max.y = self.max.y.max(point.y);
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+
+    pub fn width(&self) -> i64 {
+        self.max.x - self.min.x + 1
+    }
+
+    pub fn height(&self) -> i64 {
+        self.max.y - self.min.y + 1
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let BoundingBox { min, max } = *self;
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| Point { x, y }))
+    }
+}
+
+impl Add<Vector> for Point {
+    type Output = Point;
+    fn add(self, vector: Vector) -> Point {
+        Point {
+            x: self.x + vector.x,
+            y: self.y + vector.y,
+        }
+    }
+}
+
+impl AddAssign<Vector> for Point {
+    fn add_assign(&mut self, vector: Vector) {
+        *self = *self + vector;
+    }
+}
+
+impl Sub<Vector> for Point {
+    type Output = Point;
+    fn sub(self, vector: Vector) -> Point {
+        Point {
+            x: self.x - vector.x,
+            y: self.y - vector.y,
+        }
+    }
+}
+
+impl SubAssign<Vector> for Point {
+    fn sub_assign(&mut self, vector: Vector) {
+        *self = *self - vector;
+    }
+}
+
+impl Sub for Point {
+    type Output = Vector;
+    fn sub(self, other: Point) -> Vector {
+        Vector {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+}
+
+impl Add for Vector {
+    type Output = Vector;
+    fn add(self, other: Vector) -> Vector {
+        Vector {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+}
+
+impl Mul<i64> for Vector {
+    type Output = Vector;
+    fn mul(self, n: i64) -> Vector {
+        Vector {
+            x: self.x * n,
+            y: self.y * n,
+        }
+    }
+}
+
+impl From<(i64, i64)> for Point {
+    fn from((x, y): (i64, i64)) -> Point {
+        Point { x, y }
+    }
+}
+
+// The same `x,y` form puzzle inputs use.
+impl Display for Point {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+impl From<Direction> for Vector {
+    fn from(direction: Direction) -> Vector {
+        direction.vector()
+    }
+}
+
+impl FromStr for Direction {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Direction> {
+        use Direction::*;
+        match s {
+            "U" => Ok(Up),
+            "R" => Ok(Right),
+            "D" => Ok(Down),
+            "L" => Ok(Left),
+            _ => Err(anyhow!("unexpected direction: {}", s)),
+        }
+    }
+}
+
+#[test]
+fn distances() {
+    let a = Point::new(1, 2);
+    let b = Point::new(4, -2);
+    assert_eq!(a.manhattan_distance(b), 7);
+    assert_eq!(a.checked_manhattan_distance(b), Some(7));
+    assert_eq!(
+        Point::new(i64::MAX, 0).checked_manhattan_distance(Point::new(-i64::MAX, 0)),
+        None
+    );
+    assert_eq!(a.chebyshev_distance(b), 4);
+    assert_eq!((b - a).manhattan_length(), 7);
+    assert_eq!(a + Direction::Up.vector() * 3, Point::new(1, -1));
+    assert_eq!(b.to_string(), "4,-2");
+}
+
+#[test]
+fn lines() {
+    let line = |a: (i64, i64), b: (i64, i64)| {
+        Point::from(a)
+            .line_to(Point::from(b))
+            .map(|p| (p.x, p.y))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(line((498, 6), (496, 6)), vec![(498, 6), (497, 6), (496, 6)]);
+    assert_eq!(line((2, 0), (2, 2)), vec![(2, 0), (2, 1), (2, 2)]);
+    assert_eq!(line((0, 0), (2, -2)), vec![(0, 0), (1, -1), (2, -2)]);
+    assert_eq!(line((3, 3), (3, 3)), vec![(3, 3)]);
+    assert_eq!(line((0, 0), (4, 2)).len(), 5);
+}
+
+#[test]
+fn bounding_boxes() {
+    let bounding_box =
+        BoundingBox::from_points([Point::new(3, -1), Point::new(-2, 4), Point::new(0, 0)]).unwrap();
+    assert_eq!(bounding_box.min, Point::new(-2, -1));
+    assert_eq!(bounding_box.max, Point::new(3, 4));
+    assert_eq!((bounding_box.width(), bounding_box.height()), (6, 6));
+    assert!(bounding_box.contains(Point::new(3, 4)));
+    assert!(!bounding_box.contains(Point::new(4, 4)));
+    assert_eq!(bounding_box.points().count(), 36);
+    assert_eq!(BoundingBox::from_points([]), None);
+}
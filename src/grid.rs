@@ -0,0 +1,264 @@
+use crate::{
+    error::{at, both, collect, SpanContext},
+    geometry::{BoundingBox, Point, Vector, ALL_DIRECTIONS, ORTHOGONAL},
+};
+use anyhow::{anyhow, Result};
+use std::{
+    fmt::Display,
+    ops::{Index, IndexMut},
+};
+
+// Grids sized from puzzle input are capped at this many cells, a quarter of a gibibyte of bytes,
+// so an input with huge coordinates is an error rather than an allocation failure.
+pub const MAX_CELLS: usize = 1 << 28;
+
+// Cells are addressed by points, with x the column and y the row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Grid<T> {
+    // Errors instead of overflowing or going over `MAX_CELLS`.
+    pub fn new(width: usize, height: usize, fill: T) -> Result<Grid<T>>
+    where
+        T: Clone,
+    {
+        let cells = width
+            .checked_mul(height)
+            .filter(|&cells| cells <= MAX_CELLS)
+            .ok_or_else(|| anyhow!("grid is too big: {} by {}", width, height))?;
+        Ok(Grid {
+            cells: vec![fill; cells],
+            width,
+            height,
+        })
+    }
+
+    // Builds a grid from a map of characters, one row per line. Errors from `f` are reported at
+    // the offending character, and every one of them is reported.
+    pub fn parse(s: &str, mut f: impl FnMut(Point, char) -> Result<T>) -> Result<Grid<T>> {
+        let width = s
+            .lines()
+            .next()
+            .map(|line| line.chars().count())
+            .ok_or_else(|| anyhow!("empty input"))?;
+        let rows: Vec<Vec<T>> = collect(s.lines().enumerate().map(|(y, line)| {
+            let row = collect(line.char_indices().enumerate().map(|(x, (i, c))| {
+                let point = Point::new(x.try_into()?, y.try_into()?);
+                f(point, c).at(&line[i..])
+            }));
+            let count = line.chars().count();
+            let regular = if count == width {
+                Ok(())
+            } else {
+                Err(at(
+                    line,
+                    format!("irregular widths: expected {}, got {}", width, count),
+                ))
+            };
+            both(row, regular).map(|(row, ())| row)
+        }))?;
+        Ok(Grid {
+            height: rows.len(),
+            cells: rows.into_iter().flatten().collect(),
+            width,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        if self.cells.is_empty() {
+            None
+        } else {
+            Some(BoundingBox {
+                min: Point::new(0, 0),
+                max: Point::new(self.width as i64 - 1, self.height as i64 - 1),
+            })
+        }
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.index_of(point).is_some()
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.index_of(point).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        self.index_of(point).map(|i| &mut self.cells[i])
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width as i64;
+        (0..self.height as i64).flat_map(move |y| (0..width).map(move |x| Point::new(x, y)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.cells.iter())
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            cells: self.cells.iter().map(f).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    pub fn row(&self, y: usize) -> impl Iterator<Item = &T> {
+        self.cells
+            .chunks(self.width.max(1))
+            .nth(y)
+            .into_iter()
+            .flatten()
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        let width = self.width;
+        (0..self.height).filter_map(move |y| {
+            if x < width {
+                self.cells.get(y * width + x)
+            } else {
+                None
+            }
+        })
+    }
+
+    // Every point from `start` (exclusive) in the given direction until the edge of the grid.
+    pub fn ray(&self, start: Point, direction: Vector) -> impl Iterator<Item = Point> + '_ {
+        std::iter::successors(Some(start + direction), move |&point| {
+            Some(point + direction)
+        })
+        .take_while(|&point| self.contains(point))
+    }
+
+    pub fn neighbors4(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        ORTHOGONAL
+            .into_iter()
+            .map(move |vector| point + vector)
+            .filter(|&point| self.contains(point))
+    }
+
+    pub fn neighbors8(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        ALL_DIRECTIONS
+            .into_iter()
+            .map(move |vector| point + vector)
+            .filter(|&point| self.contains(point))
+    }
+
+    fn index_of(&self, point: Point) -> Option<usize> {
+        let x = usize::try_from(point.x).ok()?;
+        let y = usize::try_from(point.y).ok()?;
+        if x < self.width && y < self.height {
+            Some(y * self.width + x)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+    fn index(&self, point: Point) -> &T {
+        self.get(point)
+            .unwrap_or_else(|| panic!("point out of bounds: {:?}", point))
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, point: Point) -> &mut T {
+        self.get_mut(point)
+            .unwrap_or_else(|| panic!("point out of bounds: {:?}", point))
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for y in 0..self.height {
+            for cell in self.row(y) {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn test_grid() -> Grid<u32> {
+    Grid::parse("123\n456\n789\n", |_, c| {
+        c.to_digit(10).ok_or_else(|| anyhow!("not a digit"))
+    })
+    .unwrap()
+}
+
+#[test]
+fn parse() {
+    let grid = test_grid();
+    assert_eq!((grid.width(), grid.height()), (3, 3));
+    assert_eq!(grid.get(Point::new(2, 1)), Some(&6));
+    assert_eq!(grid.get(Point::new(0, 3)), None);
+    assert_eq!(grid.get(Point::new(-1, 0)), None);
+    assert_eq!(grid.to_string(), "123\n456\n789\n");
+    let err = crate::error::locate(
+        Grid::parse("12\n3x", |_, c| {
+            c.to_digit(10).ok_or_else(|| anyhow!("not a digit"))
+        })
+        .unwrap_err(),
+        "12\n3x",
+    );
+    assert!(err.to_string().starts_with("line 2, column 2: not a digit"));
+    let input = "1x\n3\ny6";
+    let err = Grid::parse(input, |_, c| {
+        c.to_digit(10).ok_or_else(|| anyhow!("not a digit"))
+    })
+    .unwrap_err();
+    let errors = crate::error::errors(crate::error::locate(err, input));
+    assert_eq!(errors.len(), 3);
+    assert!(errors[1]
+        .to_string()
+        .starts_with("line 2, column 1: irregular widths"));
+    assert!(errors[2]
+        .to_string()
+        .starts_with("line 3, column 1: not a digit"));
+    assert!(Grid::parse("12\n3", |_, c| Ok(c)).is_err());
+    assert!(Grid::parse("", |_, c| Ok(c)).is_err());
+}
+
+#[test]
+fn sizes() {
+    let grid = Grid::new(3, 2, 0).unwrap();
+    assert_eq!((grid.width(), grid.height()), (3, 2));
+    assert!(Grid::new(usize::MAX, 2, 0).is_err());
+    assert!(Grid::new(MAX_CELLS, 2, 0).is_err());
+}
+
+#[test]
+fn iterators() {
+    let grid = test_grid();
+    assert_eq!(grid.row(1).copied().collect::<Vec<_>>(), vec![4, 5, 6]);
+    assert_eq!(grid.column(2).copied().collect::<Vec<_>>(), vec![3, 6, 9]);
+    assert_eq!(
+        grid.ray(Point::new(2, 2), Vector::new(-1, -1))
+            .map(|p| grid[p])
+            .collect::<Vec<_>>(),
+        vec![5, 1]
+    );
+    assert_eq!(
+        grid.neighbors4(Point::new(0, 0)).collect::<Vec<_>>(),
+        vec![Point::new(1, 0), Point::new(0, 1)]
+    );
+    assert_eq!(grid.neighbors8(Point::new(1, 1)).count(), 8);
+    assert_eq!(grid.neighbors8(Point::new(2, 2)).count(), 3);
+}
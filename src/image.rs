@@ -0,0 +1,141 @@
+use crate::{geometry::Point, grid::Grid};
+use anyhow::Result;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+pub type Rgb = [u8; 3];
+
+pub const BLACK: Rgb = [0, 0, 0];
+pub const WHITE: Rgb = [255, 255, 255];
+
+// An RGB picture, written out as a binary PPM, or as a PGM if only the brightness matters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pixels: Grid<Rgb>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, fill: Rgb) -> Result<Image> {
+        Ok(Image {
+            pixels: Grid::new(width, height, fill)?,
+        })
+    }
+
+    pub fn from_grid<T>(grid: &Grid<T>, f: impl FnMut(&T) -> Rgb) -> Image {
+        Image {
+            pixels: grid.map(f),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.pixels.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.pixels.height()
+    }
+
+    pub fn get(&self, point: Point) -> Option<Rgb> {
+        self.pixels.get(point).copied()
+    }
+
+    // Pixels outside the image are ignored, so callers can draw without clipping first.
+    pub fn set(&mut self, point: Point, color: Rgb) {
+        if let Some(pixel) = self.pixels.get_mut(point) {
+            *pixel = color;
+        }
+    }
+
+    // Blows every pixel up into a `factor` by `factor` square, for inputs too small to see.
+    pub fn scale(&self, factor: usize) -> Result<Image> {
+        let factor = factor.max(1);
+        let mut image = Image::new(
+            self.width().saturating_mul(factor),
+            self.height().saturating_mul(factor),
+            BLACK,
+        )?;
+        for point in image.pixels.points() {
+            let source = Point::new(point.x / factor as i64, point.y / factor as i64);
+            image.pixels[point] = self.pixels[source];
+        }
+        Ok(image)
+    }
+
+    pub fn write_ppm(&self, mut writer: impl Write) -> Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width(), self.height())?;
+        for (_, pixel) in self.pixels.iter() {
+            writer.write_all(pixel)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_pgm(&self, mut writer: impl Write) -> Result<()> {
+        write!(writer, "P5\n{} {}\n255\n", self.width(), self.height())?;
+        for (_, &pixel) in self.pixels.iter() {
+            writer.write_all(&[luma(pixel)])?;
+        }
+        Ok(())
+    }
+
+    // Writes a PGM if the path ends in `.pgm` and a PPM otherwise.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        if path.extension().is_some_and(|extension| extension == "pgm") {
+            self.write_pgm(&mut writer)?;
+        } else {
+            self.write_ppm(&mut writer)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+pub fn gray(value: u8) -> Rgb {
+    [value; 3]
+}
+
+// Black through red and yellow to white as `t` goes from zero to one, so brighter always means
+// more, in colour or not.
+pub fn heat(t: f64) -> Rgb {
+    let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+    let channel = |start: f64| ((t * 3.0 - start).clamp(0.0, 1.0) * 255.0).round() as u8;
+    [channel(0.0), channel(1.0), channel(2.0)]
+}
+
+fn luma([r, g, b]: Rgb) -> u8 {
+    ((299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b)) / 1000) as u8
+}
+
+#[test]
+fn formats() {
+    let mut image = Image::new(2, 1, BLACK).unwrap();
+    image.set(Point::new(1, 0), [255, 0, 0]);
+    image.set(Point::new(5, 5), WHITE);
+    let mut ppm = Vec::new();
+    image.write_ppm(&mut ppm).unwrap();
+    assert_eq!(ppm, b"P6\n2 1\n255\n\0\0\0\xff\0\0");
+    let mut pgm = Vec::new();
+    image.write_pgm(&mut pgm).unwrap();
+    assert_eq!(pgm, b"P5\n2 1\n255\n\0\x4c");
+    let scaled = image.scale(2).unwrap();
+    assert!(image.scale(usize::MAX).is_err());
+    assert!(Image::new(1 << 20, 1 << 20, BLACK).is_err());
+    assert_eq!((scaled.width(), scaled.height()), (4, 2));
+    assert_eq!(scaled.get(Point::new(2, 1)), Some([255, 0, 0]));
+}
+
+#[test]
+fn heat_map() {
+    assert_eq!(heat(0.0), BLACK);
+    assert_eq!(heat(1.0), WHITE);
+    assert_eq!(heat(2.0), WHITE);
+    assert_eq!(heat(f64::NAN), BLACK);
+    assert_eq!(heat(1.0 / 3.0), [255, 0, 0]);
+    let brightness: Vec<u8> = (0..=100)
+        .map(|i| luma(heat(f64::from(i) / 100.0)))
+        .collect();
+    assert!(brightness.windows(2).all(|pair| pair[0] <= pair[1]));
+}
@@ -0,0 +1,137 @@
+use crate::Day;
+use anyhow::{anyhow, Error, Result};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+pub const DEFAULT_DIRECTORY: &str = "inputs";
+
+#[cfg(feature = "embedded-inputs")]
+macro_rules! embedded {
+    ($path:literal) => {
+        Some(include_str!(concat!("../inputs/", $path)))
+    };
+}
+
+#[cfg(not(feature = "embedded-inputs"))]
+macro_rules! embedded {
+    ($path:literal) => {
+        None
+    };
+}
+
+pub(crate) use embedded;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Source {
+    File(PathBuf),
+    Stdin,
+    Directory(PathBuf),
+}
+
+impl Source {
+    pub fn read(&self, day: &Day) -> Result<String> {
+        match self {
+            Source::File(path) => read_file(path),
+            Source::Stdin => {
+                let mut input = String::new();
+                let _ = std::io::stdin().read_to_string(&mut input)?;
+                Ok(input)
+            }
+            Source::Directory(directory) => {
+                let path = directory.join(format!("{:02}.txt", day.number));
+                if path.exists() {
+                    read_file(&path)
+                } else if let Some(input) = day.embedded {
+                    Ok(input.to_string())
+                } else {
+                    Err(anyhow!(
+                        "no input for day {}: {} does not exist",
+                        day.number,
+                        path.display()
+                    ))
+                }
+            }
+        }
+    }
+
+    // Like `read`, but leaves the input to be read a piece at a time.
+    pub fn open(&self, day: &Day) -> Result<Box<dyn BufRead>> {
+        match self {
+            Source::File(path) => open_file(path),
+            Source::Stdin => Ok(Box::new(std::io::stdin().lock())),
+            Source::Directory(directory) => {
+                let path = directory.join(format!("{:02}.txt", day.number));
+                if path.exists() {
+                    open_file(&path)
+                } else if let Some(input) = day.embedded {
+                    Ok(Box::new(input.as_bytes()))
+                } else {
+                    Err(anyhow!(
+                        "no input for day {}: {} does not exist",
+                        day.number,
+                        path.display()
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl Default for Source {
+    fn default() -> Source {
+        Source::Directory(PathBuf::from(DEFAULT_DIRECTORY))
+    }
+}
+
+impl FromStr for Source {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Source> {
+        if s == "-" {
+            Ok(Source::Stdin)
+        } else if s.is_empty() {
+            Err(anyhow!("empty input path"))
+        } else {
+            Ok(Source::File(PathBuf::from(s)))
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path)
+        .map_err(|err| anyhow!("could not read {}: {}", path.display(), err))
+}
+
+fn open_file(path: &Path) -> Result<Box<dyn BufRead>> {
+    let file =
+        File::open(path).map_err(|err| anyhow!("could not read {}: {}", path.display(), err))?;
+    Ok(Box::new(BufReader::new(file)))
+}
+
+#[test]
+fn parse() {
+    assert_eq!("-".parse::<Source>().unwrap(), Source::Stdin);
+    assert_eq!(
+        "inputs/07.txt".parse::<Source>().unwrap(),
+        Source::File(PathBuf::from("inputs/07.txt"))
+    );
+    assert!("".parse::<Source>().is_err());
+}
+
+#[test]
+fn directory() {
+    let day = &crate::DAYS[6];
+    let directory = std::env::temp_dir().join(format!("aoc-input-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("07.txt"), "$ cd /\n").unwrap();
+    let source = Source::Directory(directory.clone());
+    assert_eq!(source.read(day).unwrap(), "$ cd /\n");
+    let result = Source::Directory(directory.join("missing")).read(day);
+    std::fs::remove_dir_all(&directory).unwrap();
+    if day.embedded.is_none() {
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use std::fmt::{Display, Write};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn object<'a>(fields: impl IntoIterator<Item = (&'a str, Value)>) -> Value {
+        Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Value {
+        Value::String(s)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Value {
+        Value::Integer(n)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Value {
+        Value::Float(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Value {
+        Value::Bool(b)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(option: Option<T>) -> Value {
+        option.map(Into::into).unwrap_or(Value::Null)
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Value::*;
+        match self {
+            Null => write!(f, "null"),
+            Bool(b) => write!(f, "{}", b),
+            Integer(n) => write!(f, "{}", n),
+            // JSON has no representation for NaN or infinity.
+            Float(n) if !n.is_finite() => write!(f, "null"),
+            Float(n) => write!(f, "{}", n),
+            String(s) => write_string(f, s),
+            Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut std::fmt::Formatter<'_>, s: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+#[test]
+fn values() {
+    let value = Value::object([
+        ("day", Value::from(10)),
+        ("answer", Value::from("#.\n.#")),
+        ("elapsed", Value::from(0.5)),
+        ("error", Value::from(None::<String>)),
+        ("parts", Value::Array(vec![true.into(), f64::NAN.into()])),
+    ]);
+    assert_eq!(
+        value.to_string(),
+        r##"{"day":10,"answer":"#.\n.#","elapsed":0.5,"error":null,"parts":[true,null]}"##
+    );
+}
+
+#[test]
+fn escapes() {
+    assert_eq!(
+        Value::from("say \"hi\"\\\t\u{1}é").to_string(),
+        r#""say \"hi\"\\\t\u0001é""#
+    );
+}
@@ -0,0 +1,12 @@
+pub mod error;
+pub mod geometry;
+pub mod grid;
+pub mod image;
+pub mod json;
+pub mod normalize;
+pub mod rng;
+pub mod stream;
+pub mod tokens;
+
+pub use error::{ParseError, SpanContext};
+pub use tokens::Tokens;
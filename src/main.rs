@@ -0,0 +1,1542 @@
+use advent_of_code_2022::{json::Value, rng::Rng};
+use animation::Animation;
+use answers::{Answers, Key};
+use anyhow::{anyhow, Error, Result};
+use input::{embedded, Source};
+use solution::{Example, Part, Prepared, Solver};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+mod animation;
+mod answers;
+mod bench;
+#[path = "01.rs"]
+mod day_01;
+#[path = "02.rs"]
+mod day_02;
+#[path = "03.rs"]
+mod day_03;
+#[path = "04.rs"]
+mod day_04;
+#[path = "05.rs"]
+mod day_05;
+#[path = "06.rs"]
+mod day_06;
+#[path = "07.rs"]
+mod day_07;
+#[path = "08.rs"]
+mod day_08;
+#[path = "09.rs"]
+mod day_09;
+#[path = "10.rs"]
+mod day_10;
+#[path = "11.rs"]
+mod day_11;
+#[path = "12.rs"]
+mod day_12;
+#[path = "13.rs"]
+mod day_13;
+#[path = "14.rs"]
+mod day_14;
+#[path = "15.rs"]
+mod day_15;
+mod differential;
+mod examples;
+mod fuzz;
+mod input;
+mod memory;
+mod pool;
+mod serve;
+#[cfg(test)]
+mod snapshot;
+mod solution;
+mod trace;
+
+const USAGE: &str = "usage:
+    aoc run <day|all> [--part <1|2>] [--input <path|->] [--inputs <directory>] [--format <text|json>]
+            [--stream] [--jobs <n>] [--set <name>=<value>]...
+            [--trace <summary|steps|state> [--trace-file <path>]]
+            [--image <path.ppm|path.pgm>] [--animate [--every <n>] [--delay <ms>] [--viewport <w>x<h>]]
+    aoc check <day> <path|->
+    aoc bench [day|all] [--warmup <n>] [--iterations <n>] [--inputs <directory>] [--memory]
+    aoc gen <day> [--seed <n>] [--size <n>]
+    aoc diff [day|all] [--seeds <n>] [--size <n>]
+    aoc fuzz [day|all] [--cases <n>] [--seed <n>]
+    aoc verify [day|all] [--inputs <directory>] [--answers <path>] [--record]
+    aoc examples [day|all]
+    aoc serve [--port <n>] [--inputs <directory>]
+    aoc list";
+
+// Counts what each day allocates, for `aoc bench --memory`.
+#[global_allocator]
+static ALLOCATOR: memory::Counting = memory::Counting;
+
+static DAYS: [Day; 15] = [
+    Day {
+        number: 1,
+        title: "Calorie Counting",
+        embedded: embedded!("01.txt"),
+        solver: &day_01::CalorieCounting,
+        examples: &day_01::EXAMPLES,
+    },
+    Day {
+        number: 2,
+        title: "Rock Paper Scissors",
+        embedded: embedded!("02.txt"),
+        solver: &day_02::RockPaperScissors,
+        examples: &day_02::EXAMPLES,
+    },
+    Day {
+        number: 3,
+        title: "Rucksack Reorganization",
+        embedded: embedded!("03.txt"),
+        solver: &day_03::RucksackReorganization,
+        examples: &day_03::EXAMPLES,
+    },
+    Day {
+        number: 4,
+        title: "Camp Cleanup",
+        embedded: embedded!("04.txt"),
+        solver: &day_04::CampCleanup,
+        examples: &day_04::EXAMPLES,
+    },
+    Day {
+        number: 5,
+        title: "Supply Stacks",
+        embedded: embedded!("05.txt"),
+        solver: &day_05::SupplyStacks,
+        examples: &day_05::EXAMPLES,
+    },
+    Day {
+        number: 6,
+        title: "Tuning Trouble",
+        embedded: embedded!("06.txt"),
+        solver: &day_06::TuningTrouble::DEFAULT,
+        examples: &day_06::EXAMPLES,
+    },
+    Day {
+        number: 7,
+        title: "No Space Left On Device",
+        embedded: embedded!("07.txt"),
+        solver: &day_07::NoSpaceLeftOnDevice::DEFAULT,
+        examples: &day_07::EXAMPLES,
+    },
+    Day {
+        number: 8,
+        title: "Treetop Tree House",
+        embedded: embedded!("08.txt"),
+        solver: &day_08::TreetopTreeHouse,
+        examples: &day_08::EXAMPLES,
+    },
+    Day {
+        number: 9,
+        title: "Rope Bridge",
+        embedded: embedded!("09.txt"),
+        solver: &day_09::RopeBridge::DEFAULT,
+        examples: &day_09::EXAMPLES,
+    },
+    Day {
+        number: 10,
+        title: "Cathode-Ray Tube",
+        embedded: embedded!("10.txt"),
+        solver: &day_10::CathodeRayTube::DEFAULT,
+        examples: &day_10::EXAMPLES,
+    },
+    Day {
+        number: 11,
+        title: "Monkey in the Middle",
+        embedded: embedded!("11.txt"),
+        solver: &day_11::MonkeyInTheMiddle::DEFAULT,
+        examples: &day_11::EXAMPLES,
+    },
+    Day {
+        number: 12,
+        title: "Hill Climbing Algorithm",
+        embedded: embedded!("12.txt"),
+        solver: &day_12::HillClimbingAlgorithm,
+        examples: &day_12::EXAMPLES,
+    },
+    Day {
+        number: 13,
+        title: "Distress Signal",
+        embedded: embedded!("13.txt"),
+        solver: &day_13::DistressSignal,
+        examples: &day_13::EXAMPLES,
+    },
+    Day {
+        number: 14,
+        title: "Regolith Reservoir",
+        embedded: embedded!("14.txt"),
+        solver: &day_14::RegolithReservoir::DEFAULT,
+        examples: &day_14::EXAMPLES,
+    },
+    Day {
+        number: 15,
+        title: "Beacon Exclusion Zone",
+        embedded: embedded!("15.txt"),
+        solver: &day_15::BeaconExclusionZone::DEFAULT,
+        examples: &day_15::EXAMPLES,
+    },
+];
+
+fn main() -> Result<()> {
+    let command = Command::from_args(std::env::args().skip(1))?;
+    command.execute()
+}
+
+struct Day {
+    number: u8,
+    title: &'static str,
+    embedded: Option<&'static str>,
+    solver: &'static dyn Solver,
+    examples: &'static [Example],
+}
+
+#[cfg(test)]
+impl Day {
+    // The input the tests use when any input will do.
+    fn example(&self) -> &'static str {
+        self.examples.first().map_or("", |example| example.input)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Run {
+        selection: Selection,
+        part: Option<Part>,
+        source: Source,
+        format: Format,
+        image: Option<PathBuf>,
+        animation: Option<animation::Options>,
+        stream: bool,
+        jobs: Option<usize>,
+        parameters: Vec<(String, String)>,
+        trace: Option<trace::Options>,
+    },
+    Check {
+        day: u8,
+        source: Source,
+    },
+    Bench {
+        selection: Selection,
+        source: Source,
+        warmup: usize,
+        iterations: usize,
+        memory: bool,
+    },
+    Gen {
+        day: u8,
+        seed: u64,
+        size: usize,
+    },
+    Diff {
+        selection: Selection,
+        seeds: u64,
+        size: usize,
+    },
+    Fuzz {
+        selection: Selection,
+        cases: usize,
+        seed: u64,
+    },
+    Verify {
+        selection: Selection,
+        source: Source,
+        answers: PathBuf,
+        record: bool,
+    },
+    Examples {
+        selection: Selection,
+    },
+    Serve {
+        port: u16,
+        source: Source,
+    },
+    List,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Selection {
+    All,
+    Day(u8),
+}
+
+impl Command {
+    fn from_args(args: impl IntoIterator<Item = String>) -> Result<Command> {
+        let mut args = args.into_iter();
+        let command = args.next().ok_or_else(|| anyhow!("{}", USAGE))?;
+        match command.as_str() {
+            "run" => {
+                let mut selection = None;
+                let mut part = None;
+                let mut source = Source::default();
+                let mut format = Format::Text;
+                let mut image = None;
+                let mut animate = false;
+                let mut options = animation::Options::default();
+                let mut stream = false;
+                let mut jobs = None;
+                let mut parameters = Vec::new();
+                let mut trace_level = None;
+                let mut trace_path = None;
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--part" => part = Some(option_value(&mut args, &arg)?.parse()?),
+                        "--format" => format = option_value(&mut args, &arg)?.parse()?,
+                        "--image" => image = Some(option_value(&mut args, &arg)?.into()),
+                        "--animate" => animate = true,
+                        "--stream" => stream = true,
+                        "--jobs" => jobs = Some(option_value(&mut args, &arg)?.parse()?),
+                        "--trace" => trace_level = Some(option_value(&mut args, &arg)?.parse()?),
+                        "--trace-file" => trace_path = Some(option_value(&mut args, &arg)?.into()),
+                        "--set" => {
+                            let value = option_value(&mut args, &arg)?;
+                            let (name, value) = value.split_once('=').ok_or_else(|| {
+                                anyhow!("invalid --set, expected <name>=<value>: {}", value)
+                            })?;
+                            parameters.push((name.to_string(), value.to_string()));
+                        }
+                        "--every" => options.every = option_value(&mut args, &arg)?.parse()?,
+                        "--delay" => {
+                            let delay = option_value(&mut args, &arg)?.parse()?;
+                            options.delay = std::time::Duration::from_millis(delay);
+                        }
+                        "--viewport" => {
+                            options.viewport = Some(option_value(&mut args, &arg)?.parse()?)
+                        }
+                        "--input" => source = option_value(&mut args, &arg)?.parse()?,
+                        "--inputs" => {
+                            source = Source::Directory(option_value(&mut args, &arg)?.into())
+                        }
+                        _ if arg.starts_with("--") => {
+                            return Err(anyhow!("unknown option: {}\n{}", arg, USAGE))
+                        }
+                        _ if selection.is_none() => selection = Some(arg.parse()?),
+                        _ => return Err(anyhow!("unexpected argument: {}\n{}", arg, USAGE)),
+                    }
+                }
+                let selection: Selection =
+                    selection.ok_or_else(|| anyhow!("no day given\n{}", USAGE))?;
+                if selection == Selection::All && !matches!(source, Source::Directory(_)) {
+                    return Err(anyhow!("--input can only be used with a single day"));
+                }
+                if selection == Selection::All && image.is_some() {
+                    return Err(anyhow!("--image can only be used with a single day"));
+                }
+                if animate && (selection == Selection::All || format == Format::Json) {
+                    return Err(anyhow!("--animate needs a single day and text output"));
+                }
+                if options.every == 0 {
+                    return Err(anyhow!("--every must be at least 1"));
+                }
+                if stream
+                    && (selection == Selection::All
+                        || format == Format::Json
+                        || image.is_some()
+                        || animate)
+                {
+                    return Err(anyhow!(
+                        "--stream needs a single day and text output, without --image or --animate"
+                    ));
+                }
+                if !parameters.is_empty() {
+                    if selection == Selection::All {
+                        return Err(anyhow!("--set can only be used with a single day"));
+                    }
+                    for day in selection.days() {
+                        let _ = day.solver.configure(&parameters)?;
+                    }
+                }
+                let trace = match (trace_level, trace_path) {
+                    (Some(level), path) => Some(trace::Options { level, path }),
+                    (None, Some(_)) => return Err(anyhow!("--trace-file needs --trace")),
+                    (None, None) => None,
+                };
+                if jobs.is_some() && trace.is_some() {
+                    return Err(anyhow!("--trace can't be used with --jobs"));
+                }
+                if jobs == Some(0) {
+                    return Err(anyhow!("--jobs must be at least 1"));
+                }
+                if jobs.is_some()
+                    && (format == Format::Json || image.is_some() || animate || stream)
+                {
+                    return Err(anyhow!(
+                        "--jobs needs text output, without --image, --animate or --stream"
+                    ));
+                }
+                Ok(Command::Run {
+                    selection,
+                    part,
+                    source,
+                    format,
+                    image,
+                    animation: animate.then_some(options),
+                    stream,
+                    jobs,
+                    parameters,
+                    trace,
+                })
+            }
+            "check" => {
+                let day = args
+                    .next()
+                    .ok_or_else(|| anyhow!("no day given\n{}", USAGE))?;
+                let day = match day.parse()? {
+                    Selection::Day(day) => day,
+                    Selection::All => return Err(anyhow!("check needs a single day")),
+                };
+                let source = args
+                    .next()
+                    .ok_or_else(|| anyhow!("no input given\n{}", USAGE))?
+                    .parse()?;
+                if let Some(arg) = args.next() {
+                    return Err(anyhow!("unexpected argument: {}\n{}", arg, USAGE));
+                }
+                Ok(Command::Check { day, source })
+            }
+            "bench" => {
+                let mut selection = None;
+                let mut source = Source::default();
+                let mut warmup = bench::DEFAULT_WARMUP;
+                let mut iterations = bench::DEFAULT_ITERATIONS;
+                let mut memory = false;
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--inputs" => {
+                            source = Source::Directory(option_value(&mut args, &arg)?.into())
+                        }
+                        "--warmup" => warmup = option_value(&mut args, &arg)?.parse()?,
+                        "--iterations" => iterations = option_value(&mut args, &arg)?.parse()?,
+                        "--memory" => memory = true,
+                        _ if arg.starts_with("--") => {
+                            return Err(anyhow!("unknown option: {}\n{}", arg, USAGE))
+                        }
+                        _ if selection.is_none() => selection = Some(arg.parse()?),
+                        _ => return Err(anyhow!("unexpected argument: {}\n{}", arg, USAGE)),
+                    }
+                }
+                if iterations == 0 {
+                    return Err(anyhow!("--iterations must be at least 1"));
+                }
+                Ok(Command::Bench {
+                    selection: selection.unwrap_or(Selection::All),
+                    source,
+                    warmup,
+                    iterations,
+                    memory,
+                })
+            }
+            "gen" => {
+                let mut day = None;
+                let mut seed = 0;
+                let mut size = 10;
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--seed" => seed = option_value(&mut args, &arg)?.parse()?,
+                        "--size" => size = option_value(&mut args, &arg)?.parse()?,
+                        _ if arg.starts_with("--") => {
+                            return Err(anyhow!("unknown option: {}\n{}", arg, USAGE))
+                        }
+                        _ if day.is_none() => match arg.parse()? {
+                            Selection::Day(number) => day = Some(number),
+                            Selection::All => return Err(anyhow!("gen needs a single day")),
+                        },
+                        _ => return Err(anyhow!("unexpected argument: {}\n{}", arg, USAGE)),
+                    }
+                }
+                let day = day.ok_or_else(|| anyhow!("no day given\n{}", USAGE))?;
+                Ok(Command::Gen { day, seed, size })
+            }
+            "diff" => {
+                let mut selection = None;
+                let mut seeds = differential::DEFAULT_SEEDS;
+                let mut size = differential::DEFAULT_SIZE;
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--seeds" => seeds = option_value(&mut args, &arg)?.parse()?,
+                        "--size" => size = option_value(&mut args, &arg)?.parse()?,
+                        _ if arg.starts_with("--") => {
+                            return Err(anyhow!("unknown option: {}\n{}", arg, USAGE))
+                        }
+                        _ if selection.is_none() => selection = Some(arg.parse()?),
+                        _ => return Err(anyhow!("unexpected argument: {}\n{}", arg, USAGE)),
+                    }
+                }
+                Ok(Command::Diff {
+                    selection: selection.unwrap_or(Selection::All),
+                    seeds,
+                    size,
+                })
+            }
+            "fuzz" => {
+                let mut selection = None;
+                let mut cases = fuzz::DEFAULT_CASES;
+                let mut seed = 0;
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--cases" => cases = option_value(&mut args, &arg)?.parse()?,
+                        "--seed" => seed = option_value(&mut args, &arg)?.parse()?,
+                        _ if arg.starts_with("--") => {
+                            return Err(anyhow!("unknown option: {}\n{}", arg, USAGE))
+                        }
+                        _ if selection.is_none() => selection = Some(arg.parse()?),
+                        _ => return Err(anyhow!("unexpected argument: {}\n{}", arg, USAGE)),
+                    }
+                }
+                Ok(Command::Fuzz {
+                    selection: selection.unwrap_or(Selection::All),
+                    cases,
+                    seed,
+                })
+            }
+            "verify" => {
+                let mut selection = None;
+                let mut source = Source::default();
+                let mut answers = PathBuf::from(answers::DEFAULT_PATH);
+                let mut record = false;
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--inputs" => {
+                            source = Source::Directory(option_value(&mut args, &arg)?.into())
+                        }
+                        "--answers" => answers = option_value(&mut args, &arg)?.into(),
+                        "--record" => record = true,
+                        _ if arg.starts_with("--") => {
+                            return Err(anyhow!("unknown option: {}\n{}", arg, USAGE))
+                        }
+                        _ if selection.is_none() => selection = Some(arg.parse()?),
+                        _ => return Err(anyhow!("unexpected argument: {}\n{}", arg, USAGE)),
+                    }
+                }
+                Ok(Command::Verify {
+                    selection: selection.unwrap_or(Selection::All),
+                    source,
+                    answers,
+                    record,
+                })
+            }
+            "examples" => {
+                let mut selection = None;
+                for arg in args {
+                    match arg.as_str() {
+                        _ if arg.starts_with("--") => {
+                            return Err(anyhow!("unknown option: {}\n{}", arg, USAGE))
+                        }
+                        _ if selection.is_none() => selection = Some(arg.parse()?),
+                        _ => return Err(anyhow!("unexpected argument: {}\n{}", arg, USAGE)),
+                    }
+                }
+                Ok(Command::Examples {
+                    selection: selection.unwrap_or(Selection::All),
+                })
+            }
+            "serve" => {
+                let mut port = serve::DEFAULT_PORT;
+                let mut source = Source::default();
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--port" => port = option_value(&mut args, &arg)?.parse()?,
+                        "--inputs" => {
+                            source = Source::Directory(option_value(&mut args, &arg)?.into())
+                        }
+                        _ if arg.starts_with("--") => {
+                            return Err(anyhow!("unknown option: {}\n{}", arg, USAGE))
+                        }
+                        _ => return Err(anyhow!("unexpected argument: {}\n{}", arg, USAGE)),
+                    }
+                }
+                Ok(Command::Serve { port, source })
+            }
+            "list" => {
+                if let Some(arg) = args.next() {
+                    Err(anyhow!("unexpected argument: {}\n{}", arg, USAGE))
+                } else {
+                    Ok(Command::List)
+                }
+            }
+            _ => Err(anyhow!("unknown command: {}\n{}", command, USAGE)),
+        }
+    }
+
+    fn execute(&self) -> Result<()> {
+        if let Command::Run {
+            trace: Some(options),
+            ..
+        } = self
+        {
+            trace::start(options)?;
+        }
+        match self {
+            Command::Run {
+                selection,
+                part,
+                source,
+                jobs: Some(jobs),
+                parameters,
+                ..
+            } => run_in_parallel(*selection, *part, source, *jobs, parameters),
+            Command::Run {
+                selection,
+                part,
+                source,
+                stream: true,
+                parameters,
+                ..
+            } => {
+                for day in selection.days() {
+                    trace::day(day.number);
+                    println!("Day {:02}: {}", day.number, day.title);
+                    let answers = day
+                        .solver
+                        .configure(parameters)?
+                        .stream(&mut source.open(day)?)?
+                        .ok_or_else(|| anyhow!("day {:02} can't stream its input", day.number))?;
+                    let parts = Part::selected(*part);
+                    for (part, answer) in [Part::One, Part::Two].into_iter().zip(answers) {
+                        if parts.contains(&part) {
+                            print_answer(part, &answer);
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Command::Run {
+                selection,
+                part,
+                source,
+                format: Format::Text,
+                image,
+                animation,
+                parameters,
+                ..
+            } => {
+                for day in selection.days() {
+                    trace::day(day.number);
+                    let solver = day.solver.configure(parameters)?;
+                    let input = source.read(day)?;
+                    if let Some(options) = animation {
+                        let title = format!("Day {:02}: {}", day.number, day.title);
+                        let mut animation =
+                            Animation::new(Box::new(std::io::stdout()), title, *options);
+                        if !solver.animate(&input, &mut animation)? {
+                            return Err(anyhow!("day {:02} has no animation", day.number));
+                        }
+                    }
+                    println!("Day {:02}: {}", day.number, day.title);
+                    let parts = Part::selected(*part);
+                    let answers = solver.solve(&input, &parts)?;
+                    for (part, answer) in parts.into_iter().zip(answers) {
+                        print_answer(part, &answer);
+                    }
+                    if let Some(path) = image {
+                        save_image(day, &*solver, &input, path)?;
+                        println!("Image: {}", path.display());
+                    }
+                }
+                Ok(())
+            }
+            Command::Run {
+                selection,
+                part,
+                source,
+                format: Format::Json,
+                image,
+                parameters,
+                ..
+            } => {
+                let mut records = Vec::new();
+                let mut failures = 0;
+                for day in selection.days() {
+                    trace::day(day.number);
+                    let solver = day.solver.configure(parameters)?;
+                    let parts = Part::selected(*part);
+                    let input = source.read(day);
+                    if let (Ok(input), Some(path)) = (&input, image) {
+                        save_image(day, &*solver, input, path)?;
+                    }
+                    let outcomes = input.and_then(|input| solver.run(&input, &parts));
+                    match outcomes {
+                        Ok(outcomes) => {
+                            for outcome in outcomes {
+                                let elapsed = i64::try_from(outcome.elapsed.as_micros()).ok();
+                                let (answer, error) = match outcome.answer {
+                                    Ok(answer) => (Some(answer), None),
+                                    Err(err) => (None, Some(err.to_string())),
+                                };
+                                failures += usize::from(error.is_some());
+                                records.push(record(
+                                    day.number,
+                                    outcome.part,
+                                    answer,
+                                    elapsed,
+                                    error,
+                                ));
+                            }
+                        }
+                        Err(err) => {
+                            failures += parts.len();
+                            for part in parts {
+                                records.push(record(
+                                    day.number,
+                                    part,
+                                    None,
+                                    None,
+                                    Some(err.to_string()),
+                                ));
+                            }
+                        }
+                    }
+                }
+                println!("{}", Value::Array(records));
+                if failures > 0 {
+                    Err(anyhow!("{} part(s) failed", failures))
+                } else {
+                    Ok(())
+                }
+            }
+            Command::Check { day, source } => {
+                let day = Selection::Day(*day)
+                    .days()
+                    .next()
+                    .ok_or_else(|| anyhow!("no solution for day: {}", day))?;
+                let input = source.read(day)?;
+                let errors = day.solver.check(&input);
+                if errors.is_empty() {
+                    println!("Day {:02}: ok", day.number);
+                    return Ok(());
+                }
+                for err in &errors {
+                    eprintln!("{}\n", err);
+                }
+                Err(anyhow!(
+                    "day {:02}: {} malformed input{}",
+                    day.number,
+                    errors.len(),
+                    if errors.len() == 1 { "" } else { "s" }
+                ))
+            }
+            Command::Bench {
+                selection,
+                source,
+                warmup,
+                iterations,
+                memory,
+            } => {
+                print!("Day  Stage  {:>10}  {:>10}  {:>10}", "Min", "Median", "Max");
+                if *memory {
+                    print!(
+                        "  {:>11}  {:>10}  {:>10}",
+                        "Allocations", "Allocated", "Peak heap"
+                    );
+                }
+                println!();
+                for day in selection.days() {
+                    let input = source.read(day)?;
+                    for _ in 0..*warmup {
+                        let _ = day.solver.time(&input)?;
+                    }
+                    let timings = (0..*iterations)
+                        .map(|_| day.solver.time(&input))
+                        .collect::<Result<Vec<_>>>()?;
+                    let report = bench::Report::new(&timings)
+                        .ok_or_else(|| anyhow!("no iterations were run"))?;
+                    // Allocations don't vary between runs, so one is enough.
+                    let usage = if *memory {
+                        let usage = day.solver.memory(&input)?;
+                        [Some(usage.parse), Some(usage.part1), Some(usage.part2)]
+                    } else {
+                        [None; 3]
+                    };
+                    for ((stage, stats), usage) in [
+                        ("parse", report.parse),
+                        ("part1", report.part1),
+                        ("part2", report.part2),
+                    ]
+                    .into_iter()
+                    .zip(usage)
+                    {
+                        print!(
+                            "{:02}   {}  {:>10}  {:>10}  {:>10}",
+                            day.number,
+                            stage,
+                            bench::format(stats.min),
+                            bench::format(stats.median),
+                            bench::format(stats.max)
+                        );
+                        if let Some(usage) = usage {
+                            print!(
+                                "  {:>11}  {:>10}  {:>10}",
+                                usage.allocations,
+                                memory::format(usage.bytes),
+                                memory::format(usage.peak_heap)
+                            );
+                        }
+                        println!();
+                    }
+                }
+                Ok(())
+            }
+            Command::Gen { day, seed, size } => {
+                let day = Selection::Day(*day)
+                    .days()
+                    .next()
+                    .ok_or_else(|| anyhow!("no solution for day: {}", day))?;
+                print!("{}", day.solver.generate(&mut Rng::new(*seed), *size));
+                Ok(())
+            }
+            Command::Diff {
+                selection,
+                seeds,
+                size,
+            } => {
+                let mut failures = 0;
+                for day in selection.days() {
+                    match differential::run(day.solver, 0..*seeds, *size) {
+                        None => println!("Day {:02}: ok ({} inputs)", day.number, seeds),
+                        Some(disagreement) => {
+                            failures += 1;
+                            println!(
+                                "Day {:02}: {} disagrees on seed {}",
+                                day.number, disagreement.part, disagreement.seed
+                            );
+                            println!("solution:\n{}", disagreement.answer.trim_end());
+                            println!("reference:\n{}", disagreement.reference.trim_end());
+                            print!("minimised input:\n{}", disagreement.input);
+                        }
+                    }
+                }
+                if failures == 0 {
+                    Ok(())
+                } else {
+                    Err(anyhow!("{} day(s) disagree with their reference", failures))
+                }
+            }
+            Command::Fuzz {
+                selection,
+                cases,
+                seed,
+            } => {
+                // The crashes are reported below, so keep the default hook from printing each one.
+                let hook = std::panic::take_hook();
+                std::panic::set_hook(Box::new(|_| {}));
+                let mut failures = 0;
+                let mut result = Ok(());
+                for day in selection.days() {
+                    match fuzz::run(day, *seed, *cases) {
+                        Ok(None) => println!("Day {:02}: ok ({} cases)", day.number, cases),
+                        Ok(Some(crash)) => {
+                            failures += 1;
+                            println!("Day {:02}: panicked: {}", day.number, crash.message);
+                            println!("minimised input:\n{}", crash.input);
+                        }
+                        Err(err) => {
+                            result = Err(err);
+                            break;
+                        }
+                    }
+                }
+                std::panic::set_hook(hook);
+                result?;
+                if failures == 0 {
+                    Ok(())
+                } else {
+                    Err(anyhow!("{} day(s) panicked", failures))
+                }
+            }
+            Command::Verify {
+                selection,
+                source,
+                answers,
+                record,
+            } => verify(*selection, source, answers, *record),
+            Command::Examples { selection } => run_examples(*selection),
+            Command::Serve { port, source } => serve::run(*port, source),
+            Command::List => {
+                for day in &DAYS {
+                    let parameters: Vec<String> = day
+                        .solver
+                        .parameters()
+                        .into_iter()
+                        .map(|(name, value)| format!("{}={}", name, value))
+                        .collect();
+                    if parameters.is_empty() {
+                        println!("{:02} {}", day.number, day.title);
+                    } else {
+                        println!(
+                            "{:02} {} ({})",
+                            day.number,
+                            day.title,
+                            parameters.join(", ")
+                        );
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Selection {
+    fn days(&self) -> impl Iterator<Item = &'static Day> {
+        let selection = *self;
+        DAYS.iter().filter(move |day| match selection {
+            Selection::All => true,
+            Selection::Day(number) => day.number == number,
+        })
+    }
+}
+
+impl FromStr for Format {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Format> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(anyhow!("invalid format: {}", s)),
+        }
+    }
+}
+
+impl FromStr for Selection {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Selection> {
+        if s == "all" {
+            Ok(Selection::All)
+        } else {
+            let number: u8 = s.parse().map_err(|_| anyhow!("invalid day: {}", s))?;
+            if DAYS.iter().any(|day| day.number == number) {
+                Ok(Selection::Day(number))
+            } else {
+                Err(anyhow!("no solution for day: {}", number))
+            }
+        }
+    }
+}
+
+fn verify(selection: Selection, source: &Source, path: &Path, record: bool) -> Result<()> {
+    let mut answers = Answers::load(path)?;
+    let mut failures = 0;
+    println!("Day  Part  Result    Answer");
+    for day in selection.days() {
+        let parts = [Part::One, Part::Two];
+        let result = source.read(day).and_then(|input| {
+            let actual = day.solver.solve(&input, &parts)?;
+            Ok((input, actual))
+        });
+        let (input, actual) = match result {
+            Ok(result) => result,
+            Err(err) => {
+                failures += 1;
+                let message = err.to_string();
+                let message = message.lines().next().unwrap_or_default();
+                println!("{:02}   -     error     {}", day.number, message);
+                continue;
+            }
+        };
+        for (part, actual) in parts.into_iter().zip(actual) {
+            let key = Key::new(day.number, part, &input);
+            let (result, answer) = if record {
+                let answer = summary(&actual);
+                answers.insert(key, actual);
+                ("recorded", answer)
+            } else {
+                match answers.get(&key) {
+                    Some(expected) if expected == actual => ("pass", summary(&actual)),
+                    Some(expected) => {
+                        failures += 1;
+                        let answer =
+                            format!("expected {}, got {}", summary(expected), summary(&actual));
+                        ("FAIL", answer)
+                    }
+                    None => ("missing", summary(&actual)),
+                }
+            };
+            println!(
+                "{:02}   {}     {:<9} {}",
+                day.number,
+                part.number(),
+                result,
+                answer
+            );
+        }
+    }
+    if record {
+        answers.save(path)?;
+    }
+    if failures > 0 {
+        Err(anyhow!("{} answer(s) did not verify", failures))
+    } else {
+        Ok(())
+    }
+}
+
+// Checks every example the selected days have and prints a line for each part it answers.
+fn run_examples(selection: Selection) -> Result<()> {
+    let mut failures = 0;
+    println!("Day  Example           Part  Result  Answer");
+    for day in selection.days() {
+        for check in examples::check(day) {
+            let (result, answer) = match &check.answer {
+                Ok(answer) if check.passed() => ("pass", summary(answer)),
+                Ok(answer) => (
+                    "FAIL",
+                    format!(
+                        "expected {}, got {}",
+                        summary(check.expected),
+                        summary(answer)
+                    ),
+                ),
+                Err(err) => ("error", err.lines().next().unwrap_or_default().to_string()),
+            };
+            if !check.passed() {
+                failures += 1;
+            }
+            println!(
+                "{:02}   {:<16}  {}     {:<6}  {}",
+                day.number,
+                check.example,
+                check.part.number(),
+                result,
+                answer
+            );
+        }
+    }
+    if failures > 0 {
+        Err(anyhow!("{} example(s) failed", failures))
+    } else {
+        Ok(())
+    }
+}
+
+// Parses every selected day and then runs each of their parts as tasks on the pool, printing
+// each day once it's done along with the time its tasks spent running. That's close to its CPU
+// time, since each task runs on a single thread.
+fn run_in_parallel(
+    selection: Selection,
+    part: Option<Part>,
+    source: &Source,
+    jobs: usize,
+    parameters: &[(String, String)],
+) -> Result<()> {
+    let start = Instant::now();
+    let days: Vec<&Day> = selection.days().collect();
+    let solvers = days
+        .iter()
+        .map(|day| day.solver.configure(parameters))
+        .collect::<Result<Vec<_>>>()?;
+    let inputs = days
+        .iter()
+        .map(|day| source.read(day))
+        .collect::<Result<Vec<_>>>()?;
+    let parsing: Vec<pool::Task<(Result<Prepared>, Duration)>> = solvers
+        .iter()
+        .zip(&inputs)
+        .map(|(solver, input)| -> pool::Task<_> {
+            Box::new(move || timed(|| solver.prepare(input)))
+        })
+        .collect();
+    let mut prepared = Vec::new();
+    pool::run(jobs, parsing, |_, result| {
+        prepared.push(match result {
+            Ok((solver, elapsed)) => (solver.map_err(|err| err.to_string()), elapsed),
+            Err(_) => (Err("panicked".to_string()), Duration::ZERO),
+        })
+    });
+    let parts = Part::selected(part);
+    let mut solving: Vec<pool::Task<(Result<String>, Duration)>> = Vec::new();
+    for (solver, _) in &prepared {
+        for &part in &parts {
+            solving.push(Box::new(move || match solver {
+                Ok(solver) => timed(|| solver(part)),
+                Err(err) => (Err(anyhow!("{}", err)), Duration::ZERO),
+            }));
+        }
+    }
+    let mut failures = 0;
+    let mut cpu = Duration::ZERO;
+    let mut day_cpu = Duration::ZERO;
+    pool::run(jobs, solving, |i, result| {
+        let (day, part) = (i / parts.len(), parts[i % parts.len()]);
+        if i % parts.len() == 0 {
+            println!("Day {:02}: {}", days[day].number, days[day].title);
+            day_cpu = prepared[day].1;
+        }
+        let answer = match result {
+            Ok((answer, elapsed)) => {
+                day_cpu += elapsed;
+                answer
+            }
+            Err(_) => Err(anyhow!("panicked")),
+        };
+        match answer {
+            Ok(answer) => print_answer(part, &answer),
+            Err(err) => {
+                failures += 1;
+                println!("{}: error: {}", part, err);
+            }
+        }
+        if i % parts.len() == parts.len() - 1 {
+            println!("CPU: {}", bench::format(day_cpu));
+            cpu += day_cpu;
+        }
+    });
+    println!(
+        "Total: {} wall clock, {} CPU on {} job(s)",
+        bench::format(start.elapsed()),
+        bench::format(cpu),
+        jobs
+    );
+    if failures > 0 {
+        Err(anyhow!("{} part(s) failed", failures))
+    } else {
+        Ok(())
+    }
+}
+
+fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+fn print_answer(part: Part, answer: &str) {
+    if answer.contains('\n') {
+        println!("{}:\n{}", part, answer.trim_end());
+    } else {
+        println!("{}: {}", part, answer);
+    }
+}
+
+fn save_image(day: &Day, solver: &dyn Solver, input: &str, path: &Path) -> Result<()> {
+    solver
+        .render(input)?
+        .ok_or_else(|| anyhow!("day {:02} has no image", day.number))?
+        .save(path)
+        .map_err(|err| anyhow!("could not write {}: {}", path.display(), err))
+}
+
+fn record(
+    day: u8,
+    part: Part,
+    answer: Option<String>,
+    elapsed_us: Option<i64>,
+    error: Option<String>,
+) -> Value {
+    Value::object([
+        ("day", i64::from(day).into()),
+        ("part", i64::from(part.number()).into()),
+        ("answer", answer.into()),
+        ("elapsed_us", elapsed_us.into()),
+        ("error", error.into()),
+    ])
+}
+
+fn summary(answer: &str) -> String {
+    let lines = answer.trim_end().lines().count();
+    if lines > 1 {
+        format!("[{} lines]", lines)
+    } else {
+        answer.to_string()
+    }
+}
+
+fn option_value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String> {
+    args.next()
+        .ok_or_else(|| anyhow!("missing value for {}", option))
+}
+
+#[cfg(test)]
+fn args(s: &str) -> Vec<String> {
+    s.split_ascii_whitespace().map(String::from).collect()
+}
+
+#[test]
+fn parse_run() {
+    assert_eq!(
+        Command::from_args(args("run 7 --part 2")).unwrap(),
+        Command::Run {
+            selection: Selection::Day(7),
+            part: Some(Part::Two),
+            source: Source::default(),
+            format: Format::Text,
+            image: None,
+            animation: None,
+            stream: false,
+            jobs: None,
+            parameters: Vec::new(),
+            trace: None,
+        }
+    );
+    assert_eq!(
+        Command::from_args(args("run 7 --input -")).unwrap(),
+        Command::Run {
+            selection: Selection::Day(7),
+            part: None,
+            source: Source::Stdin,
+            format: Format::Text,
+            image: None,
+            animation: None,
+            stream: false,
+            jobs: None,
+            parameters: Vec::new(),
+            trace: None,
+        }
+    );
+    assert_eq!(
+        Command::from_args(args("run all --format json")).unwrap(),
+        Command::Run {
+            selection: Selection::All,
+            part: None,
+            source: Source::default(),
+            format: Format::Json,
+            image: None,
+            animation: None,
+            stream: false,
+            jobs: None,
+            parameters: Vec::new(),
+            trace: None,
+        }
+    );
+    assert_eq!(
+        Command::from_args(args("run 14 --image cave.ppm")).unwrap(),
+        Command::Run {
+            selection: Selection::Day(14),
+            part: None,
+            source: Source::default(),
+            format: Format::Text,
+            image: Some("cave.ppm".into()),
+            animation: None,
+            stream: false,
+            jobs: None,
+            parameters: Vec::new(),
+            trace: None,
+        }
+    );
+    assert_eq!(
+        Command::from_args(args(
+            "run 9 --animate --every 10 --delay 0 --viewport 40x10"
+        ))
+        .unwrap(),
+        Command::Run {
+            selection: Selection::Day(9),
+            part: None,
+            source: Source::default(),
+            format: Format::Text,
+            image: None,
+            animation: Some(animation::Options {
+                every: 10,
+                delay: std::time::Duration::ZERO,
+                viewport: Some("40x10".parse().unwrap()),
+            }),
+            stream: false,
+            jobs: None,
+            parameters: Vec::new(),
+            trace: None,
+        }
+    );
+    assert!(Command::from_args(args("run all --animate")).is_err());
+    assert!(Command::from_args(args("run 9 --animate --every 0")).is_err());
+    assert_eq!(
+        Command::from_args(args("run 1 --stream --input -")).unwrap(),
+        Command::Run {
+            selection: Selection::Day(1),
+            part: None,
+            source: Source::Stdin,
+            format: Format::Text,
+            image: None,
+            animation: None,
+            stream: true,
+            jobs: None,
+            parameters: Vec::new(),
+            trace: None,
+        }
+    );
+    assert!(Command::from_args(args("run 1 --stream --format json")).is_err());
+    assert_eq!(
+        Command::from_args(args("run all --jobs 4 --part 2")).unwrap(),
+        Command::Run {
+            selection: Selection::All,
+            part: Some(Part::Two),
+            source: Source::default(),
+            format: Format::Text,
+            image: None,
+            animation: None,
+            stream: false,
+            jobs: Some(4),
+            parameters: Vec::new(),
+            trace: None,
+        }
+    );
+    assert_eq!(
+        Command::from_args(args("run 9 --set long-rope=3 --set short-rope=3")).unwrap(),
+        Command::Run {
+            selection: Selection::Day(9),
+            part: None,
+            source: Source::default(),
+            format: Format::Text,
+            image: None,
+            animation: None,
+            stream: false,
+            jobs: None,
+            parameters: vec![
+                ("long-rope".to_string(), "3".to_string()),
+                ("short-rope".to_string(), "3".to_string()),
+            ],
+            trace: None,
+        }
+    );
+    assert_eq!(
+        Command::from_args(args("run 5 --trace state --trace-file 05.log --part 1")).unwrap(),
+        Command::Run {
+            selection: Selection::Day(5),
+            part: Some(Part::One),
+            source: Source::default(),
+            format: Format::Text,
+            image: None,
+            animation: None,
+            stream: false,
+            jobs: None,
+            parameters: Vec::new(),
+            trace: Some(trace::Options {
+                level: trace::Level::State,
+                path: Some("05.log".into()),
+            }),
+        }
+    );
+    assert!(Command::from_args(args("run 5 --trace-file 05.log")).is_err());
+    assert!(Command::from_args(args("run 5 --trace everything")).is_err());
+    assert!(Command::from_args(args("run all --trace steps --jobs 2")).is_err());
+    assert!(Command::from_args(args("run all --set long-rope=3")).is_err());
+    assert!(Command::from_args(args("run 9 --set long-rope")).is_err());
+    assert!(Command::from_args(args("run 9 --set knots=3")).is_err());
+    assert!(Command::from_args(args("run 9 --set long-rope=many")).is_err());
+    assert!(Command::from_args(args("run 1 --set top=3")).is_err());
+    assert!(Command::from_args(args("run all --jobs 0")).is_err());
+    assert!(Command::from_args(args("run all --image all.ppm")).is_err());
+    assert!(Command::from_args(args("run all --input 07.txt")).is_err());
+    assert!(Command::from_args(args("run 42")).is_err());
+    assert!(Command::from_args(args("run 7 --part 3")).is_err());
+    assert!(Command::from_args(args("run 7 --format xml")).is_err());
+    assert!(Command::from_args(args("run")).is_err());
+}
+
+#[test]
+fn parse_check() {
+    assert_eq!(
+        Command::from_args(args("check 9 bad.txt")).unwrap(),
+        Command::Check {
+            day: 9,
+            source: Source::File("bad.txt".into()),
+        }
+    );
+    assert!(Command::from_args(args("check all bad.txt")).is_err());
+    assert!(Command::from_args(args("check 9")).is_err());
+}
+
+#[test]
+fn parse_bench() {
+    assert_eq!(
+        Command::from_args(args("bench 8 --iterations 5 --warmup 0")).unwrap(),
+        Command::Bench {
+            selection: Selection::Day(8),
+            source: Source::default(),
+            warmup: 0,
+            iterations: 5,
+            memory: false,
+        }
+    );
+    assert_eq!(
+        Command::from_args(args("bench --memory")).unwrap(),
+        Command::Bench {
+            selection: Selection::All,
+            source: Source::default(),
+            warmup: bench::DEFAULT_WARMUP,
+            iterations: bench::DEFAULT_ITERATIONS,
+            memory: true,
+        }
+    );
+    assert!(Command::from_args(args("bench --iterations 0")).is_err());
+    assert!(Command::from_args(args("bench --iterations x")).is_err());
+}
+
+#[test]
+fn parse_gen() {
+    assert_eq!(
+        Command::from_args(args("gen 13 --seed 7 --size 100")).unwrap(),
+        Command::Gen {
+            day: 13,
+            seed: 7,
+            size: 100,
+        }
+    );
+    assert!(Command::from_args(args("gen all")).is_err());
+    assert!(Command::from_args(args("gen --seed 7")).is_err());
+}
+
+#[test]
+fn generated_inputs_are_solvable() {
+    for day in &DAYS {
+        for seed in 0..5 {
+            let input = day.solver.generate(&mut Rng::new(seed), 10);
+            let parts = if day.number == 15 {
+                // Random sensors almost never leave exactly one gap for the distress beacon.
+                vec![Part::One]
+            } else {
+                vec![Part::One, Part::Two]
+            };
+            if let Err(err) = day.solver.solve(&input, &parts) {
+                panic!("day {} seed {}: {}\n{}", day.number, seed, err, input);
+            }
+        }
+    }
+}
+
+#[test]
+fn streams_agree() {
+    let mut streamed = Vec::new();
+    for day in &DAYS {
+        for seed in 0..5 {
+            let mut inputs = vec![day.solver.generate(&mut Rng::new(seed), 10)];
+            if seed == 0 {
+                inputs.push(day.example().to_string());
+            }
+            for input in inputs {
+                let Some(answers) = day.solver.stream(&mut input.as_bytes()).unwrap() else {
+                    continue;
+                };
+                let expected = day.solver.solve(&input, &[Part::One, Part::Two]).unwrap();
+                assert_eq!(answers, expected, "day {}\n{}", day.number, input);
+                streamed.push(day.number);
+            }
+        }
+    }
+    streamed.dedup();
+    assert_eq!(streamed, vec![1, 2, 3, 4, 6, 9, 10]);
+}
+
+#[test]
+fn messy_inputs_are_normalized() {
+    for day in &DAYS {
+        let lines: Vec<String> = day
+            .example()
+            .lines()
+            .map(|line| format!("{} \t", line))
+            .collect();
+        let messy = format!("\u{feff}{}\r\n\r\n", lines.join("\r\n"));
+        let parts = [Part::One, Part::Two];
+        assert_eq!(
+            day.solver.solve(&messy, &parts).ok(),
+            day.solver.solve(day.example(), &parts).ok(),
+            "day {}",
+            day.number
+        );
+        if let Some(answers) = day.solver.stream(&mut messy.as_bytes()).unwrap() {
+            assert_eq!(Some(answers), day.solver.solve(day.example(), &parts).ok());
+        }
+    }
+}
+
+#[test]
+fn parse_diff() {
+    assert_eq!(
+        Command::from_args(args("diff 11 --seeds 5")).unwrap(),
+        Command::Diff {
+            selection: Selection::Day(11),
+            seeds: 5,
+            size: differential::DEFAULT_SIZE,
+        }
+    );
+    assert!(Command::from_args(args("diff --size")).is_err());
+}
+
+#[test]
+fn parse_fuzz() {
+    assert_eq!(
+        Command::from_args(args("fuzz --cases 50 --seed 3")).unwrap(),
+        Command::Fuzz {
+            selection: Selection::All,
+            cases: 50,
+            seed: 3,
+        }
+    );
+    assert!(Command::from_args(args("fuzz 5 6")).is_err());
+}
+
+#[test]
+fn parse_verify() {
+    assert_eq!(
+        Command::from_args(args("verify")).unwrap(),
+        Command::Verify {
+            selection: Selection::All,
+            source: Source::default(),
+            answers: PathBuf::from(answers::DEFAULT_PATH),
+            record: false,
+        }
+    );
+    assert_eq!(
+        Command::from_args(args("verify 14 --record --answers a.txt")).unwrap(),
+        Command::Verify {
+            selection: Selection::Day(14),
+            source: Source::default(),
+            answers: PathBuf::from("a.txt"),
+            record: true,
+        }
+    );
+    assert!(Command::from_args(args("verify --input 14.txt")).is_err());
+}
+
+#[test]
+fn parse_examples() {
+    assert_eq!(
+        Command::from_args(args("examples")).unwrap(),
+        Command::Examples {
+            selection: Selection::All
+        }
+    );
+    assert_eq!(
+        Command::from_args(args("examples 6")).unwrap(),
+        Command::Examples {
+            selection: Selection::Day(6)
+        }
+    );
+    assert!(Command::from_args(args("examples 6 7")).is_err());
+    assert!(Command::from_args(args("examples --part 1")).is_err());
+}
+
+#[test]
+fn parse_serve() {
+    assert_eq!(
+        Command::from_args(args("serve")).unwrap(),
+        Command::Serve {
+            port: serve::DEFAULT_PORT,
+            source: Source::default(),
+        }
+    );
+    assert_eq!(
+        Command::from_args(args("serve --port 8080 --inputs elsewhere")).unwrap(),
+        Command::Serve {
+            port: 8080,
+            source: Source::Directory("elsewhere".into()),
+        }
+    );
+    assert!(Command::from_args(args("serve --port 70000")).is_err());
+    assert!(Command::from_args(args("serve 7")).is_err());
+}
+
+#[test]
+fn parse_list() {
+    assert_eq!(Command::from_args(args("list")).unwrap(), Command::List);
+    assert!(Command::from_args(args("list 7")).is_err());
+    assert!(Command::from_args(args("")).is_err());
+}
@@ -0,0 +1,153 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+// The system allocator, counting what each thread allocates so `aoc bench --memory` can report
+// it for each day and part.
+pub struct Counting;
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Usage {
+    pub allocations: u64,
+    pub bytes: u64,
+    // The most heap this thread had live at once, on top of whatever it already had. That's not
+    // resident memory: allocations on other threads and heap kept from earlier stages are missed.
+    pub peak_heap: u64,
+}
+
+#[derive(Clone, Copy)]
+struct Counters {
+    allocations: u64,
+    bytes: u64,
+    // Signed, since memory can be freed on a different thread than it was allocated on.
+    live: i64,
+    peak: i64,
+}
+
+thread_local! {
+    // Const-initialised and without a destructor, so using it never allocates.
+    static COUNTERS: Cell<Counters> = const {
+        Cell::new(Counters {
+            allocations: 0,
+            bytes: 0,
+            live: 0,
+            peak: 0,
+        })
+    };
+}
+
+// Runs `f` and returns what it allocated on this thread.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Usage) {
+    let start = COUNTERS.with(|counters| {
+        let mut start = counters.get();
+        start.peak = start.live;
+        counters.set(start);
+        start
+    });
+    let result = f();
+    let end = COUNTERS.with(Cell::get);
+    let usage = Usage {
+        allocations: end.allocations - start.allocations,
+        bytes: end.bytes - start.bytes,
+        peak_heap: u64::try_from(end.peak - start.live).unwrap_or(0),
+    };
+    (result, usage)
+}
+
+pub fn format(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2} {}", size, UNITS[unit])
+}
+
+fn record(allocated: usize, freed: usize, allocation: bool) {
+    // Fails only while the thread is being torn down, when nobody's measuring anyway.
+    let _ = COUNTERS.try_with(|counters| {
+        let mut current = counters.get();
+        if allocation {
+            current.allocations += 1;
+        }
+        current.bytes += allocated as u64;
+        current.live += allocated as i64 - freed as i64;
+        current.peak = current.peak.max(current.live);
+        counters.set(current);
+    });
+}
+
+// Everything is passed straight on to `System`, which upholds the `GlobalAlloc` contract.
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            record(layout.size(), 0, true);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            record(layout.size(), 0, true);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        record(0, layout.size(), false);
+    }
+
+    // Growing a buffer counts as an allocation of its new size, since that's what it may cost.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !ptr.is_null() {
+            record(new_size, layout.size(), true);
+        }
+        ptr
+    }
+}
+
+#[test]
+fn measuring() {
+    let (buffer, usage) = measure(|| vec![0u8; 1000]);
+    assert_eq!(
+        usage,
+        Usage {
+            allocations: 1,
+            bytes: 1000,
+            peak_heap: 1000
+        }
+    );
+    let (_, usage) = measure(|| {
+        drop(vec![0u8; 100]);
+        let mut small = Vec::with_capacity(50);
+        small.extend_from_slice(&buffer[..50]);
+        small.len()
+    });
+    assert_eq!(
+        usage,
+        Usage {
+            allocations: 2,
+            bytes: 150,
+            peak_heap: 100
+        }
+    );
+    let (_, usage) = measure(|| drop(buffer));
+    assert_eq!(usage.peak_heap, 0);
+}
+
+#[test]
+fn formatting() {
+    assert_eq!(format(512), "512 B");
+    assert_eq!(format(1536), "1.50 KiB");
+    assert_eq!(format(3 * 1024 * 1024), "3.00 MiB");
+}
@@ -0,0 +1,78 @@
+use anyhow::Result;
+use std::{borrow::Cow, io::BufRead};
+
+const BOM: char = '\u{feff}';
+
+// Irons out the differences editors and downloads leave behind, so parsers only ever see one
+// layout: no byte order mark, `\n` line endings, no trailing whitespace on any line, and exactly
+// one newline at the end. Inputs that are already like that are passed through untouched.
+pub fn input(input: &str) -> Cow<'_, str> {
+    let input = input.strip_prefix(BOM).unwrap_or(input);
+    if is_normal(input) {
+        return Cow::Borrowed(input);
+    }
+    let mut normalized = String::with_capacity(input.len() + 1);
+    for line in input.split('\n') {
+        normalized.push_str(line.trim_end());
+        normalized.push('\n');
+    }
+    let len = normalized.trim_end_matches('\n').len();
+    normalized.truncate(len);
+    if !normalized.is_empty() {
+        normalized.push('\n');
+    }
+    Cow::Owned(normalized)
+}
+
+// The same for a single line, with or without its line ending, for inputs read a line at a time.
+pub fn line(line: &str) -> &str {
+    line.trim_end()
+}
+
+// Skips the byte order mark at the start of `reader`, if there is one.
+pub fn skip_bom(reader: &mut dyn BufRead) -> Result<()> {
+    let mut bom = [0; 3];
+    let _ = BOM.encode_utf8(&mut bom);
+    if reader.fill_buf()?.starts_with(&bom) {
+        reader.consume(bom.len());
+    }
+    Ok(())
+}
+
+fn is_normal(input: &str) -> bool {
+    let ending =
+        input.is_empty() || (input.ends_with('\n') && !input.ends_with("\n\n") && input != "\n");
+    ending
+        && input
+            .split('\n')
+            .all(|line| line.len() == line.trim_end().len())
+}
+
+#[test]
+fn line_endings() {
+    assert_eq!(input("a\r\nb\r\n\r\nc\r\n"), "a\nb\n\nc\n");
+    assert_eq!(input("a\nb"), "a\nb\n");
+    assert_eq!(input("a\nb\n\n\n"), "a\nb\n");
+    assert_eq!(input("\n"), "");
+    assert_eq!(input(""), "");
+    assert!(matches!(input("a\n\nb\n"), Cow::Borrowed(_)));
+}
+
+#[test]
+fn whitespace() {
+    assert_eq!(input("    [D]    \n[N] [C]    \n"), "    [D]\n[N] [C]\n");
+    assert_eq!(input("1\n \t\n2\n"), "1\n\n2\n");
+    assert_eq!(line("  a b \r\n"), "  a b");
+}
+
+#[test]
+fn byte_order_mark() {
+    assert_eq!(input("\u{feff}a\r\n"), "a\n");
+    assert!(matches!(input("\u{feff}a\n"), Cow::Borrowed("a\n")));
+    let mut reader = "\u{feff}abc".as_bytes();
+    skip_bom(&mut reader).unwrap();
+    assert_eq!(reader, b"abc");
+    let mut reader = "abc".as_bytes();
+    skip_bom(&mut reader).unwrap();
+    assert_eq!(reader, b"abc");
+}
@@ -0,0 +1,80 @@
+use std::{
+    collections::BTreeMap,
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+pub type Task<'a, T> = Box<dyn FnOnce() -> T + Send + 'a>;
+
+// Runs the tasks on `jobs` threads and hands each result to `done` in the order the tasks were
+// given, as soon as it and everything before it have finished. A task that panics is passed on
+// as the panic's payload rather than taking its worker down with it.
+pub fn run<'a, T: Send>(
+    jobs: usize,
+    tasks: Vec<Task<'a, T>>,
+    mut done: impl FnMut(usize, thread::Result<T>),
+) {
+    let count = tasks.len();
+    let (task_sender, task_receiver) = mpsc::channel();
+    for task in tasks.into_iter().enumerate() {
+        // The receiver is still alive, so this can't fail.
+        let _ = task_sender.send(task);
+    }
+    drop(task_sender);
+    let task_receiver = Mutex::new(task_receiver);
+    let (result_sender, result_receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, count.max(1)) {
+            let result_sender = result_sender.clone();
+            let task_receiver = &task_receiver;
+            let _ = scope.spawn(move || loop {
+                let task = match task_receiver.lock() {
+                    Ok(receiver) => receiver.recv(),
+                    Err(_) => return,
+                };
+                let Ok((i, task)) = task else {
+                    return;
+                };
+                let result = panic::catch_unwind(AssertUnwindSafe(task));
+                if result_sender.send((i, result)).is_err() {
+                    return;
+                }
+            });
+        }
+        drop(result_sender);
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+        for (i, result) in result_receiver {
+            let _ = pending.insert(i, result);
+            while let Some(result) = pending.remove(&next) {
+                done(next, result);
+                next += 1;
+            }
+        }
+    });
+}
+
+#[test]
+fn in_order() {
+    let tasks: Vec<Task<usize>> = (0..20)
+        .map(|i| -> Task<usize> {
+            Box::new(move || {
+                // Later tasks finish first.
+                thread::sleep(std::time::Duration::from_millis(20 - i as u64));
+                i * i
+            })
+        })
+        .collect();
+    let mut results = Vec::new();
+    run(4, tasks, |i, result| results.push((i, result.unwrap())));
+    assert_eq!(results, (0..20).map(|i| (i, i * i)).collect::<Vec<_>>());
+}
+
+#[test]
+fn panics_are_results() {
+    let tasks: Vec<Task<u8>> = vec![Box::new(|| 1), Box::new(|| panic!("oops")), Box::new(|| 3)];
+    let mut results = Vec::new();
+    run(2, tasks, |_, result| results.push(result.ok()));
+    assert_eq!(results, vec![Some(1), None, Some(3)]);
+}
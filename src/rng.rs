@@ -0,0 +1,86 @@
+use std::ops::RangeInclusive;
+
+// A small seedable generator (SplitMix64) for producing test inputs. Not for cryptography.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // A uniform number in `0..n`, or zero if `n` is zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            0
+        } else {
+            (self.next_u64() % n as u64) as usize
+        }
+    }
+
+    pub fn range(&mut self, range: RangeInclusive<i64>) -> i64 {
+        let (start, end) = (*range.start(), *range.end());
+        if end <= start {
+            return start;
+        }
+        let span = end.abs_diff(start).saturating_add(1);
+        start.wrapping_add((self.next_u64() % span) as i64)
+    }
+
+    pub fn chance(&mut self, numerator: u64, denominator: u64) -> bool {
+        denominator > 0 && self.next_u64() % denominator < numerator
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            items.get(self.below(items.len()))
+        }
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+#[test]
+fn deterministic() {
+    let mut a = Rng::new(42);
+    let mut b = Rng::new(42);
+    let mut c = Rng::new(43);
+    let a: Vec<_> = (0..8).map(|_| a.next_u64()).collect();
+    let b: Vec<_> = (0..8).map(|_| b.next_u64()).collect();
+    let c: Vec<_> = (0..8).map(|_| c.next_u64()).collect();
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+}
+
+#[test]
+fn ranges() {
+    let mut rng = Rng::new(7);
+    for _ in 0..1000 {
+        assert!((-3..=3).contains(&rng.range(-3..=3)));
+        assert!(rng.below(5) < 5);
+    }
+    assert_eq!(rng.range(4..=4), 4);
+    assert_eq!(rng.below(0), 0);
+    assert_eq!(rng.choose::<u8>(&[]), None);
+    let mut items = [1, 2, 3, 4, 5];
+    rng.shuffle(&mut items);
+    items.sort();
+    assert_eq!(items, [1, 2, 3, 4, 5]);
+}
@@ -0,0 +1,360 @@
+use crate::{bench, input::Source, solution::Part, timed, Day, DAYS};
+use anyhow::{anyhow, Result};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    time::Duration,
+};
+
+pub const DEFAULT_PORT: u16 = 2022;
+
+// Pasted inputs bigger than this are turned away rather than read into memory, and so are request
+// and header lines longer than `MAX_LINE`.
+const MAX_BODY: usize = 16 * 1024 * 1024;
+const MAX_LINE: usize = 8 * 1024;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 60em; margin: 2em auto; }
+pre { background: #f4f4f4; padding: 0.5em; overflow-x: auto; }
+td, th { padding: 0.2em 1em; text-align: left; vertical-align: top; }
+.error { color: #b00; }";
+
+#[derive(Debug, PartialEq, Eq)]
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Response {
+    status: u16,
+    reason: &'static str,
+    body: String,
+}
+
+// Serves the dashboard on localhost, one connection at a time, until the program is killed.
+pub fn run(port: u16, source: &Source) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .map_err(|err| anyhow!("could not listen on port {}: {}", port, err))?;
+    println!("Serving on http://127.0.0.1:{}/", port);
+    for stream in listener.incoming() {
+        // One bad connection shouldn't take the server down.
+        if let Err(err) = stream
+            .map_err(Into::into)
+            .and_then(|stream| handle(stream, source))
+        {
+            eprintln!("error: {}", err);
+        }
+    }
+    Ok(())
+}
+
+fn handle(stream: TcpStream, source: &Source) -> Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let response = match read_request(&mut BufReader::new(&stream)) {
+        Ok(request) => respond(&request, source),
+        Err(err) => page(400, "Bad Request", "Bad request", &error(&err.to_string())),
+    };
+    write_response(&stream, &response)
+}
+
+fn read_request(reader: &mut impl BufRead) -> Result<Request> {
+    let mut line = String::new();
+    let _ = read_line(reader, &mut line)?;
+    let mut words = line.split_ascii_whitespace();
+    let (Some(method), Some(path), Some(_)) = (words.next(), words.next(), words.next()) else {
+        return Err(anyhow!("invalid request line: {}", line.trim_end()));
+    };
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        body: Vec::new(),
+    };
+    let mut length = 0;
+    loop {
+        if read_line(reader, &mut line)? == 0 {
+            return Err(anyhow!("connection closed in the headers"));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow!("invalid content length: {}", value.trim()))?;
+            }
+        }
+    }
+    if length > MAX_BODY {
+        return Err(anyhow!("input is over {} bytes", MAX_BODY));
+    }
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body)?;
+    Ok(request)
+}
+
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> Result<usize> {
+    line.clear();
+    let read = reader.by_ref().take(MAX_LINE as u64).read_line(line)?;
+    if read == MAX_LINE && !line.ends_with('\n') {
+        return Err(anyhow!("line is over {} bytes", MAX_LINE));
+    }
+    Ok(read)
+}
+
+fn write_response(mut writer: impl Write, response: &Response) -> Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.reason,
+        response.body.len()
+    )?;
+    writer.write_all(response.body.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn respond(request: &Request, source: &Source) -> Response {
+    let path = request.path.split('?').next().unwrap_or_default();
+    // Only a single day's number, like `/14` or `/01`, has a page.
+    let day = path
+        .strip_prefix('/')
+        .filter(|number| !number.is_empty() && number.bytes().all(|byte| byte.is_ascii_digit()))
+        .and_then(|number| number.parse::<u8>().ok())
+        .and_then(|number| DAYS.iter().find(|day| day.number == number));
+    match (request.method.as_str(), path, day) {
+        ("GET", "/", _) => index(),
+        ("GET", _, Some(day)) => match source.read(day) {
+            Ok(input) => day_page(day, &input, false),
+            Err(err) => page(
+                200,
+                "OK",
+                &heading(day),
+                &format!("{}{}", error(&err.to_string()), form(day, "")),
+            ),
+        },
+        ("POST", _, Some(day)) => match form_value(&request.body, "input") {
+            Some(input) => day_page(day, &input, true),
+            None => page(
+                400,
+                "Bad Request",
+                &heading(day),
+                &error("the form has no input"),
+            ),
+        },
+        ("GET" | "POST", _, _) => page(404, "Not Found", "Not found", &error(path)),
+        _ => page(
+            405,
+            "Method Not Allowed",
+            "Method not allowed",
+            &error(&request.method),
+        ),
+    }
+}
+
+fn index() -> Response {
+    let mut body = String::from("<ul>\n");
+    for day in &DAYS {
+        body.push_str(&format!(
+            "<li><a href=\"/{:02}\">{}</a></li>\n",
+            day.number,
+            escape(&heading(day))
+        ));
+    }
+    body.push_str("</ul>\n");
+    page(200, "OK", "Advent of Code 2022", &body)
+}
+
+// The answers to `input` with how long each step took, the final state if the day has one, and
+// the form, filled in with the input if it was pasted.
+fn day_page(day: &Day, input: &str, pasted: bool) -> Response {
+    let mut body = String::from("<p><a href=\"/\">All days</a></p>\n");
+    let (prepared, elapsed) = timed(|| day.solver.prepare(input));
+    match prepared {
+        Ok(prepared) => {
+            body.push_str("<table>\n<tr><th>Step</th><th>Answer</th><th>Time</th></tr>\n");
+            body.push_str(&format!(
+                "<tr><td>Parse</td><td></td><td>{}</td></tr>\n",
+                bench::format(elapsed)
+            ));
+            for part in [Part::One, Part::Two] {
+                let (answer, elapsed) = timed(|| prepared(part));
+                let answer = match answer {
+                    Ok(answer) if answer.contains('\n') => {
+                        format!("<pre>{}</pre>", escape(&answer))
+                    }
+                    Ok(answer) => escape(&answer),
+                    Err(err) => error(&err.to_string()),
+                };
+                body.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    part,
+                    answer,
+                    bench::format(elapsed)
+                ));
+            }
+            body.push_str("</table>\n");
+            match day.solver.state(input) {
+                Ok(Some(state)) => {
+                    body.push_str(&format!("<h2>State</h2>\n<pre>{}</pre>\n", escape(&state)))
+                }
+                Ok(None) => (),
+                Err(err) => body.push_str(&error(&err.to_string())),
+            }
+        }
+        Err(err) => body.push_str(&error(&err.to_string())),
+    }
+    body.push_str(&form(day, if pasted { input } else { "" }));
+    page(200, "OK", &heading(day), &body)
+}
+
+fn form(day: &Day, input: &str) -> String {
+    format!(
+        "<h2>Your own input</h2>
+<form method=\"post\" action=\"/{:02}\">
+<textarea name=\"input\" rows=\"20\" cols=\"80\">{}</textarea>
+<p><button type=\"submit\">Solve</button></p>
+</form>\n",
+        day.number,
+        escape(input)
+    )
+}
+
+fn page(status: u16, reason: &'static str, title: &str, body: &str) -> Response {
+    let title = escape(title);
+    Response {
+        status,
+        reason,
+        body: format!(
+            "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{}</title>
+<style>
+{}
+</style>
+</head>
+<body>
+<h1>{}</h1>
+{}</body>
+</html>\n",
+            title, STYLE, title, body
+        ),
+    }
+}
+
+fn heading(day: &Day) -> String {
+    format!("Day {:02}: {}", day.number, day.title)
+}
+
+fn error(message: &str) -> String {
+    format!("<pre class=\"error\">{}</pre>\n", escape(message))
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Pulls a field out of an `application/x-www-form-urlencoded` body.
+fn form_value(body: &[u8], name: &str) -> Option<String> {
+    body.split(|&byte| byte == b'&').find_map(|field| {
+        let value = field.strip_prefix(name.as_bytes())?.strip_prefix(b"=")?;
+        Some(percent_decode(value))
+    })
+}
+
+fn percent_decode(value: &[u8]) -> String {
+    let mut decoded = Vec::with_capacity(value.len());
+    let mut i = 0;
+    while i < value.len() {
+        let hex = value
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (value[i], hex) {
+            (b'+', _) => decoded.push(b' '),
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 2;
+            }
+            (byte, _) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[test]
+fn requests() {
+    let mut raw =
+        "POST /01 HTTP/1.1\r\nHost: localhost\r\nContent-Length: 10\r\n\r\ninput=1%0A2".as_bytes();
+    assert_eq!(
+        read_request(&mut raw).unwrap(),
+        Request {
+            method: "POST".to_string(),
+            path: "/01".to_string(),
+            body: b"input=1%0A".to_vec(),
+        }
+    );
+    assert!(read_request(&mut "nonsense\r\n\r\n".as_bytes()).is_err());
+    let long = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+    assert!(read_request(&mut long.as_bytes()).is_err());
+    let long = format!("GET / HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(MAX_LINE));
+    assert!(read_request(&mut long.as_bytes()).is_err());
+    assert_eq!(
+        form_value(b"day=1&input=a+b%3D%0Ac%ZZ", "input").unwrap(),
+        "a b=\nc%ZZ"
+    );
+    assert_eq!(form_value(b"inputs=1", "input"), None);
+    assert_eq!(
+        escape("<a href=\"x\">&</a>"),
+        "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+    );
+}
+
+#[test]
+fn pages() {
+    let request = |method: &str, path: &str, body: &str| Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        body: body.as_bytes().to_vec(),
+    };
+    let source = Source::Directory("no-such-directory".into());
+    let index = respond(&request("GET", "/", ""), &source);
+    assert_eq!(index.status, 200);
+    assert!(index
+        .body
+        .contains("<a href=\"/14\">Day 14: Regolith Reservoir</a>"));
+    let input = crate::day_01::EXAMPLE.replace('\n', "%0A");
+    let day = respond(
+        &request("POST", "/01", &format!("input={}", input)),
+        &source,
+    );
+    assert!(day.body.contains("<td>Part 1</td><td>24000</td>"));
+    assert!(day.body.contains("<td>Part 2</td><td>45000</td>"));
+    let missing = respond(&request("GET", "/01", ""), &source);
+    assert!(missing.body.contains("class=\"error\">no input for day 1"));
+    let crt = respond(&request("POST", "/10", "input=noop"), &source);
+    assert!(crt.body.contains("<h2>State</h2>"));
+    for path in ["/99", "/all", "/1-3", "/+1"] {
+        let status = respond(&request("GET", path, ""), &source).status;
+        assert_eq!(status, 404, "{}", path);
+    }
+    assert_eq!(respond(&request("DELETE", "/", ""), &source).status, 405);
+}
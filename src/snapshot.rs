@@ -0,0 +1,77 @@
+use std::{fs, path::PathBuf};
+
+// Set to anything to write the current renderings over the snapshots instead of checking them.
+const UPDATE: &str = "UPDATE_SNAPSHOTS";
+
+// Checks `actual` against `snapshots/<name>.txt` and panics with a line diff if they differ.
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("snapshots");
+    let path = directory.join(format!("{}.txt", name));
+    if std::env::var_os(UPDATE).is_some() {
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = match fs::read_to_string(&path) {
+        Ok(expected) => expected,
+        Err(err) => panic!(
+            "could not read {}: {}\nrun the tests with {}=1 to create it",
+            path.display(),
+            err,
+            UPDATE
+        ),
+    };
+    if expected != actual {
+        panic!(
+            "{} doesn't match, run the tests with {}=1 if the change is intended\n{}",
+            path.display(),
+            UPDATE,
+            diff(&expected, actual)
+        );
+    }
+}
+
+// Lines only in `expected` start with `-` and lines only in `actual` with `+`, around the longest
+// run of lines the two have in common.
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.split('\n').collect();
+    let actual: Vec<&str> = actual.split('\n').collect();
+    // The longest common subsequence of `expected[i..]` and `actual[j..]`.
+    let mut lengths = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lengths[i][j] = if expected[i] == actual[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut diff = String::new();
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            diff.push_str(&format!("  {}\n", expected[i]));
+            i += 1;
+            j += 1;
+        } else if i < expected.len()
+            && (j == actual.len() || lengths[i + 1][j] >= lengths[i][j + 1])
+        {
+            diff.push_str(&format!("- {}\n", expected[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+ {}\n", actual[j]));
+            j += 1;
+        }
+    }
+    diff
+}
+
+#[test]
+fn diffs() {
+    assert_eq!(diff("a\nb\nc", "a\nx\nc"), "  a\n- b\n+ x\n  c\n");
+    assert_eq!(diff("a\nb", "b\nc"), "- a\n  b\n+ c\n");
+    assert_eq!(diff("#.\n", "#.\n"), "  #.\n  \n");
+    // A missing final newline shows up as a changed empty last line.
+    assert_eq!(diff("a\n", "a"), "  a\n- \n");
+}
@@ -0,0 +1,328 @@
+use crate::{
+    animation::Animation,
+    memory::{self, Usage},
+    trace,
+};
+use advent_of_code_2022::{error, image::Image, normalize, rng::Rng};
+use anyhow::{anyhow, Error, Result};
+use std::{
+    fmt::Display,
+    hint::black_box,
+    io::BufRead,
+    ops::RangeInclusive,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+pub trait Solution {
+    type Input;
+    type Answer1: Display;
+    type Answer2: Display;
+
+    // Every input is run through `normalize::input` before it gets here, and lines read by
+    // `stream` through `normalize::line`, so none of them have to cope with `\r\n`, a byte order
+    // mark or stray trailing whitespace.
+    fn parse(&self, input: &str) -> Result<Self::Input>;
+    fn part1(&self, input: &Self::Input) -> Result<Self::Answer1>;
+    fn part2(&self, input: &Self::Input) -> Result<Self::Answer2>;
+    fn generate(&self, rng: &mut Rng, size: usize) -> String;
+
+    // Slow but obviously correct versions of each part, checked against the real ones by `aoc
+    // diff`. They should reject inputs that break the puzzle's promises rather than guess.
+    fn reference1(&self, input: &str) -> Result<Self::Answer1>;
+    fn reference2(&self, input: &str) -> Result<Self::Answer2>;
+
+    // A picture of the puzzle's final state, for `aoc run --image`. Most days don't have one.
+    fn render(&self, _input: &Self::Input) -> Result<Option<Image>> {
+        Ok(None)
+    }
+
+    // The final state as text, such as the lit pixels or the settled sand, for `aoc serve`. Most
+    // days don't have one.
+    fn state(&self, _input: &Self::Input) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    // Plays the simulation back step by step, for `aoc run --animate`. Returns false for days
+    // that don't have one.
+    fn animate(&self, _input: &Self::Input, _animation: &mut Animation) -> Result<bool> {
+        Ok(false)
+    }
+
+    // Solves both parts in a single pass over the input, holding on to as little of it as
+    // possible, for `aoc run --stream`. Only the line-oriented days have one.
+    fn stream(&self, _input: &mut dyn BufRead) -> Result<Option<(Self::Answer1, Self::Answer2)>> {
+        Ok(None)
+    }
+
+    // The puzzle's constants, such as how many knots are on the rope, by name and current value.
+    // `set` changes one of them, for `aoc run --set`.
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+
+    fn set(&mut self, name: &str, _value: &str) -> Result<()> {
+        Err(anyhow!("unknown parameter: {}", name))
+    }
+}
+
+pub trait Solver: Sync {
+    fn run(&self, input: &str, parts: &[Part]) -> Result<Vec<Outcome>>;
+    fn check(&self, input: &str) -> Vec<Error>;
+    fn time(&self, input: &str) -> Result<Timings>;
+    fn memory(&self, input: &str) -> Result<Memory>;
+    fn generate(&self, rng: &mut Rng, size: usize) -> String;
+    fn reference(&self, input: &str, part: Part) -> Result<String>;
+    fn render(&self, input: &str) -> Result<Option<Image>>;
+    fn state(&self, input: &str) -> Result<Option<String>>;
+    fn animate(&self, input: &str, animation: &mut Animation) -> Result<bool>;
+    fn stream(&self, input: &mut dyn BufRead) -> Result<Option<Vec<String>>>;
+    fn prepare(&self, input: &str) -> Result<Prepared<'_>>;
+    fn parameters(&self) -> Vec<(&'static str, String)>;
+    fn configure(&self, parameters: &[(String, String)]) -> Result<Box<dyn Solver>>;
+
+    fn solve(&self, input: &str, parts: &[Part]) -> Result<Vec<String>> {
+        self.run(input, parts)?
+            .into_iter()
+            .map(|outcome| outcome.answer)
+            .collect()
+    }
+}
+
+// A parsed input that can answer either part, from any thread.
+pub type Prepared<'a> = Box<dyn Fn(Part) -> Result<String> + Send + Sync + 'a>;
+
+// One of the examples from the puzzle text, with the answers the puzzle gives for it. Parts the
+// puzzle doesn't answer are left as `None`.
+#[derive(Debug, Clone, Copy)]
+pub struct Example {
+    pub name: &'static str,
+    pub input: &'static str,
+    // Changes the puzzle makes for the example, like day 15 looking at row 10.
+    pub parameters: &'static [(&'static str, &'static str)],
+    pub part1: Option<&'static str>,
+    pub part2: Option<&'static str>,
+}
+
+#[derive(Debug)]
+pub struct Outcome {
+    pub part: Part,
+    pub answer: Result<String>,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Timings {
+    pub parse: Duration,
+    pub part1: Duration,
+    pub part2: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Memory {
+    pub parse: Usage,
+    pub part1: Usage,
+    pub part2: Usage,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl<S> Solver for S
+where
+    S: Solution + Clone + Sync + 'static,
+    S::Input: Send + Sync,
+{
+    fn run(&self, input: &str, parts: &[Part]) -> Result<Vec<Outcome>> {
+        let input = &normalize::input(input);
+        trace::stage("parse");
+        let input = self.parse(input).map_err(|err| error::locate(err, input))?;
+        Ok(parts
+            .iter()
+            .map(|&part| {
+                trace::stage(part.stage());
+                let start = Instant::now();
+                let answer = match part {
+                    Part::One => self.part1(&input).map(|answer| answer.to_string()),
+                    Part::Two => self.part2(&input).map(|answer| answer.to_string()),
+                };
+                Outcome {
+                    part,
+                    answer,
+                    elapsed: start.elapsed(),
+                }
+            })
+            .collect())
+    }
+
+    fn check(&self, input: &str) -> Vec<Error> {
+        let input = &normalize::input(input);
+        match self.parse(input) {
+            Ok(_) => Vec::new(),
+            Err(err) => error::errors(error::locate(err, input)),
+        }
+    }
+
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        Solution::generate(self, rng, size)
+    }
+
+    fn reference(&self, input: &str, part: Part) -> Result<String> {
+        let input = &normalize::input(input);
+        match part {
+            Part::One => self.reference1(input).map(|answer| answer.to_string()),
+            Part::Two => self.reference2(input).map(|answer| answer.to_string()),
+        }
+    }
+
+    fn render(&self, input: &str) -> Result<Option<Image>> {
+        let input = &normalize::input(input);
+        trace::stage("parse");
+        let input = self.parse(input).map_err(|err| error::locate(err, input))?;
+        trace::stage("render");
+        Solution::render(self, &input)
+    }
+
+    fn state(&self, input: &str) -> Result<Option<String>> {
+        let input = &normalize::input(input);
+        trace::stage("parse");
+        let input = self.parse(input).map_err(|err| error::locate(err, input))?;
+        trace::stage("state");
+        Solution::state(self, &input)
+    }
+
+    fn animate(&self, input: &str, animation: &mut Animation) -> Result<bool> {
+        let input = &normalize::input(input);
+        trace::stage("parse");
+        let input = self.parse(input).map_err(|err| error::locate(err, input))?;
+        trace::stage("animate");
+        Solution::animate(self, &input, animation)
+    }
+
+    fn prepare(&self, input: &str) -> Result<Prepared<'_>> {
+        let input = &normalize::input(input);
+        let input = self.parse(input).map_err(|err| error::locate(err, input))?;
+        Ok(Box::new(move |part| match part {
+            Part::One => self.part1(&input).map(|answer| answer.to_string()),
+            Part::Two => self.part2(&input).map(|answer| answer.to_string()),
+        }))
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        Solution::parameters(self)
+    }
+
+    fn configure(&self, parameters: &[(String, String)]) -> Result<Box<dyn Solver>> {
+        let mut solution = self.clone();
+        for (name, value) in parameters {
+            solution.set(name, value)?;
+        }
+        Ok(Box::new(solution))
+    }
+
+    fn stream(&self, input: &mut dyn BufRead) -> Result<Option<Vec<String>>> {
+        normalize::skip_bom(input)?;
+        trace::stage("stream");
+        Ok(Solution::stream(self, input)?
+            .map(|(answer1, answer2)| vec![answer1.to_string(), answer2.to_string()]))
+    }
+
+    fn time(&self, input: &str) -> Result<Timings> {
+        let input = &normalize::input(input);
+        let start = Instant::now();
+        let parsed = self.parse(input).map_err(|err| error::locate(err, input))?;
+        let parse = start.elapsed();
+        let start = Instant::now();
+        let _ = black_box(self.part1(black_box(&parsed))?);
+        let part1 = start.elapsed();
+        let start = Instant::now();
+        let _ = black_box(self.part2(black_box(&parsed))?);
+        let part2 = start.elapsed();
+        Ok(Timings {
+            parse,
+            part1,
+            part2,
+        })
+    }
+
+    fn memory(&self, input: &str) -> Result<Memory> {
+        let input = &normalize::input(input);
+        let (parsed, parse) = memory::measure(|| self.parse(input));
+        let parsed = parsed.map_err(|err| error::locate(err, input))?;
+        let (answer, part1) = memory::measure(|| self.part1(&parsed).map(drop));
+        answer?;
+        let (answer, part2) = memory::measure(|| self.part2(&parsed).map(drop));
+        answer?;
+        Ok(Memory {
+            parse,
+            part1,
+            part2,
+        })
+    }
+}
+
+// Parses a parameter's value, which has to be in `range`.
+pub fn parameter<T: FromStr + PartialOrd + Display>(
+    name: &str,
+    value: &str,
+    range: RangeInclusive<T>,
+) -> Result<T> {
+    let value: T = value
+        .parse()
+        .map_err(|_| anyhow!("invalid value for {}: {}", name, value))?;
+    if range.contains(&value) {
+        Ok(value)
+    } else {
+        Err(anyhow!(
+            "{} must be between {} and {}",
+            name,
+            range.start(),
+            range.end()
+        ))
+    }
+}
+
+impl Part {
+    pub fn selected(part: Option<Part>) -> Vec<Part> {
+        if let Some(part) = part {
+            vec![part]
+        } else {
+            vec![Part::One, Part::Two]
+        }
+    }
+
+    // What the part is called in trace events.
+    pub fn stage(&self) -> &'static str {
+        match self {
+            Part::One => "part1",
+            Part::Two => "part2",
+        }
+    }
+
+    pub fn number(&self) -> u8 {
+        match self {
+            Part::One => 1,
+            Part::Two => 2,
+        }
+    }
+}
+
+impl FromStr for Part {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Part> {
+        match s {
+            "1" => Ok(Part::One),
+            "2" => Ok(Part::Two),
+            _ => Err(anyhow!("invalid part: {}", s)),
+        }
+    }
+}
+
+impl Display for Part {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Part {}", self.number())
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{error, normalize};
+use anyhow::{anyhow, Result};
+use std::io::BufRead;
+
+// Calls `f` with each line of `reader`, without its line ending or trailing whitespace, like
+// `normalize::input` would leave it. Blank lines at the end are dropped. One buffer is reused for
+// every line, so memory is bounded by the longest line rather than the whole input. Errors from
+// `f` are reported at their line.
+pub fn for_each_line(
+    reader: &mut dyn BufRead,
+    mut f: impl FnMut(&str) -> Result<()>,
+) -> Result<()> {
+    let mut buffer = String::new();
+    let mut number = 0;
+    // Blank lines are held back until there's a line after them.
+    let mut blank = 0;
+    loop {
+        buffer.clear();
+        if reader.read_line(&mut buffer)? == 0 {
+            return Ok(());
+        }
+        number += 1;
+        let line = normalize::line(&buffer);
+        if line.is_empty() {
+            blank += 1;
+            continue;
+        }
+        for number in number - blank..number {
+            f("").map_err(|err| error::locate_line(err, "", number))?;
+        }
+        blank = 0;
+        f(line).map_err(|err| error::locate_line(err, line, number))?;
+    }
+}
+
+// Calls `f` with each byte of `reader`, a buffer at a time, until it returns false.
+pub fn for_each_byte(reader: &mut dyn BufRead, mut f: impl FnMut(u8) -> bool) -> Result<()> {
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(());
+        }
+        let len = buffer.len();
+        for &byte in buffer {
+            if !f(byte) {
+                return Ok(());
+            }
+        }
+        reader.consume(len);
+    }
+}
+
+// Calls `f` with each character of `reader`, decoded from UTF-8 a byte at a time, until it returns
+// false.
+pub fn for_each_char(reader: &mut dyn BufRead, mut f: impl FnMut(char) -> bool) -> Result<()> {
+    let mut bytes = Vec::with_capacity(4);
+    let mut result = Ok(());
+    for_each_byte(reader, |byte| {
+        bytes.push(byte);
+        match std::str::from_utf8(&bytes) {
+            Ok(s) => {
+                let c = s.chars().next();
+                bytes.clear();
+                c.is_none_or(&mut f)
+            }
+            // The rest of the character is still to come.
+            Err(err) if err.error_len().is_none() => true,
+            Err(_) => {
+                result = Err(anyhow!("invalid UTF-8: {:02x?}", bytes));
+                false
+            }
+        }
+    })?;
+    if result.is_ok() && !bytes.is_empty() {
+        return Err(anyhow!("input ends partway through a character"));
+    }
+    result
+}
+
+#[test]
+fn lines() {
+    let mut lines = Vec::new();
+    for_each_line(&mut "a\r\nb \n\nc\n\n".as_bytes(), |line| {
+        lines.push(line.to_string());
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(lines, vec!["a", "b", "", "c"]);
+    let err = for_each_line(&mut "1\n2\nx\n".as_bytes(), |line| {
+        let _ = line.parse::<i64>().map_err(|err| error::at(line, err))?;
+        Ok(())
+    })
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "line 3, column 1: invalid digit found in string\n3 | x\n  | ^"
+    );
+    let err = for_each_line(&mut "1\n2\n".as_bytes(), |line| {
+        if line == "2" {
+            Err(anyhow::anyhow!("two"))
+        } else {
+            Ok(())
+        }
+    })
+    .unwrap_err();
+    assert_eq!(err.to_string(), "line 2: two");
+}
+
+#[test]
+fn bytes() {
+    let mut reader = std::io::BufReader::with_capacity(2, "abcdef".as_bytes());
+    let mut bytes = Vec::new();
+    for_each_byte(&mut reader, |byte| {
+        bytes.push(byte);
+        byte != b'd'
+    })
+    .unwrap();
+    assert_eq!(bytes, b"abcd");
+}
+
+#[test]
+fn chars() {
+    let mut reader = std::io::BufReader::with_capacity(1, "aé€b".as_bytes());
+    let mut chars = String::new();
+    for_each_char(&mut reader, |c| {
+        chars.push(c);
+        c != '€'
+    })
+    .unwrap();
+    assert_eq!(chars, "aé€");
+    assert!(for_each_char(&mut b"a\xffb".as_slice(), |_| true).is_err());
+    assert!(for_each_char(&mut b"a\xc3".as_slice(), |_| true).is_err());
+}
@@ -0,0 +1,167 @@
+use crate::error::{at, SpanContext};
+use anyhow::{Error, Result};
+use std::str::FromStr;
+
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    s: &'a str,
+    position: usize,
+}
+
+impl<'a> Tokens<'a> {
+    pub fn new(s: &'a str) -> Tokens<'a> {
+        Tokens { s, position: 0 }
+    }
+
+    pub fn next_str(&mut self) -> Result<&'a str> {
+        self.next()
+            .ok_or_else(|| at(self.rest(), "unexpected end of input"))
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.s[self.position..]
+    }
+
+    pub fn expect(&mut self, expected: &str) -> Result<()> {
+        for word in expected.split_ascii_whitespace() {
+            let actual = self
+                .next()
+                .ok_or_else(|| at(self.rest(), format!("expected {}, got end of input", word)))?;
+            if actual != word {
+                return Err(at(actual, format!("expected {}, got {}", word, actual)));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn parse<T>(&mut self) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Into<Error>,
+    {
+        let s = self.next_str()?;
+        parse(s)
+    }
+
+    pub fn parse_terminated<T>(&mut self, terminator: char) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Into<Error>,
+    {
+        let s = self.next_str()?;
+        let value = s
+            .strip_suffix(terminator)
+            .ok_or_else(|| at(s, format!("expected {} to end with '{}'", s, terminator)))?;
+        parse(value)
+    }
+
+    pub fn field<T>(&mut self, name: &str, terminator: impl Into<Option<char>>) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Into<Error>,
+    {
+        let s = self.next_str()?;
+        let value = s
+            .strip_prefix(name)
+            .and_then(|s| s.strip_prefix('='))
+            .ok_or_else(|| at(s, format!("expected {}=, got {}", name, s)))?;
+        if let Some(terminator) = terminator.into() {
+            let value = value
+                .strip_suffix(terminator)
+                .ok_or_else(|| at(s, format!("expected {} to end with '{}'", s, terminator)))?;
+            parse(value)
+        } else {
+            parse(value)
+        }
+    }
+
+    pub fn finish(&mut self) -> Result<()> {
+        if let Some(s) = self.next() {
+            Err(at(s, format!("unexpected trailing input: {}", s)))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let rest = &self.s[self.position..];
+        let start = rest.len()
+            - rest
+                .trim_start_matches(|c: char| c.is_ascii_whitespace())
+                .len();
+        let rest = &rest[start..];
+        if rest.is_empty() {
+            self.position = self.s.len();
+            return None;
+        }
+        let len = rest
+            .find(|c: char| c.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        self.position += start + len;
+        Some(&rest[..len])
+    }
+}
+
+fn parse<T>(s: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Into<Error>,
+{
+    s.parse().at(s)
+}
+
+#[test]
+fn words() {
+    let mut tokens = Tokens::new("  move 1 from\t2 to 1 ");
+    tokens.expect("move").unwrap();
+    assert_eq!(tokens.parse::<usize>().unwrap(), 1);
+    tokens.expect("from").unwrap();
+    assert_eq!(tokens.parse::<char>().unwrap(), '2');
+    assert!(tokens.expect("from").is_err());
+    assert_eq!(tokens.parse::<char>().unwrap(), '1');
+    assert!(tokens.next_str().is_err());
+    tokens.finish().unwrap();
+}
+
+#[test]
+fn fields() {
+    let mut tokens = Tokens::new("Sensor at x=-2, y=18: closest beacon is at x=10, y=16");
+    tokens.expect("Sensor at").unwrap();
+    assert_eq!(tokens.field::<i64>("x", ',').unwrap(), -2);
+    assert_eq!(tokens.field::<i64>("y", ':').unwrap(), 18);
+    tokens.expect("closest beacon is at").unwrap();
+    assert!(tokens.clone().field::<i64>("y", ',').is_err());
+    assert!(tokens.clone().field::<i64>("x", ':').is_err());
+    assert_eq!(tokens.field::<i64>("x", ',').unwrap(), 10);
+    assert_eq!(tokens.field::<i64>("y", None).unwrap(), 16);
+    tokens.finish().unwrap();
+}
+
+#[test]
+fn terminated() {
+    let mut tokens = Tokens::new("Monkey 0: x");
+    tokens.expect("Monkey").unwrap();
+    assert_eq!(tokens.parse_terminated::<u8>(':').unwrap(), 0);
+    assert!(tokens.finish().is_err());
+}
+
+#[test]
+fn spans() {
+    use crate::error::locate;
+    let input = "move 1 from 2 to";
+    let mut tokens = Tokens::new(input);
+    tokens.expect("move").unwrap();
+    let err = locate(tokens.expect("to").unwrap_err(), input);
+    assert!(err
+        .to_string()
+        .starts_with("line 1, column 6: expected to, got 1"));
+    tokens.expect("from 2 to").unwrap();
+    let err = locate(tokens.parse::<char>().unwrap_err(), input);
+    assert!(err
+        .to_string()
+        .starts_with("line 1, column 17: unexpected end of input"));
+}
@@ -0,0 +1,153 @@
+use anyhow::{anyhow, Error, Result};
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{LineWriter, Write},
+    path::PathBuf,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Mutex,
+    },
+};
+
+// How much `aoc run --trace` logs. Each level includes the ones before it.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Level {
+    // A handful of events per part, like the directory sizes part one adds up.
+    Summary = 1,
+    // An event for every step, like each crate move or each round's score.
+    Steps = 2,
+    // Every step along with the whole state it left behind, like the stacks after each move.
+    State = 3,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Options {
+    pub level: Level,
+    pub path: Option<PathBuf>,
+}
+
+// Zero while tracing is off, so solutions only pay for an atomic load per event.
+static LEVEL: AtomicU8 = AtomicU8::new(0);
+static TRACER: Mutex<Option<Tracer>> = Mutex::new(None);
+
+struct Tracer {
+    out: Box<dyn Write + Send>,
+    day: u8,
+    stage: &'static str,
+}
+
+// Sends events to the file in `options`, or to stderr, until the program exits.
+pub fn start(options: &Options) -> Result<()> {
+    let out: Box<dyn Write + Send> = match &options.path {
+        Some(path) => {
+            Box::new(LineWriter::new(File::create(path).map_err(|err| {
+                anyhow!("could not create {}: {}", path.display(), err)
+            })?))
+        }
+        None => Box::new(std::io::stderr()),
+    };
+    let mut tracer = TRACER
+        .lock()
+        .map_err(|_| anyhow!("the tracer panicked while logging"))?;
+    *tracer = Some(Tracer {
+        out,
+        day: 0,
+        stage: "",
+    });
+    LEVEL.store(options.level as u8, Ordering::Relaxed);
+    Ok(())
+}
+
+pub fn enabled(level: Level) -> bool {
+    LEVEL.load(Ordering::Relaxed) >= level as u8
+}
+
+// Sets the day the following events belong to.
+pub fn day(number: u8) {
+    with_tracer(|tracer| tracer.day = number);
+}
+
+// Sets what the day is doing, such as parsing or solving part one.
+pub fn stage(stage: &'static str) {
+    with_tracer(|tracer| tracer.stage = stage);
+}
+
+pub fn event(level: Level, name: &str, fields: &[(&str, &dyn Display)]) {
+    if enabled(level) {
+        with_tracer(|tracer| {
+            let line = line(tracer.day, tracer.stage, name, fields);
+            let _ = tracer.out.write_all(line.as_bytes());
+        });
+    }
+}
+
+fn with_tracer(f: impl FnOnce(&mut Tracer)) {
+    if LEVEL.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    if let Ok(mut tracer) = TRACER.lock() {
+        if let Some(tracer) = tracer.as_mut() {
+            f(tracer);
+        }
+    }
+}
+
+// One event per line, as space-separated key=value pairs.
+fn line(day: u8, stage: &str, name: &str, fields: &[(&str, &dyn Display)]) -> String {
+    let mut line = format!("day={:02} stage={} event={}", day, stage, name);
+    for (key, value) in fields {
+        line.push_str(&format!(" {}={}", key, quote(&value.to_string())));
+    }
+    line.push('\n');
+    line
+}
+
+// Values with spaces, quotes, equals signs or newlines in them are quoted and escaped so every
+// event stays on a single line.
+fn quote(value: &str) -> String {
+    if value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c == '"' || c == '=' || c == '\\')
+    {
+        format!("{:?}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+impl FromStr for Level {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Level> {
+        match s {
+            "summary" | "1" => Ok(Level::Summary),
+            "steps" | "2" => Ok(Level::Steps),
+            "state" | "3" => Ok(Level::State),
+            _ => Err(anyhow!("invalid trace level: {}", s)),
+        }
+    }
+}
+
+#[test]
+fn formatting() {
+    let stacks = "[Z] [M]\n[N]";
+    let line = line(5, "part1", "move", &[("count", &3), ("stacks", &stacks)]);
+    assert_eq!(
+        line,
+        "day=05 stage=part1 event=move count=3 stacks=\"[Z] [M]\\n[N]\"\n"
+    );
+    assert_eq!(quote("a=b"), "\"a=b\"");
+    assert_eq!(quote(""), "\"\"");
+    assert_eq!(quote("/a/e"), "/a/e");
+}
+
+#[test]
+fn levels() {
+    assert_eq!("steps".parse::<Level>().unwrap(), Level::Steps);
+    assert_eq!("3".parse::<Level>().unwrap(), Level::State);
+    assert!("loud".parse::<Level>().is_err());
+    assert!(Level::State > Level::Summary);
+    assert!(!enabled(Level::Summary));
+}